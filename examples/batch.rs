@@ -0,0 +1,77 @@
+//! Run a scenario headlessly for a fixed number of steps and write summary statistics.
+//!
+//! ```text
+//! cargo run --release --example batch -- --steps 10000 --out stats.json [--scene scene.json]
+//!     [--particles 10000] [--seed 42] [--lifetime 300] [--png final.png] [--backend gpu]
+//! ```
+//!
+//! Without `--out` the statistics are printed to stdout. `--backend gpu` steps and draws
+//! the particles in shaders, falling back to the CPU without a GPU or with a scene.
+
+use riscwaves::batch::render_png;
+use riscwaves::{BackendKind, Batch, Scene};
+use std::time::Instant;
+
+struct Args {
+    batch: Batch,
+    out: Option<String>,
+    png: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args { batch: Batch::default(), out: None, png: None };
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--steps" => args.batch.steps = value()?.parse().map_err(|err| format!("invalid --steps: {err}"))?,
+            "--particles" => {
+                args.batch.particles = value()?.parse().map_err(|err| format!("invalid --particles: {err}"))?
+            }
+            "--seed" => args.batch.seed = Some(value()?.parse().map_err(|err| format!("invalid --seed: {err}"))?),
+            "--lifetime" => {
+                args.batch.lifetime = value()?.parse().map_err(|err| format!("invalid --lifetime: {err}"))?
+            }
+            "--scene" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(&path).map_err(|err| format!("cannot read {path}: {err}"))?;
+                args.batch.scene = Some(Scene::from_json(&text).map_err(|err| format!("invalid scene {path}: {err}"))?);
+            }
+            "--backend" => {
+                args.batch.backend = match value()?.as_str() {
+                    "cpu" => BackendKind::Cpu,
+                    "gpu" => BackendKind::Gpu,
+                    other => return Err(format!("invalid --backend {other}, expected cpu or gpu")),
+                }
+            }
+            "--out" => args.out = Some(value()?),
+            "--png" => args.png = Some(value()?),
+            _ => return Err(format!("unknown argument {flag}")),
+        }
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let start = Instant::now();
+    let (mut particles, stats) = args.batch.run_on_backend();
+    eprintln!("{} steps in {:.2}s", stats.steps, start.elapsed().as_secs_f32());
+
+    let json = serde_json::to_string_pretty(&stats).map_err(|err| err.to_string())?;
+    match &args.out {
+        Some(path) => std::fs::write(path, json).map_err(|err| format!("cannot write {path}: {err}"))?,
+        None => println!("{json}"),
+    }
+    if let Some(path) = &args.png {
+        std::fs::write(path, render_png(particles.as_mut())).map_err(|err| format!("cannot write {path}: {err}"))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("batch: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,79 @@
+//! Check the simulation against a stored trajectory digest to catch physics regressions.
+//!
+//! ```text
+//! cargo run --release --example digest -- --baseline baseline.json --write   # record
+//! cargo run --release --example digest -- --baseline baseline.json           # compare
+//! ```
+//!
+//! `tests/digest.rs` checks the default run against `tests/digest_baseline.json`.
+//!
+//! Runs a fixed-seed headless batch (`--steps`, `--particles`, `--scene`, `--every`
+//! to tune) and exits with an error naming the first step that differs.
+
+use riscwaves::digest::TrajectoryDigest;
+use riscwaves::{Batch, Scene};
+
+struct Args {
+    batch: Batch,
+    every: usize,
+    baseline: String,
+    write: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let batch = Batch { steps: 2000, particles: 2000, seed: Some(1), ..Batch::default() };
+    let mut args = Args { batch, every: 100, baseline: String::new(), write: false };
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--steps" => args.batch.steps = value()?.parse().map_err(|err| format!("invalid --steps: {err}"))?,
+            "--particles" => {
+                args.batch.particles = value()?.parse().map_err(|err| format!("invalid --particles: {err}"))?
+            }
+            "--every" => args.every = value()?.parse().map_err(|err| format!("invalid --every: {err}"))?,
+            "--scene" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(&path).map_err(|err| format!("cannot read {path}: {err}"))?;
+                args.batch.scene = Some(Scene::from_json(&text).map_err(|err| format!("invalid scene {path}: {err}"))?);
+            }
+            "--baseline" => args.baseline = value()?,
+            "--write" => args.write = true,
+            _ => return Err(format!("unknown argument {flag}")),
+        }
+    }
+    if args.baseline.is_empty() {
+        return Err("--baseline is required".into());
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let mut digest = TrajectoryDigest::new(args.every);
+    args.batch.run_with(|particles| digest.record(particles));
+
+    let path = &args.baseline;
+    if args.write {
+        let json = serde_json::to_string_pretty(&digest).map_err(|err| err.to_string())?;
+        std::fs::write(path, json).map_err(|err| format!("cannot write {path}: {err}"))?;
+        eprintln!("wrote {} checkpoints to {path}", digest.checkpoints.len());
+        return Ok(());
+    }
+    let text = std::fs::read_to_string(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+    let baseline: TrajectoryDigest = serde_json::from_str(&text).map_err(|err| format!("invalid {path}: {err}"))?;
+    match digest.first_divergence(&baseline) {
+        Some(step) => Err(format!("trajectory diverges from {path} by step {step}")),
+        None => {
+            eprintln!("{} checkpoints match {path}", digest.checkpoints.len());
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("digest: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,72 @@
+//! Tune simulation parameters headlessly to maximize a batch metric.
+//!
+//! ```text
+//! cargo run --release --example optimize -- --config optimize.json --out best.json [--threads 8]
+//! ```
+//!
+//! with a config like
+//!
+//! ```json
+//! { "batch": { "steps": 2000, "scene": { "simulation": {}, "sinks": [...], "sources": [...] } },
+//!   "params": { "gravity.1": { "min": 0.0, "max": 1.0 }, "global_drag": { "min": 0.0, "max": 0.1 } },
+//!   "metric": "absorbed_rate", "rounds": 20, "population": 8 }
+//! ```
+//!
+//! The best simulation parameters are written as JSON, ready for a scene's `"simulation"`.
+
+use riscwaves::optimize::Optimizer;
+use std::time::Instant;
+
+struct Args {
+    config: String,
+    out: Option<String>,
+    threads: usize,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut args = Args { config: String::new(), out: None, threads };
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--config" => args.config = value()?,
+            "--out" => args.out = Some(value()?),
+            "--threads" => args.threads = value()?.parse().map_err(|err| format!("invalid --threads: {err}"))?,
+            _ => return Err(format!("unknown argument {flag}")),
+        }
+    }
+    if args.config.is_empty() {
+        return Err("--config is required".into());
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let text = std::fs::read_to_string(&args.config).map_err(|err| format!("cannot read {}: {err}", args.config))?;
+    let optimizer: Optimizer = serde_json::from_str(&text).map_err(|err| format!("invalid {}: {err}", args.config))?;
+
+    let start = Instant::now();
+    let best = optimizer.run(args.threads, |round, best| {
+        let values: Vec<String> =
+            optimizer.params.keys().zip(&best.values).map(|(name, value)| format!("{name}={value}")).collect();
+        eprintln!("round {round}: {} = {} at {}", optimizer.metric, best.score, values.join(", "));
+    })?;
+    eprintln!("done in {:.2}s", start.elapsed().as_secs_f32());
+
+    let params = optimizer.params_for(&best)?;
+    let json = serde_json::to_string_pretty(&params).map_err(|err| err.to_string())?;
+    match &args.out {
+        Some(path) => std::fs::write(path, json).map_err(|err| format!("cannot write {path}: {err}"))?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("optimize: {err}");
+        std::process::exit(1);
+    }
+}
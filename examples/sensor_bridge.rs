@@ -0,0 +1,157 @@
+//! Streams a local accelerometer in the riscwaves sensor wire format.
+//!
+//! Runs on the (RISC-V) Linux board the IMU is attached to and reads it through the
+//! kernel's IIO sysfs interface:
+//!
+//! ```text
+//! cargo run --release --example sensor_bridge -- --tcp 0.0.0.0:9000
+//! cargo run --release --example sensor_bridge -- --serial /dev/ttyS1 --rate 50
+//! ```
+//!
+//! `--device` selects the IIO device directory, by default the first one with
+//! `in_accel_x_raw`. Serial ports must be configured beforehand, e.g. `stty -F /dev/ttyS1 115200 raw`.
+
+use riscwaves::sensor::{encode, Packet};
+use std::io::{ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+
+struct Accelerometer {
+    dir: PathBuf,
+    scale: [f32; 3],
+    offset: [f32; 3],
+}
+
+impl Accelerometer {
+    fn open(dir: PathBuf) -> Result<Self, String> {
+        // per-axis attributes take precedence over the shared ones
+        let attr = |axis: char, name: &str, default: f32| {
+            read_value(&dir.join(format!("in_accel_{axis}_{name}")))
+                .or_else(|| read_value(&dir.join(format!("in_accel_{name}"))))
+                .unwrap_or(default)
+        };
+        let scale = ['x', 'y', 'z'].map(|axis| attr(axis, "scale", 1.0));
+        let offset = ['x', 'y', 'z'].map(|axis| attr(axis, "offset", 0.0));
+        let sensor = Self { dir, scale, offset };
+        sensor.read().map(|_| sensor)
+    }
+
+    /// Acceleration in m/s².
+    fn read(&self) -> Result<[f32; 3], String> {
+        let mut axes = [0.0; 3];
+        for (i, axis) in ['x', 'y', 'z'].into_iter().enumerate() {
+            let path = self.dir.join(format!("in_accel_{axis}_raw"));
+            let raw = read_value(&path).ok_or_else(|| format!("cannot read {}", path.display()))?;
+            axes[i] = (raw + self.offset[i]) * self.scale[i];
+        }
+        Ok(axes)
+    }
+}
+
+fn read_value(path: &Path) -> Option<f32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn find_device() -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(IIO_DEVICES).ok()?.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    dirs.sort();
+    dirs.into_iter().find(|dir| dir.join("in_accel_x_raw").exists())
+}
+
+enum Output {
+    /// Every connected client gets every packet.
+    Tcp { listener: TcpListener, clients: Vec<TcpStream> },
+    Serial(std::fs::File),
+}
+
+impl Output {
+    fn send(&mut self, frame: &[u8]) -> Result<(), String> {
+        match self {
+            Output::Tcp { listener, clients } => {
+                loop {
+                    match listener.accept() {
+                        Ok((client, addr)) => {
+                            eprintln!("client connected: {addr}");
+                            let _ = client.set_nodelay(true);
+                            clients.push(client);
+                        }
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                        Err(err) => return Err(format!("accept failed: {err}")),
+                    }
+                }
+                clients.retain_mut(|client| client.write_all(frame).is_ok());
+                Ok(())
+            }
+            Output::Serial(port) => port.write_all(frame).map_err(|err| format!("serial write failed: {err}")),
+        }
+    }
+}
+
+struct Args {
+    device: Option<PathBuf>,
+    tcp: Option<String>,
+    serial: Option<PathBuf>,
+    rate_hz: f32,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args { device: None, tcp: None, serial: None, rate_hz: 100.0 };
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--device" => args.device = Some(value()?.into()),
+            "--tcp" => args.tcp = Some(value()?),
+            "--serial" => args.serial = Some(value()?.into()),
+            "--rate" => args.rate_hz = value()?.parse().map_err(|err| format!("invalid --rate: {err}"))?,
+            _ => return Err(format!("unknown argument {flag}")),
+        }
+    }
+    if args.tcp.is_none() && args.serial.is_none() {
+        args.tcp = Some("0.0.0.0:9000".into());
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let dir = args.device.or_else(find_device).ok_or("no IIO accelerometer found")?;
+    let sensor = Accelerometer::open(dir.clone())?;
+    let mut output = match (&args.serial, &args.tcp) {
+        (Some(path), _) => {
+            let port = std::fs::OpenOptions::new().write(true).open(path);
+            Output::Serial(port.map_err(|err| format!("cannot open {}: {err}", path.display()))?)
+        }
+        (None, Some(addr)) => {
+            let listener = TcpListener::bind(addr).map_err(|err| format!("cannot listen on {addr}: {err}"))?;
+            listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+            Output::Tcp { listener, clients: Vec::new() }
+        }
+        (None, None) => unreachable!("tcp is the default output"),
+    };
+    eprintln!("streaming {} at {} Hz", dir.display(), args.rate_hz);
+
+    let period = Duration::from_secs_f32(1.0 / args.rate_hz.max(1.0));
+    let mut next = Instant::now();
+    let mut frame = Vec::new();
+    loop {
+        frame.clear();
+        // sampling time lets receivers with a synchronized clock measure transport latency
+        let micros = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros() as u64);
+        encode(&Packet::Time(micros), &mut frame);
+        encode(&Packet::Accel(sensor.read()?), &mut frame);
+        output.send(&frame)?;
+        next += period;
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("sensor_bridge: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,65 @@
+//! Run headless batches over a grid of simulation parameters and write a CSV of the outcomes.
+//!
+//! ```text
+//! cargo run --release --example sweep -- --config sweep.json --out results.csv [--threads 8]
+//! ```
+//!
+//! with a config like
+//!
+//! ```json
+//! { "batch": { "steps": 2000, "particles": 5000 },
+//!   "params": { "global_drag": { "start": 0.0, "end": 0.1, "steps": 10 },
+//!               "restitution": { "start": 0.5, "end": 1.0, "steps": 3 } } }
+//! ```
+
+use riscwaves::sweep::Sweep;
+use std::time::Instant;
+
+struct Args {
+    config: String,
+    out: Option<String>,
+    threads: usize,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut args = Args { config: String::new(), out: None, threads };
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--config" => args.config = value()?,
+            "--out" => args.out = Some(value()?),
+            "--threads" => args.threads = value()?.parse().map_err(|err| format!("invalid --threads: {err}"))?,
+            _ => return Err(format!("unknown argument {flag}")),
+        }
+    }
+    if args.config.is_empty() {
+        return Err("--config is required".into());
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let text = std::fs::read_to_string(&args.config).map_err(|err| format!("cannot read {}: {err}", args.config))?;
+    let sweep: Sweep = serde_json::from_str(&text).map_err(|err| format!("invalid {}: {err}", args.config))?;
+
+    let start = Instant::now();
+    let runs = sweep.run(args.threads)?;
+    eprintln!("{} runs on {} threads in {:.2}s", runs.len(), args.threads, start.elapsed().as_secs_f32());
+
+    let csv = sweep.to_csv(&runs);
+    match &args.out {
+        Some(path) => std::fs::write(path, csv).map_err(|err| format!("cannot write {path}: {err}"))?,
+        None => print!("{csv}"),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("sweep: {err}");
+        std::process::exit(1);
+    }
+}
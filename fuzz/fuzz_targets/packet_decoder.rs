@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use riscwaves::sensor::{encode, PacketDecoder, MAX_FRAME};
+
+fuzz_target!(|data: &[u8]| {
+    // Any input decodes without panicking and without unbounded buffering
+    let mut decoder = PacketDecoder::new();
+    let mut whole = Vec::new();
+    decoder.push(data, &mut whole);
+    assert!(decoder.buffered() <= MAX_FRAME);
+
+    // Chunking the stream does not change the result
+    let split = data.first().map_or(0, |&b| b as usize % (data.len() + 1));
+    let mut chunked_decoder = PacketDecoder::new();
+    let mut chunked = Vec::new();
+    chunked_decoder.push(&data[..split], &mut chunked);
+    chunked_decoder.push(&data[split..], &mut chunked);
+
+    // Compare encoded bytes, NaN payloads are not equal to themselves
+    let mut expected = Vec::new();
+    whole.iter().for_each(|packet| encode(packet, &mut expected));
+    let mut actual = Vec::new();
+    chunked.iter().for_each(|packet| encode(packet, &mut actual));
+    assert_eq!(expected, actual);
+
+    // Decoded packets round-trip through the encoder
+    let mut round_trip = Vec::new();
+    PacketDecoder::new().push(&expected, &mut round_trip);
+    let mut reencoded = Vec::new();
+    round_trip.iter().for_each(|packet| encode(packet, &mut reencoded));
+    assert_eq!(expected, reencoded);
+});
@@ -0,0 +1,126 @@
+use crate::arena::FrameArena;
+use crate::memory::vec_bytes;
+use crate::spatial::{SpatialGrid, GRID_CELL};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Radius of the seed points a cluster grows from.
+const SEED_RADIUS: f32 = 2.0;
+
+/// Diffusion-limited aggregation, see `Aggregate`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregationConfig {
+    /// Points the cluster grows from; without any (and without `floor`) the arena center.
+    pub seeds: Vec<Vec2>,
+    /// Let particles also freeze on the bottom edge, growing a forest upwards.
+    pub floor: bool,
+    /// Chance that a particle touching the cluster freezes in each step; lower values
+    /// let walkers reach deeper into the gaps, for denser clusters.
+    pub stickiness: f32,
+    /// Speed of the random walk added to the moving particles' velocity.
+    pub jitter: f32,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self { seeds: Vec::new(), floor: false, stickiness: 1.0, jitter: 0.5 }
+    }
+}
+
+/// Frozen cluster of a diffusion-limited aggregation: particles that touch it stop and
+/// become part of it, growing fractal branches. The frozen points are kept apart from
+/// the live particles, so they cost nothing to simulate and stay drawn.
+#[derive(Clone, Debug)]
+pub struct Aggregate {
+    pub config: AggregationConfig,
+    width: usize,
+    height: usize,
+    points: Vec<Vec2>,
+    radii: Vec<f32>,
+    max_radius: f32,
+    /// Frozen points bucketed by position, rebuilt by `refresh` after growth.
+    grid: SpatialGrid,
+    scratch: FrameArena<u32>,
+    stale: bool,
+}
+
+impl Aggregate {
+    /// Cluster of just the seeds of `config` in a `width` x `height` arena.
+    pub fn new(width: usize, height: usize, config: AggregationConfig) -> Self {
+        let mut aggregate = Self {
+            config,
+            width,
+            height,
+            points: Vec::new(),
+            radii: Vec::new(),
+            max_radius: 0.0,
+            grid: SpatialGrid::new(),
+            scratch: FrameArena::new(),
+            stale: true,
+        };
+        aggregate.clear();
+        aggregate
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.points) + vec_bytes(&self.radii) + self.grid.memory_bytes() + self.scratch.memory_bytes()
+    }
+
+    /// Frozen points in the order they joined, seeds first.
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    pub fn radii(&self) -> &[f32] {
+        &self.radii
+    }
+
+    /// Back to just the seeds.
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.radii.clear();
+        self.max_radius = 0.0;
+        let center = Vec2::new(self.width as f32, self.height as f32) * 0.5;
+        let seeds = if self.config.seeds.is_empty() && !self.config.floor {
+            vec![center]
+        } else {
+            self.config.seeds.clone()
+        };
+        for seed in seeds {
+            self.freeze(seed, SEED_RADIUS);
+        }
+        self.stale = true;
+    }
+
+    /// Add a frozen disc at `pos`.
+    pub fn freeze(&mut self, pos: Vec2, radius: f32) {
+        self.points.push(pos);
+        self.radii.push(radius);
+        self.max_radius = self.max_radius.max(radius);
+        self.stale = true;
+    }
+
+    /// Rebuild the neighbor grid if points were frozen since the last call; `touches`
+    /// only sees points frozen before it.
+    pub fn refresh(&mut self) {
+        if !self.stale {
+            return;
+        }
+        self.scratch.reset();
+        let cell = GRID_CELL.max(2.0 * self.max_radius);
+        self.grid.build(&self.points, cell, (self.width, self.height), [false; 2], &mut self.scratch);
+        self.stale = false;
+    }
+
+    /// Whether a disc at `pos` with `radius` touches the cluster (or the floor, if enabled).
+    pub fn touches(&self, pos: Vec2, radius: f32) -> bool {
+        if self.config.floor && pos.y + radius >= self.height as f32 {
+            return true;
+        }
+        self.grid.near(pos, radius + self.max_radius).any(|k| {
+            let reach = radius + self.radii[k];
+            self.points[k].distance_squared(pos) <= reach * reach
+        })
+    }
+}
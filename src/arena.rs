@@ -0,0 +1,66 @@
+use crate::memory::vec_bytes;
+use std::ops::Range;
+
+/// Bump allocator for per-step temporaries such as neighbor lists and bin cursors.
+///
+/// Allocations are ranges into one backing buffer that is reset, not freed, at the start
+/// of every step, so once it has grown to the steady-state size a step performs no heap
+/// allocations for its scratch data. That churn shows up as jitter on wasm.
+#[derive(Clone, Debug)]
+pub struct FrameArena<T> {
+    data: Vec<T>,
+    used: usize,
+}
+
+impl<T> Default for FrameArena<T> {
+    fn default() -> Self {
+        Self { data: Vec::new(), used: 0 }
+    }
+}
+
+impl<T: Copy> FrameArena<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve `len` elements set to `value`, valid until the next `reset`.
+    pub fn alloc(&mut self, len: usize, value: T) -> Range<usize> {
+        let range = self.used..self.used + len;
+        self.used = range.end;
+        if self.data.len() < self.used {
+            self.data.resize(self.used, value);
+        }
+        self.data[range.clone()].fill(value);
+        range
+    }
+
+    /// Reserve a copy of `values`, valid until the next `reset`.
+    pub fn alloc_slice(&mut self, values: &[T]) -> Range<usize> {
+        let range = self.used..self.used + values.len();
+        self.used = range.end;
+        if self.data.len() < self.used {
+            self.data.truncate(range.start);
+            self.data.extend_from_slice(values);
+        } else {
+            self.data[range.clone()].copy_from_slice(values);
+        }
+        range
+    }
+
+    pub fn get(&self, range: Range<usize>) -> &[T] {
+        &self.data[range]
+    }
+
+    pub fn get_mut(&mut self, range: Range<usize>) -> &mut [T] {
+        &mut self.data[range]
+    }
+
+    /// Release every allocation at once, keeping the backing buffer.
+    pub fn reset(&mut self) {
+        self.used = 0;
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.data)
+    }
+}
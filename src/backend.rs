@@ -0,0 +1,117 @@
+use crate::world::{ParticleSystem, Renderer, SimParams};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Core simulation operations, independent of where the particle state lives.
+///
+/// Code that only steps, spawns and reads the particles back (benchmarks, batch runs)
+/// can be written against this instead of `ParticleSystem`, and runs on either backend.
+pub trait SimBackend {
+    /// Advance the simulation by one `SimParams::dt` step.
+    fn step(&mut self);
+    /// Spawn a particle; ignored when the backend is full.
+    fn spawn(&mut self, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32);
+    /// Positions of the live particles. May read them back from the device first.
+    fn read_positions(&mut self) -> &[Vec2];
+    /// Velocities of the live particles, in the order of `read_positions`.
+    fn read_velocities(&mut self) -> &[Vec2];
+    /// Masses of the live particles, in the order of `read_positions`.
+    fn read_masses(&mut self) -> &[f32];
+    /// Indices into `read_positions` of the particles within `radius` of `point`, found
+    /// through the grid rebuilt at the end of every step, as `ParticleSystem::neighbors_within`.
+    fn read_neighbors(&mut self, point: Vec2, radius: f32) -> Vec<usize>;
+    /// RGBA frame of the arena with the default `Renderer`'s post-processing, e.g. for the
+    /// PNG export. The GPU backend draws on the device and reads the frame back for it.
+    fn render_frame(&mut self) -> Vec<u8>;
+    fn params(&self) -> &SimParams;
+    fn set_params(&mut self, params: SimParams);
+    fn live_count(&self) -> usize;
+    /// Simulation time elapsed so far.
+    fn time(&self) -> f32;
+}
+
+/// Where the particles are stored and stepped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackendKind {
+    /// `ParticleSystem`, with every feature of the app.
+    #[default]
+    Cpu,
+    /// `GpuSimulation`: compute shaders on a device of its own, for plain motion of many
+    /// particles. See its docs for the parameters it honors.
+    Gpu,
+}
+
+/// Backend of `kind` holding up to `capacity` particles in a `width` x `height` arena.
+///
+/// Without a usable GPU, e.g. in a headless build or on the web where the device
+/// cannot be waited for, the CPU backend is returned instead with a warning.
+pub fn create(kind: BackendKind, capacity: usize, width: usize, height: usize) -> Box<dyn SimBackend> {
+    match kind {
+        BackendKind::Cpu => Box::new(ParticleSystem::new(capacity, width, height)),
+        BackendKind::Gpu => match gpu_simulation(capacity, width, height) {
+            Ok(simulation) => simulation,
+            Err(err) => {
+                log::warn!("falling back to the CPU backend: {err}");
+                Box::new(ParticleSystem::new(capacity, width, height))
+            }
+        },
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn gpu_simulation(capacity: usize, width: usize, height: usize) -> Result<Box<dyn SimBackend>, String> {
+    Ok(Box::new(crate::gpu_sim::GpuSimulation::request(capacity, width, height)?))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn gpu_simulation(_capacity: usize, _width: usize, _height: usize) -> Result<Box<dyn SimBackend>, String> {
+    Err("the GPU backend needs a native build".into())
+}
+
+/// The CPU struct-of-arrays system.
+impl SimBackend for ParticleSystem {
+    fn step(&mut self) {
+        self.update();
+    }
+
+    fn spawn(&mut self, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32) {
+        ParticleSystem::spawn(self, pos, vel, mass, lifetime);
+    }
+
+    fn read_positions(&mut self) -> &[Vec2] {
+        self.positions()
+    }
+
+    fn read_velocities(&mut self) -> &[Vec2] {
+        self.velocities()
+    }
+
+    fn read_masses(&mut self) -> &[f32] {
+        self.masses()
+    }
+
+    fn read_neighbors(&mut self, point: Vec2, radius: f32) -> Vec<usize> {
+        self.neighbors_within(point, radius).collect()
+    }
+
+    fn render_frame(&mut self) -> Vec<u8> {
+        let (width, height) = self.size();
+        Renderer::new(width, height).render(self).pixels.to_vec()
+    }
+
+    fn params(&self) -> &SimParams {
+        &self.simulation
+    }
+
+    fn set_params(&mut self, params: SimParams) {
+        self.simulation = params;
+    }
+
+    fn live_count(&self) -> usize {
+        ParticleSystem::live_count(self)
+    }
+
+    fn time(&self) -> f32 {
+        ParticleSystem::time(self)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::backend::{self, BackendKind, SimBackend};
+use crate::emitter::RandomEmitter;
+use crate::png;
+use crate::scene::Scene;
+use crate::world::{ParticleSystem, SimParams, INFINITE_LIFETIME};
+use crate::{HEIGHT, INITIAL_PARTICLES, WIDTH};
+use glam::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// A non-interactive run of a fixed number of steps, for scripted experiments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Batch {
+    pub steps: usize,
+    /// Particles spawned up front; one more is emitted every step, as in the app.
+    pub particles: usize,
+    pub lifetime: f32,
+    /// Seed for the random spawns, overriding the scene's.
+    pub seed: Option<u64>,
+    /// Setup applied before spawning, e.g. loaded from a scene file.
+    pub scene: Option<Scene>,
+    /// Simulation parameters, overriding the scene's.
+    pub simulation: Option<SimParams>,
+    /// Where `run_stats` steps the particles. Scenes need the CPU, so batches with one
+    /// always run there.
+    pub backend: BackendKind,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self {
+            steps: 1000,
+            particles: INITIAL_PARTICLES,
+            lifetime: INFINITE_LIFETIME,
+            seed: None,
+            scene: None,
+            simulation: None,
+            backend: BackendKind::Cpu,
+        }
+    }
+}
+
+/// Summary of the particle state at the end of a batch.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BatchStats {
+    pub steps: usize,
+    pub time: f32,
+    pub seed: u64,
+    pub live: usize,
+    pub mean_speed: f32,
+    pub max_speed: f32,
+    pub kinetic_energy: f32,
+    pub centroid: Vec2,
+    /// Root mean square distance from the centroid.
+    pub spread: f32,
+    /// Particles absorbed by all sinks during the run.
+    pub absorbed: u64,
+    /// `absorbed` per unit of simulation time.
+    pub absorbed_rate: f32,
+}
+
+impl Batch {
+    /// Run to completion on the CPU and return the final system with its summary.
+    pub fn run(&self) -> (ParticleSystem, BatchStats) {
+        self.run_with(|_| ())
+    }
+
+    /// Like `run`, calling `on_step` with the system after every step.
+    pub fn run_with(&self, mut on_step: impl FnMut(&ParticleSystem)) -> (ParticleSystem, BatchStats) {
+        let mut particles = ParticleSystem::new(self.particles, WIDTH as usize, HEIGHT as usize);
+        if let Some(scene) = self.scene.clone() {
+            scene.apply(&mut particles);
+        }
+        if let Some(simulation) = self.simulation.clone() {
+            particles.simulation = simulation;
+        }
+        if let Some(seed) = self.seed {
+            particles.reseed(seed);
+        }
+        particles.add_emitter(RandomEmitter::with_lifetime(self.lifetime));
+        for _ in 0..self.particles {
+            particles.spawn_random(1.0, self.lifetime);
+        }
+        for _ in 0..self.steps {
+            particles.update();
+            on_step(&particles);
+        }
+        let mut stats = BatchStats::measure(&particles);
+        stats.steps = self.steps;
+        (particles, stats)
+    }
+
+    /// Run to completion on `backend` and return the summary.
+    pub fn run_stats(&self) -> BatchStats {
+        self.run_on_backend().1
+    }
+
+    /// Like `run_stats`, also returning the final state on the backend it ran on.
+    pub fn run_on_backend(&self) -> (Box<dyn SimBackend>, BatchStats) {
+        if self.backend == BackendKind::Cpu || self.scene.is_some() {
+            if self.backend != BackendKind::Cpu {
+                log::warn!("running the batch on the CPU, which its scene needs");
+            }
+            let (particles, stats) = self.run();
+            return (Box::new(particles), stats);
+        }
+        let mut particles = backend::create(self.backend, self.particles, WIDTH as usize, HEIGHT as usize);
+        if let Some(simulation) = self.simulation.clone() {
+            particles.set_params(simulation);
+        }
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        // same spread as `ParticleSystem::spawn_random` and the app's emitter
+        let mut spawn = |particles: &mut dyn SimBackend| {
+            let position = [rng.random::<f32>() * WIDTH as f32, rng.random::<f32>() * HEIGHT as f32];
+            let velocity = [(rng.random::<f32>() - 0.5) * 4.0, (rng.random::<f32>() - 0.5) * 4.0];
+            particles.spawn(position, velocity, 1.0, self.lifetime);
+        };
+        for _ in 0..self.particles {
+            spawn(particles.as_mut());
+        }
+        for _ in 0..self.steps {
+            spawn(particles.as_mut());
+            particles.step();
+        }
+        let mut stats = BatchStats::measure_backend(particles.as_mut());
+        stats.seed = seed;
+        stats.steps = self.steps;
+        (particles, stats)
+    }
+}
+
+impl BatchStats {
+    /// Value of the numeric field `name`, e.g. `"absorbed_rate"`.
+    pub fn metric(&self, name: &str) -> Option<f32> {
+        let json = serde_json::to_value(self).ok()?;
+        json.get(name)?.as_f64().map(|value| value as f32)
+    }
+
+    pub fn measure(particles: &ParticleSystem) -> Self {
+        let mut stats = Self { time: particles.time(), seed: particles.seed(), ..Self::default() };
+        stats.absorbed = (0..particles.sinks.len()).map(|i| particles.sink_stats(i).total).sum();
+        if stats.time > 0.0 {
+            stats.absorbed_rate = stats.absorbed as f32 / stats.time;
+        }
+        stats.measure_motion(particles.positions(), particles.velocities(), particles.masses());
+        stats
+    }
+
+    /// Summary of the particles of any backend, reading them back first. Backends have no
+    /// sinks, so nothing is absorbed, and the seed is left for the caller.
+    pub fn measure_backend(particles: &mut dyn SimBackend) -> Self {
+        let mut stats = Self { time: particles.time(), ..Self::default() };
+        let velocities = particles.read_velocities().to_vec();
+        let masses = particles.read_masses().to_vec();
+        stats.measure_motion(particles.read_positions(), &velocities, &masses);
+        stats
+    }
+
+    /// Fill in the counts, speeds and spread of the live particles.
+    fn measure_motion(&mut self, positions: &[Vec2], velocities: &[Vec2], masses: &[f32]) {
+        let live = positions.len();
+        self.live = live;
+        if live == 0 {
+            return;
+        }
+        let mut total_speed = 0.0;
+        for (&velocity, &mass) in velocities.iter().zip(masses) {
+            let speed = velocity.length();
+            total_speed += speed;
+            self.max_speed = self.max_speed.max(speed);
+            self.kinetic_energy += 0.5 * mass * speed * speed;
+        }
+        self.mean_speed = total_speed / live as f32;
+        self.centroid = positions.iter().sum::<Vec2>() / live as f32;
+        let variance = positions.iter().map(|p| p.distance_squared(self.centroid)).sum::<f32>() / live as f32;
+        self.spread = variance.sqrt();
+    }
+}
+
+/// Render the particles as the app would and encode the frame as a PNG.
+pub fn render_png(particles: &mut dyn SimBackend) -> Vec<u8> {
+    png::encode_rgb(WIDTH as usize, HEIGHT as usize, &particles.render_frame())
+}
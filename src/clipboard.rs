@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// System clipboard access: `arboard` on native, the async Clipboard API on the web.
+///
+/// Pasting may complete asynchronously, so the result is picked up with `take_pasted()`.
+#[derive(Default)]
+pub struct Clipboard {
+    pasted: Rc<RefCell<Option<String>>>,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy(&self, text: String) {
+        if let Err(err) = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(text)) {
+            log::warn!("clipboard copy failed: {err}");
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn request_paste(&self) {
+        match arboard::Clipboard::new().and_then(|mut cb| cb.get_text()) {
+            Ok(text) => *self.pasted.borrow_mut() = Some(text),
+            Err(err) => log::warn!("clipboard paste failed: {err}"),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn copy(&self, text: String) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let promise = window.navigator().clipboard().write_text(&text);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = wasm_bindgen_futures::JsFuture::from(promise).await {
+                log::warn!("clipboard copy failed: {:?}", err);
+            }
+        });
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn request_paste(&self) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let promise = window.navigator().clipboard().read_text();
+        let pasted = Rc::clone(&self.pasted);
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(promise).await {
+                Ok(text) => *pasted.borrow_mut() = text.as_string(),
+                Err(err) => log::warn!("clipboard paste failed: {:?}", err),
+            }
+        });
+    }
+
+    /// Text from the last completed paste request, if any.
+    pub fn take_pasted(&self) -> Option<String> {
+        self.pasted.borrow_mut().take()
+    }
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+#[cfg(target_arch = "wasm32")]
+use std::{cell::Cell, rc::Rc};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Most steps run for one presented frame, so a long stall does not freeze the app catching up.
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// Decides how many simulation steps run before each presented frame.
+///
+/// By default the simulation advances once per frame. With a fixed rate, steps are
+/// scheduled independently of the display: by a `setInterval` timer on the web (so a
+/// throttled `requestAnimationFrame` does not slow the simulation down) and by wall-clock
+/// time on native.
+pub enum StepClock {
+    PerFrame,
+    /// Steps from elapsed frame time on both native and the web, see `FixedTimestep`.
+    Accumulated { timestep: FixedTimestep, last_ms: Option<f64> },
+    #[cfg(not(target_arch = "wasm32"))]
+    WallClock { step_ms: f64, last_ms: Option<f64>, carry_ms: f64 },
+    #[cfg(target_arch = "wasm32")]
+    Interval { id: i32, pending: Rc<Cell<u32>>, _tick: Closure<dyn FnMut()> },
+}
+
+impl StepClock {
+    /// Run the steps of `timestep` for the time elapsed between calls to `take_steps`.
+    pub fn accumulated(timestep: FixedTimestep) -> Self {
+        Self::Accumulated { timestep, last_ms: None }
+    }
+
+    /// Schedule `hz` steps per second from wall-clock time.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn fixed_rate(hz: f64) -> Self {
+        Self::WallClock {
+            step_ms: 1000.0 / hz.max(1.0),
+            last_ms: None,
+            carry_ms: 0.0,
+        }
+    }
+
+    /// Schedule `hz` steps per second from a `setInterval` timer, separate from
+    /// `requestAnimationFrame`. Falls back to one step per frame without a window.
+    #[cfg(target_arch = "wasm32")]
+    pub fn fixed_rate(hz: f64) -> Self {
+        let pending = Rc::new(Cell::new(0u32));
+        let tick = {
+            let pending = Rc::clone(&pending);
+            Closure::<dyn FnMut()>::new(move || pending.set(pending.get().saturating_add(1)))
+        };
+        let id = web_sys::window().and_then(|window| {
+            window
+                .set_interval_with_callback_and_timeout_and_arguments_0(
+                    tick.as_ref().unchecked_ref(),
+                    (1000.0 / hz.max(1.0)) as i32,
+                )
+                .ok()
+        });
+        match id {
+            Some(id) => Self::Interval { id, pending, _tick: tick },
+            None => Self::PerFrame,
+        }
+    }
+
+    /// Number of steps due now; `now_ms` is the current wall-clock time.
+    pub fn take_steps(&mut self, now_ms: f64) -> u32 {
+        match self {
+            Self::PerFrame => 1,
+            Self::Accumulated { timestep, last_ms } => {
+                let elapsed = last_ms.replace(now_ms).map_or(0.0, |last| now_ms - last);
+                timestep.step(elapsed / 1000.0)
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::WallClock { step_ms, last_ms, carry_ms } => {
+                let elapsed = last_ms.replace(now_ms).map_or(0.0, |last| now_ms - last);
+                *carry_ms += elapsed;
+                let steps = (*carry_ms / *step_ms) as u32;
+                *carry_ms -= steps as f64 * *step_ms;
+                steps.min(MAX_STEPS_PER_FRAME)
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::Interval { pending, .. } => {
+                let _ = now_ms;
+                pending.replace(0).min(MAX_STEPS_PER_FRAME)
+            }
+        }
+    }
+
+    /// Fraction of a step left over after the last `take_steps`, for render interpolation.
+    /// Always 1 unless accumulating.
+    pub fn alpha(&self) -> f32 {
+        match self {
+            Self::Accumulated { timestep, .. } => timestep.alpha(),
+            _ => 1.0,
+        }
+    }
+}
+
+/// Settings of a `FixedTimestep`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FixedTimestepConfig {
+    /// Simulation steps per second, each advancing by `SimParams::dt`.
+    pub hz: f64,
+    /// Draw particles between their last two positions so motion stays smooth when the
+    /// display rate is not a multiple of `hz`.
+    pub interpolate: bool,
+}
+
+impl Default for FixedTimestepConfig {
+    fn default() -> Self {
+        Self { hz: 60.0, interpolate: true }
+    }
+}
+
+/// Accumulates real elapsed time and pays it out in whole steps of a fixed length, so
+/// the simulation speed does not depend on the display refresh rate.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    step_seconds: f64,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(hz: f64) -> Self {
+        Self { step_seconds: 1.0 / hz.max(1.0), accumulator: 0.0 }
+    }
+
+    /// Add `elapsed_seconds` and return how many steps are due. Time beyond
+    /// `MAX_STEPS_PER_FRAME` steps is dropped rather than caught up later.
+    pub fn step(&mut self, elapsed_seconds: f64) -> u32 {
+        self.accumulator += elapsed_seconds.max(0.0);
+        let steps = (self.accumulator / self.step_seconds) as u32;
+        if steps > MAX_STEPS_PER_FRAME {
+            self.accumulator = 0.0;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f64 * self.step_seconds;
+        steps
+    }
+
+    /// Fraction of the next step already accumulated, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step_seconds).clamp(0.0, 1.0) as f32
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for StepClock {
+    fn drop(&mut self) {
+        if let (Self::Interval { id, .. }, Some(window)) = (&*self, web_sys::window()) {
+            window.clear_interval_with_handle(*id);
+        }
+    }
+}
+
+/// Frame duration the simulation's `dt` is tuned for (60 Hz).
+pub const REFERENCE_FRAME_MS: f64 = 1000.0 / 60.0;
+
+/// Limits applied to measured frame times.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameTimerConfig {
+    /// Longer frames (pauses, tab switches, debugger breaks) count as this long.
+    pub max_frame_ms: f64,
+    /// Number of recent frames averaged; 1 disables smoothing.
+    pub smoothing_frames: usize,
+}
+
+impl Default for FrameTimerConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_ms: 100.0,
+            smoothing_frames: 4,
+        }
+    }
+}
+
+/// Measures real elapsed time per frame, clamping spikes and smoothing over a few frames.
+pub struct FrameTimer {
+    config: FrameTimerConfig,
+    last_ms: Option<f64>,
+    history: VecDeque<f64>,
+}
+
+impl FrameTimer {
+    pub fn new(config: FrameTimerConfig) -> Self {
+        Self {
+            config,
+            last_ms: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Register a frame at `now_ms` and return its clamped, smoothed duration in ms.
+    pub fn tick(&mut self, now_ms: f64) -> f64 {
+        let elapsed = self
+            .last_ms
+            .replace(now_ms)
+            .map_or(REFERENCE_FRAME_MS, |last| now_ms - last)
+            .clamp(0.0, self.config.max_frame_ms);
+        self.history.push_back(elapsed);
+        while self.history.len() > self.config.smoothing_frames.max(1) {
+            self.history.pop_front();
+        }
+        self.history.iter().sum::<f64>() / self.history.len() as f64
+    }
+
+    /// Like `tick`, as a multiple of `REFERENCE_FRAME_MS` for scaling `dt`.
+    pub fn tick_scale(&mut self, now_ms: f64) -> f32 {
+        (self.tick(now_ms) / REFERENCE_FRAME_MS) as f32
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+/// Color stops of the heat colormap, from cold (t = 0) to hot (t = 1).
+const HEAT: [[u8; 3]; 5] = [
+    [0x00, 0x00, 0x00],
+    [0x3b, 0x0f, 0x70],
+    [0xb5, 0x36, 0x79],
+    [0xfb, 0x88, 0x61],
+    [0xfc, 0xfd, 0xbf],
+];
+
+/// Cividis stops: blue to yellow, with the same lightness ramp for deuteranopes and protanopes.
+const CIVIDIS: [[u8; 3]; 5] = [
+    [0x00, 0x22, 0x4e],
+    [0x3b, 0x49, 0x6c],
+    [0x7c, 0x7b, 0x78],
+    [0xbc, 0xaf, 0x6f],
+    [0xfe, 0xe8, 0x38],
+];
+
+/// Viridis stops: purple through teal to yellow, avoiding red-green contrasts.
+const VIRIDIS: [[u8; 3]; 5] = [
+    [0x44, 0x01, 0x54],
+    [0x3b, 0x52, 0x8b],
+    [0x21, 0x91, 0x8c],
+    [0x5e, 0xc9, 0x62],
+    [0xfd, 0xe7, 0x25],
+];
+
+/// Colors for distinct items such as traced paths.
+const CATEGORIES: [[u8; 3]; 8] = [
+    [0xff, 0x55, 0x55],
+    [0x55, 0xff, 0x55],
+    [0x55, 0x99, 0xff],
+    [0xff, 0xdd, 0x44],
+    [0xff, 0x55, 0xff],
+    [0x44, 0xff, 0xee],
+    [0xff, 0x99, 0x33],
+    [0xdd, 0xdd, 0xdd],
+];
+
+/// Okabe-Ito categorical colors, distinguishable with deuteranopia and protanopia.
+/// Its black is replaced by light gray to show on the black background.
+const OKABE_ITO: [[u8; 3]; 8] = [
+    [0xe6, 0x9f, 0x00],
+    [0x56, 0xb4, 0xe9],
+    [0x00, 0x9e, 0x73],
+    [0xf0, 0xe4, 0x42],
+    [0x00, 0x72, 0xb2],
+    [0xd5, 0x5e, 0x00],
+    [0xcc, 0x79, 0xa7],
+    [0xdd, 0xdd, 0xdd],
+];
+
+/// Map `t` in `[0, 1]` to an opaque RGBA color by interpolating the heat stops.
+pub fn heat(t: f32) -> [u8; 4] {
+    Palette::Heat.sample(t)
+}
+
+/// Colormap for densities, impacts and collision glow, plus matching categorical colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Black through purple and orange to pale yellow.
+    #[default]
+    Heat,
+    /// Color-blind safe: cividis with Okabe-Ito categories.
+    Cividis,
+    /// Color-blind safe: viridis with Okabe-Ito categories.
+    Viridis,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Heat, Palette::Cividis, Palette::Viridis];
+
+    pub fn stops(&self) -> &'static [[u8; 3]; 5] {
+        match self {
+            Palette::Heat => &HEAT,
+            Palette::Cividis => &CIVIDIS,
+            Palette::Viridis => &VIRIDIS,
+        }
+    }
+
+    pub fn categories(&self) -> &'static [[u8; 3]; 8] {
+        match self {
+            Palette::Heat => &CATEGORIES,
+            Palette::Cividis | Palette::Viridis => &OKABE_ITO,
+        }
+    }
+
+    /// Whether the palette avoids red-green distinctions.
+    pub fn is_colorblind_safe(&self) -> bool {
+        *self != Palette::Heat
+    }
+
+    /// Map `t` in `[0, 1]` to an opaque RGBA color by interpolating the stops.
+    pub fn sample(&self, t: f32) -> [u8; 4] {
+        let stops = self.stops();
+        let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let i = (scaled as usize).min(stops.len() - 2);
+        let f = scaled - i as f32;
+        let (a, b) = (stops[i], stops[i + 1]);
+        let mix = |c: usize| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f) as u8;
+        [mix(0), mix(1), mix(2), 0xFF]
+    }
+
+    /// The palette after this one in `ALL`, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Colors of the scene elements drawn under the particles. Their alpha stays 0 so
+/// dilation leaves them alone.
+#[derive(Clone, Copy, Debug)]
+pub struct OverlayColors {
+    pub ghost: [u8; 4],
+    pub obstacle: [u8; 4],
+    pub portal_entry: [u8; 4],
+    pub portal_exit: [u8; 4],
+    pub gate: [u8; 4],
+    pub sink: [u8; 4],
+    pub source: [u8; 4],
+    pub preview: [u8; 4],
+    pub spring: [u8; 4],
+    /// Streamlines of the imported flow field.
+    pub flow: [u8; 4],
+}
+
+impl OverlayColors {
+    pub const STANDARD: OverlayColors = OverlayColors {
+        ghost: [0x30, 0x50, 0x70, 0x00],
+        obstacle: [0x40, 0x40, 0x48, 0x00],
+        portal_entry: [0x60, 0x38, 0x10, 0x00],
+        portal_exit: [0x10, 0x38, 0x60, 0x00],
+        gate: [0x70, 0x70, 0x20, 0x00],
+        sink: [0x50, 0x18, 0x18, 0x00],
+        source: [0x18, 0x50, 0x20, 0x00],
+        preview: [0x50, 0x70, 0x90, 0x00],
+        spring: [0x60, 0x60, 0x60, 0x00],
+        flow: [0x28, 0x48, 0x40, 0x00],
+    };
+
+    /// Like `STANDARD`, with sinks and sources told apart by blue and orange instead of
+    /// red and green.
+    pub const COLORBLIND: OverlayColors = OverlayColors {
+        sink: [0x5a, 0x2a, 0x00, 0x00],
+        source: [0x00, 0x30, 0x4c, 0x00],
+        ..OverlayColors::STANDARD
+    };
+
+    /// Bright Okabe-Ito hues, readable against dense particles and with color blindness.
+    pub const HIGH_CONTRAST: OverlayColors = OverlayColors {
+        ghost: [0x56, 0xb4, 0xe9, 0x00],
+        obstacle: [0xb0, 0xb0, 0xb0, 0x00],
+        portal_entry: [0xe6, 0x9f, 0x00, 0x00],
+        portal_exit: [0x00, 0x72, 0xb2, 0x00],
+        gate: [0xf0, 0xe4, 0x42, 0x00],
+        sink: [0xd5, 0x5e, 0x00, 0x00],
+        source: [0x00, 0x9e, 0x73, 0x00],
+        preview: [0xff, 0xff, 0xff, 0x00],
+        spring: [0xdd, 0xdd, 0xdd, 0x00],
+        flow: [0xcc, 0x79, 0xa7, 0x00],
+    };
+}
+
+/// Palette and overlay contrast used for drawing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    /// Draw obstacles, portals, gates and other overlays in bright colors.
+    pub high_contrast: bool,
+}
+
+impl Theme {
+    pub fn overlays(&self) -> &'static OverlayColors {
+        if self.high_contrast {
+            &OverlayColors::HIGH_CONTRAST
+        } else if self.palette.is_colorblind_safe() {
+            &OverlayColors::COLORBLIND
+        } else {
+            &OverlayColors::STANDARD
+        }
+    }
+}
+
+/// Color multiplied into particles over their life, from spawn (t = 0) to expiry (t = 1).
+///
+/// Stops are `(t, rgba)` pairs sorted by `t`; alpha fades the particle out, so e.g.
+/// white → orange → transparent makes sparks that cool and vanish.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ColorOverLifetime {
+    pub stops: Vec<(f32, [u8; 4])>,
+}
+
+impl ColorOverLifetime {
+    /// Interpolated color at `t`, held constant before the first and after the last stop.
+    pub fn sample(&self, t: f32) -> [u8; 4] {
+        let Some(&(first_t, first)) = self.stops.first() else {
+            return [0xFF; 4];
+        };
+        if t <= first_t {
+            return first;
+        }
+        for pair in self.stops.windows(2) {
+            let ((t0, a), (t1, b)) = (pair[0], pair[1]);
+            if t <= t1 {
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                let mix = |c: usize| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f).round() as u8;
+                return [mix(0), mix(1), mix(2), mix(3)];
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+/// Component-wise product of two colors.
+pub fn multiply(a: [u8; 4], b: [u8; 4]) -> [u8; 4] {
+    let mul = |c: usize| ((a[c] as u16 * b[c] as u16 + 127) / 255) as u8;
+    [mul(0), mul(1), mul(2), mul(3)]
+}
+
+/// `color` scaled by `intensity` and its own alpha, premultiplied as the frame expects.
+pub fn shade(color: [u8; 4], intensity: f32) -> [u8; 4] {
+    let k = intensity * color[3] as f32 / 255.0;
+    let scale = |c: u8| (c as f32 * k) as u8;
+    [scale(color[0]), scale(color[1]), scale(color[2]), (k * 255.0) as u8]
+}
@@ -0,0 +1,230 @@
+use crate::aggregation::AggregationConfig;
+use crate::clock::{FixedTimestepConfig, FrameTimerConfig};
+use crate::colormap::{ColorOverLifetime, Theme};
+use crate::decal::DecalConfig;
+use crate::distribution::VectorDistribution;
+use crate::emitter::RandomEmitter;
+use crate::fluid::FluidConfig;
+use crate::format::NumberFormat;
+use crate::memory::MemoryBudget;
+use crate::gpu_heatmap::GpuHeatmapConfig;
+use crate::group::ParticleGroup;
+use crate::input::InputMap;
+use crate::journal::DEFAULT_JOURNAL_ENTRIES;
+use crate::life::LifeConfig;
+use crate::ramp::RampConfig;
+use crate::reckoning::DeadReckoningConfig;
+use crate::tunnel::WindTunnel;
+use crate::vector_field::FlowFieldConfig;
+use crate::wetness::WetnessConfig;
+use crate::world::{EmitterId, FadeOut, PostProcess, SimParams, INFINITE_LIFETIME};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use winit::keyboard::KeyCode;
+
+/// Default config file location, relative to the working directory.
+/// Can be overridden with the `RISCWAVES_CONFIG` environment variable.
+pub const CONFIG_PATH: &str = "riscwaves.json";
+
+/// User configuration. Missing fields fall back to their defaults.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub input: InputMap,
+    /// Lifetime of spawned particles in simulation time; `None` means they never expire.
+    pub lifetime: Option<f32>,
+    /// Parameters of a second system shown side by side with the default one (A/B view).
+    pub compare: Option<SimParams>,
+    /// File a recorded replay is saved to, and loaded from at startup as the ghost baseline.
+    pub replay_path: Option<String>,
+    /// Ramp the particle count through levels and report frame stats per level.
+    pub ramp: Option<RampConfig>,
+    /// Simulation steps run before the first frame is presented.
+    pub warmup_steps: usize,
+    /// Fixed simulation rate in steps per second, independent of the display refresh.
+    /// `None` steps once per presented frame.
+    pub sim_rate_hz: Option<f64>,
+    /// Step at a fixed rate from the elapsed frame time, optionally interpolating the drawn
+    /// positions. Takes precedence over `sim_rate_hz`.
+    pub fixed_timestep: Option<FixedTimestepConfig>,
+    /// Scale `dt` by the measured frame time (clamped and smoothed) when stepping per frame.
+    pub frame_timer: Option<FrameTimerConfig>,
+    /// Seconds of history kept for rewinding; 0 disables it.
+    pub rewind_seconds: f32,
+    /// Scene file loaded at startup and saved when leaving obstacle edit mode.
+    pub scene_path: Option<String>,
+    /// Time steps to check the integrator at against analytic scenarios on startup;
+    /// empty skips the check.
+    pub validate_dts: Vec<f32>,
+    /// Address of a `sensor_bridge` whose accelerometer drives `SimParams::acceleration` (native only).
+    pub sensor_addr: Option<String>,
+    /// Measure sensor to display latency and show a rolling estimate in the window title.
+    pub measure_latency: bool,
+    /// Move the attractor by dead reckoning the sensor's motion instead of applying its
+    /// acceleration as a force.
+    pub dead_reckoning: Option<DeadReckoningConfig>,
+    /// File gesture macros are saved to when recorded, and loaded from at startup.
+    pub macros_path: Option<String>,
+    /// Keys playing the gesture macro of that name.
+    pub macro_keys: BTreeMap<String, KeyCode>,
+    /// Particle groups with their own gravity, drag and color, by emitter.
+    pub groups: Vec<ParticleGroup>,
+    /// Keys toggling the overrides of the group of that name.
+    pub group_keys: BTreeMap<String, KeyCode>,
+    /// Tint the particle glow by recent collision energy.
+    pub heat_glow: bool,
+    /// Leave slowly fading marks where particles hit hard.
+    pub decals: Option<DecalConfig>,
+    /// Let rain collect into a water line along the bottom edge.
+    pub wetness: Option<WetnessConfig>,
+    /// Simulate a coarse fluid that particles stir up and are carried along by.
+    pub fluid: Option<FluidConfig>,
+    /// Grow a fractal cluster from particles freezing where they touch it.
+    pub aggregation: Option<AggregationConfig>,
+    /// Run a game of life layer that emits, attracts and is seeded by particles.
+    pub life: Option<LifeConfig>,
+    /// Colormap (including color-blind safe ones) and high-contrast overlays.
+    pub theme: Theme,
+    /// Whether expiring particles fade, shrink, or both.
+    pub fade_out: FadeOut,
+    /// Post-processing chains for particles of specific emitters, rendered in their own layer.
+    pub post_routes: BTreeMap<EmitterId, Vec<PostProcess>>,
+    /// Colormap the heatmap draw mode in a shader instead of on the CPU.
+    pub gpu_heatmap: Option<GpuHeatmapConfig>,
+    /// Emitters spawning particles over time; `None` spawns one particle per step
+    /// with `lifetime` at random points.
+    pub emitters: Option<Vec<RandomEmitter>>,
+    /// Initial velocities of the particles scattered at startup, on reset and by ramps.
+    pub spawn_velocity: VectorDistribution,
+    /// Stream particles from the left edge to the right around an obstacle; replaces the
+    /// default emitter.
+    pub wind_tunnel: Option<WindTunnel>,
+    /// Externally computed vector field steering the particles.
+    pub flow_field: Option<FlowFieldConfig>,
+    /// Cap on memory use; lowers the particle capacity and history length to fit.
+    pub memory_budget: Option<MemoryBudget>,
+    /// Color gradients over the particles' lifetime for specific emitters.
+    pub color_gradients: BTreeMap<EmitterId, ColorOverLifetime>,
+    /// Recent input, sensor and parameter events kept for bug reports; 0 disables the journal.
+    pub journal_entries: usize,
+    /// Decimal and thousands separators of on-screen stats and CSV exports.
+    pub number_format: NumberFormat,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input: InputMap::default(),
+            lifetime: None,
+            compare: None,
+            replay_path: None,
+            ramp: None,
+            warmup_steps: 0,
+            sim_rate_hz: None,
+            fixed_timestep: None,
+            frame_timer: None,
+            rewind_seconds: 5.0,
+            scene_path: None,
+            validate_dts: Vec::new(),
+            sensor_addr: None,
+            measure_latency: false,
+            dead_reckoning: None,
+            macros_path: None,
+            macro_keys: BTreeMap::new(),
+            groups: Vec::new(),
+            group_keys: BTreeMap::new(),
+            heat_glow: false,
+            decals: None,
+            wetness: None,
+            fluid: None,
+            aggregation: None,
+            life: None,
+            theme: Theme::default(),
+            fade_out: FadeOut::default(),
+            post_routes: BTreeMap::new(),
+            gpu_heatmap: None,
+            emitters: None,
+            spawn_velocity: VectorDistribution::default(),
+            wind_tunnel: None,
+            flow_field: None,
+            memory_budget: None,
+            color_gradients: BTreeMap::new(),
+            journal_entries: DEFAULT_JOURNAL_ENTRIES,
+            number_format: NumberFormat::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file, falling back to defaults if it is missing or invalid.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        let path = std::env::var("RISCWAVES_CONFIG").unwrap_or_else(|_| CONFIG_PATH.to_string());
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::from_json(&text).unwrap_or_else(|err| {
+                log::warn!("invalid config {path}: {err}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// There is no file system on the web; use the preset of a shared link (see
+    /// `share::preset_query`), or the defaults.
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .and_then(|search| crate::share::preset_from_query(&search))
+            .unwrap_or_default()
+    }
+
+    /// The configured emitters, or the default one unless the wind tunnel feeds particles.
+    pub fn particle_emitters(&self) -> Vec<RandomEmitter> {
+        let lifetime = self.particle_lifetime();
+        match &self.emitters {
+            Some(emitters) => emitters.clone(),
+            None if self.wind_tunnel.is_some() => Vec::new(),
+            None => vec![RandomEmitter::with_lifetime(lifetime)],
+        }
+    }
+
+    /// Particles scattered over the arena at start and on reset; none in the wind tunnel,
+    /// which fills itself through its inflow.
+    pub fn initial_particles(&self) -> usize {
+        match self.wind_tunnel {
+            Some(_) => 0,
+            None => crate::INITIAL_PARTICLES,
+        }
+    }
+
+    /// Lifetime to spawn particles with.
+    pub fn particle_lifetime(&self) -> f32 {
+        self.lifetime.unwrap_or(INFINITE_LIFETIME)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+}
+
+/// Deserialize a finite number above zero, e.g. for periods and densities that are
+/// divided by or would make a loop spin.
+pub(crate) fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f32::deserialize(deserializer)?;
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom(format!("{value} is not a positive number")))
+    }
+}
+
+/// Deserialize a number in `[0, 1]`, e.g. a threshold on normalized brightness.
+pub(crate) fn unit_interval<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f32::deserialize(deserializer)?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom(format!("{value} is not between 0 and 1")))
+    }
+}
@@ -0,0 +1,71 @@
+use glam::Vec2;
+
+/// Spring stiffness of ropes and cloth, soft enough to stay stable at the default `dt`
+/// with eight springs per particle, so long ropes stretch visibly under gravity.
+pub const FABRIC_STIFFNESS: f32 = 0.25;
+
+/// Spring damping of ropes and cloth.
+pub const FABRIC_DAMPING: f32 = 0.05;
+
+/// Damped spring between two particles, see `ParticleSystem::add_spring`.
+///
+/// Ends are stable particle ids so the spring survives other particles dying; it is
+/// dropped once either end dies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub a: u64,
+    pub b: u64,
+    pub rest_length: f32,
+    /// Force per unit of stretch.
+    pub stiffness: f32,
+    /// Force per unit of relative speed along the spring.
+    pub damping: f32,
+    /// Indices the ends had last step, checked first when looking them up.
+    pub(crate) hints: (usize, usize),
+}
+
+impl Spring {
+    /// Force on end `a` for ends at `pa`/`pb` moving at `va`/`vb`; `b` gets the opposite.
+    pub fn force(&self, pa: Vec2, pb: Vec2, va: Vec2, vb: Vec2) -> Vec2 {
+        let offset = pb - pa;
+        let length = offset.length();
+        if length == 0.0 {
+            return Vec2::ZERO;
+        }
+        let direction = offset / length;
+        let stretch = length - self.rest_length;
+        let closing = (vb - va).dot(direction);
+        (self.stiffness * stretch + self.damping * closing) * direction
+    }
+}
+
+/// One end of a spring as seen from the particle at it, with the other end frozen at
+/// the start of the step, so integrators can evaluate the spring at their probe states.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SpringLink {
+    pub spring: Spring,
+    /// Whether the particle is end `a`.
+    pub at_a: bool,
+    pub other_position: Vec2,
+    pub other_velocity: Vec2,
+}
+
+impl SpringLink {
+    /// Force on the particle when it is at `pos` moving at `vel`.
+    pub fn force(&self, pos: Vec2, vel: Vec2) -> Vec2 {
+        if self.at_a {
+            self.spring.force(pos, self.other_position, vel, self.other_velocity)
+        } else {
+            -self.spring.force(self.other_position, pos, self.other_velocity, vel)
+        }
+    }
+}
+
+/// Holds a particle in place, see `ParticleSystem::pin`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pin {
+    pub particle: u64,
+    pub anchor: Vec2,
+    /// Index the particle had last step, checked first when looking it up.
+    pub(crate) hint: usize,
+}
@@ -0,0 +1,104 @@
+use crate::memory::vec_bytes;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Coverage below which a decal pixel is cleared, so faded decals stop costing time.
+const MIN_COVERAGE: f32 = 1.0 / 255.0;
+
+/// Look of the marks strong impacts leave on the background.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecalConfig {
+    /// Normal impact speed from which a hit leaves a mark; must be positive.
+    #[serde(deserialize_with = "crate::config::positive")]
+    pub min_speed: f32,
+    /// Radius in pixels of the mark of a hit at `min_speed`; faster hits leave larger ones.
+    pub size: f32,
+    /// Simulation time for a mark to fade to half its strength.
+    pub half_life: f32,
+    /// Color of a fully covered pixel, e.g. dark brown for scorch marks or blue for splashes.
+    pub color: [u8; 3],
+}
+
+impl Default for DecalConfig {
+    fn default() -> Self {
+        Self { min_speed: 4.0, size: 3.0, half_life: 600.0, color: [0x58, 0x38, 0x20] }
+    }
+}
+
+/// Per-pixel coverage of impact marks, accumulated over time and slowly fading.
+#[derive(Clone, Debug)]
+pub struct DecalLayer {
+    pub config: DecalConfig,
+    width: usize,
+    height: usize,
+    coverage: Vec<f32>,
+    /// Whether any pixel is covered, so empty layers skip fading and drawing.
+    marked: bool,
+}
+
+impl DecalLayer {
+    pub fn new(width: usize, height: usize, config: DecalConfig) -> Self {
+        Self { config, width, height, coverage: vec![0.0; width * height], marked: false }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.coverage)
+    }
+
+    /// Stamp a soft round mark at `pos` for a hit at normal `speed`, if it is fast enough.
+    /// Nothing is stamped while `min_speed` is not positive.
+    pub fn stamp(&mut self, pos: Vec2, speed: f32) {
+        if self.config.min_speed <= 0.0 || speed < self.config.min_speed {
+            return;
+        }
+        let radius = self.config.size * (speed / self.config.min_speed).sqrt();
+        let min = (pos - radius).max(Vec2::ZERO);
+        let max = (pos + radius).min(Vec2::new(self.width as f32 - 1.0, self.height as f32 - 1.0));
+        for y in min.y as usize..=max.y.max(0.0) as usize {
+            for x in min.x as usize..=max.x.max(0.0) as usize {
+                let distance = Vec2::new(x as f32 + 0.5, y as f32 + 0.5).distance(pos);
+                if distance < radius {
+                    let c = &mut self.coverage[y * self.width + x];
+                    *c = (*c + 1.0 - distance / radius).min(1.0);
+                    self.marked = true;
+                }
+            }
+        }
+    }
+
+    /// Let the marks fade for `dt` of simulation time.
+    pub fn fade(&mut self, dt: f32) {
+        if !self.marked {
+            return;
+        }
+        let factor = 0.5f32.powf(dt / self.config.half_life.max(f32::EPSILON));
+        self.marked = false;
+        for c in &mut self.coverage {
+            *c *= factor;
+            if *c < MIN_COVERAGE {
+                *c = 0.0;
+            } else {
+                self.marked = true;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.coverage.fill(0.0);
+        self.marked = false;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.marked
+    }
+
+    /// Coverage of pixel (`x`, `y`) in `[0, 1]`.
+    pub fn coverage_at(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.coverage[y * self.width + x]
+        } else {
+            0.0
+        }
+    }
+}
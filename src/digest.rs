@@ -0,0 +1,67 @@
+use crate::world::ParticleSystem;
+use serde::{Deserialize, Serialize};
+
+/// Rolling hash of the particle state, checkpointed every `every` steps.
+///
+/// Two runs with the same setup and seed produce the same checkpoints bit for bit, so
+/// comparing against a stored baseline pins down the first step where the physics
+/// changed, at a fraction of the cost of comparing rendered frames.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrajectoryDigest {
+    pub every: usize,
+    pub checkpoints: Vec<u64>,
+    #[serde(skip)]
+    step: usize,
+    #[serde(skip)]
+    hash: u64,
+}
+
+impl TrajectoryDigest {
+    pub fn new(every: usize) -> Self {
+        Self { every: every.max(1), checkpoints: Vec::new(), step: 0, hash: FNV_OFFSET }
+    }
+
+    /// Fold in the state after one simulation step.
+    pub fn record(&mut self, particles: &ParticleSystem) {
+        self.step += 1;
+        if !self.step.is_multiple_of(self.every) {
+            return;
+        }
+        let mut hash = self.hash;
+        hash = fnv1a(hash, &(particles.live_count() as u64).to_le_bytes());
+        for (p, v) in particles.positions().iter().zip(particles.velocities()) {
+            for value in [p.x, p.y, v.x, v.y] {
+                hash = fnv1a(hash, &value.to_bits().to_le_bytes());
+            }
+        }
+        self.hash = hash;
+        self.checkpoints.push(hash);
+    }
+
+    /// Step of the first checkpoint differing from `baseline`, or of the first missing
+    /// one if either run is shorter. `None` if they match.
+    pub fn first_divergence(&self, baseline: &TrajectoryDigest) -> Option<usize> {
+        if self.every != baseline.every {
+            return Some(0);
+        }
+        let mismatch = self.checkpoints.iter().zip(&baseline.checkpoints).position(|(a, b)| a != b);
+        let shorter = self.checkpoints.len().min(baseline.checkpoints.len());
+        let index = match mismatch {
+            Some(index) => index,
+            None if self.checkpoints.len() != baseline.checkpoints.len() => shorter,
+            None => return None,
+        };
+        Some((index + 1) * self.every)
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
@@ -0,0 +1,81 @@
+use glam::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Random distribution of a scalar spawn parameter such as mass or lifetime.
+///
+/// Written as `[min, max]` for a uniform range, `{ "mean": 1.0, "sigma": 0.2 }` for a
+/// normal distribution, or a plain number for a constant.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Distribution {
+    Constant(f32),
+    Uniform(f32, f32),
+    Normal { mean: f32, sigma: f32 },
+}
+
+impl Distribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> f32 {
+        match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform(min, max) => uniform(rng, min, max),
+            Distribution::Normal { mean, sigma } => mean + sigma * standard_normal(rng),
+        }
+    }
+}
+
+/// Random distribution of a vector spawn parameter such as the initial velocity.
+///
+/// Written as `[[x0, y0], [x1, y1]]` for a uniform box between two corners,
+/// `{ "mean": [0, 0], "sigma": [1, 1] }` for independent normals per axis, or
+/// `{ "center": [0, 0], "inner": 1, "outer": 2 }` for a uniform annulus, which is a
+/// ring when `inner == outer` and a disc when `inner` is 0.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VectorDistribution {
+    Box(Vec2, Vec2),
+    Normal { mean: Vec2, sigma: Vec2 },
+    Annulus { center: Vec2, inner: f32, outer: f32 },
+}
+
+impl Default for VectorDistribution {
+    /// Uniform in `[-2, 2]` on both axes, the spread of randomly spawned particles.
+    fn default() -> Self {
+        VectorDistribution::Box(Vec2::splat(-2.0), Vec2::splat(2.0))
+    }
+}
+
+impl VectorDistribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> Vec2 {
+        match *self {
+            VectorDistribution::Box(min, max) => Vec2::new(uniform(rng, min.x, max.x), uniform(rng, min.y, max.y)),
+            VectorDistribution::Normal { mean, sigma } => {
+                mean + sigma * Vec2::new(standard_normal(rng), standard_normal(rng))
+            }
+            VectorDistribution::Annulus { center, inner, outer } => {
+                // uniform over the area: the squared radius is uniform
+                let (inner_sq, outer_sq) = (inner * inner, outer * outer);
+                let radius = uniform(rng, inner_sq.min(outer_sq), inner_sq.max(outer_sq)).sqrt();
+                let angle = rng.random::<f32>() * std::f32::consts::TAU;
+                center + Vec2::from_angle(angle) * radius
+            }
+        }
+    }
+}
+
+/// Uniform sample from `[min, max]`; `min` for empty or infinite ranges.
+pub fn uniform(rng: &mut impl Rng, min: f32, max: f32) -> f32 {
+    if min < max && (max - min).is_finite() {
+        rng.random_range(min..=max)
+    } else {
+        min
+    }
+}
+
+/// Sample of the standard normal distribution (Box-Muller).
+pub fn standard_normal(rng: &mut impl Rng) -> f32 {
+    // 1 - u keeps the logarithm finite
+    let u = 1.0 - rng.random::<f32>();
+    let v = rng.random::<f32>();
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
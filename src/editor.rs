@@ -0,0 +1,123 @@
+use crate::obstacle::Shape;
+use crate::sdf::WALL_BRUSH;
+use crate::world::ParticleSystem;
+use glam::Vec2;
+
+/// Mouse editing of obstacles: drag on empty space to draw a rectangle (or a circle),
+/// drag an obstacle to move it, delete the obstacle under the cursor.
+/// With the wall tool, drags sketch freehand walls and delete erases them.
+#[derive(Default)]
+pub struct ObstacleEditor {
+    pub active: bool,
+    pub tool: EditTool,
+    drag: Option<Drag>,
+    walls_changed: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditTool {
+    #[default]
+    Shapes,
+    /// Freehand walls painted into `ParticleSystem::wall_sdf`.
+    Walls,
+}
+
+enum Drag {
+    Drawing { start: Vec2, circle: bool },
+    Moving { index: usize, last: Vec2 },
+    Sketching { last: Vec2 },
+}
+
+impl ObstacleEditor {
+    /// Primary button pressed at `pos`.
+    pub fn press(&mut self, particles: &mut ParticleSystem, pos: Vec2, circle: bool) {
+        if self.tool == EditTool::Walls {
+            particles.wall_sdf.paint_segment(pos, pos, WALL_BRUSH, true);
+            self.walls_changed = true;
+            self.drag = Some(Drag::Sketching { last: pos });
+            return;
+        }
+        self.drag = Some(match topmost_at(&particles.obstacles, pos) {
+            Some(index) => Drag::Moving { index, last: pos },
+            None => Drag::Drawing { start: pos, circle },
+        });
+    }
+
+    /// Cursor moved to `pos`; moves the dragged obstacle or extends the wall stroke.
+    pub fn drag(&mut self, particles: &mut ParticleSystem, pos: Vec2) {
+        match &mut self.drag {
+            Some(Drag::Moving { index, last }) => {
+                if let Some(shape) = particles.obstacles.get_mut(*index) {
+                    shape.translate(pos - *last);
+                }
+                *last = pos;
+            }
+            Some(Drag::Sketching { last }) if *last != pos => {
+                particles.wall_sdf.paint_segment(*last, pos, WALL_BRUSH, true);
+                self.walls_changed = true;
+                *last = pos;
+            }
+            _ => (),
+        }
+    }
+
+    /// Primary button released at `pos`; finishes a drawn shape.
+    pub fn release(&mut self, particles: &mut ParticleSystem, pos: Vec2) {
+        if let Some(Drag::Drawing { .. }) = self.drag {
+            if let Some(shape) = self.preview(pos) {
+                particles.obstacles.push(shape);
+            }
+        }
+        self.drag = None;
+    }
+
+    /// Remove the topmost obstacle under `pos`, or erase walls around it with the wall tool.
+    pub fn delete_at(&mut self, particles: &mut ParticleSystem, pos: Vec2) {
+        if self.tool == EditTool::Walls {
+            particles.wall_sdf.paint_segment(pos, pos, 2.0 * WALL_BRUSH, false);
+            self.walls_changed = true;
+        } else if let Some(index) = topmost_at(&particles.obstacles, pos) {
+            particles.obstacles.remove(index);
+            self.drag = None;
+        }
+    }
+
+    pub fn toggle_tool(&mut self) {
+        self.tool = match self.tool {
+            EditTool::Shapes => EditTool::Walls,
+            EditTool::Walls => EditTool::Shapes,
+        };
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Whether walls were painted or erased since the last call.
+    pub fn take_walls_changed(&mut self) -> bool {
+        std::mem::take(&mut self.walls_changed)
+    }
+
+    /// Shape being drawn if released at `pos`; tiny shapes are discarded.
+    pub fn preview(&self, pos: Vec2) -> Option<Shape> {
+        let Some(Drag::Drawing { start, circle }) = self.drag else {
+            return None;
+        };
+        let shape = if circle {
+            Shape::Circle { center: start, radius: start.distance(pos) }
+        } else {
+            Shape::rect(start, pos)
+        };
+        let size = match shape {
+            Shape::Circle { radius, .. } => radius,
+            Shape::Rect { min, max } => (max - min).min_element(),
+            Shape::Segment { start, end } => start.distance(end),
+        };
+        (size >= 2.0).then_some(shape)
+    }
+}
+
+fn topmost_at(obstacles: &[Shape], pos: Vec2) -> Option<usize> {
+    obstacles.iter().rposition(|shape| shape.contains(pos))
+}
@@ -0,0 +1,185 @@
+use crate::distribution::{uniform, Distribution, VectorDistribution};
+use crate::world::{EmitterId, ParticleSystem, DEFAULT_EMITTER, INFINITE_LIFETIME};
+use glam::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Smallest mass a normal distribution can produce, so forces stay finite.
+pub(crate) const MIN_MASS: f32 = 1e-3;
+
+/// Spawns particles over time; added with `ParticleSystem::add_emitter` and run at
+/// the start of every `update`.
+pub trait Emitter {
+    /// Spawn the particles due after `dt` of simulation time.
+    fn emit(&mut self, dt: f32, particles: &mut ParticleSystem);
+}
+
+/// Where an emitter places new particles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum EmitterShape {
+    /// Uniformly random points across the whole arena.
+    #[default]
+    Arena,
+    Point(Vec2),
+    /// Uniformly random points along the segment.
+    Line { start: Vec2, end: Vec2 },
+    /// Uniformly random points on the circle.
+    Ring { center: Vec2, radius: f32 },
+    /// Particles leave `position` at `speed` within a cone `spread` degrees wide centered
+    /// on `direction` (half of it either side), overriding the emitter's velocity.
+    Cone { position: Vec2, direction: Vec2, spread: f32, speed: (f32, f32) },
+    /// Points drawn from a distribution, e.g. a normal cluster or a filled disc.
+    Scatter(VectorDistribution),
+}
+
+/// Emits particles at random points of its `shape`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RandomEmitter {
+    /// Owner of the spawned particles, see `ParticleSystem::emitter_stats`.
+    pub id: EmitterId,
+    pub shape: EmitterShape,
+    /// Particles per unit of simulation time.
+    pub rate: f32,
+    /// Distribution of the initial velocities.
+    pub velocity: VectorDistribution,
+    /// Distribution of the masses; samples are kept positive.
+    pub mass: Distribution,
+    /// Left out when saved if particles never expire, since JSON has no infinity.
+    #[serde(skip_serializing_if = "never_expires")]
+    pub lifetime: Distribution,
+    /// Distribution of the radii; `None` derives them from the mass and density.
+    pub radius: Option<Distribution>,
+    /// Distribution of the drag multipliers, see `ParticleSystem::set_drag`; `None` keeps `1.0`.
+    pub drag: Option<Distribution>,
+    /// RGBA color of the spawned particles.
+    pub color: [u8; 4],
+    /// Keep this many of the emitter's particles alive, replacing expired ones right
+    /// away, instead of emitting at `rate` or on `schedule`.
+    pub target_count: Option<usize>,
+    /// Emit in bursts on a schedule instead of continuously at `rate`.
+    pub schedule: Option<EmissionSchedule>,
+    /// Fractional particles owed from previous steps.
+    #[serde(skip)]
+    carry: f32,
+    /// Simulation time since the emitter started, for `schedule`.
+    #[serde(skip)]
+    elapsed: f32,
+}
+
+/// When a scheduled emitter fires its bursts, in simulation time since it started.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EmissionSchedule {
+    /// `count` particles immediately and then every `interval`.
+    Every { count: usize, interval: f32 },
+    /// `count` particles once, at time `at`.
+    Once { count: usize, at: f32 },
+}
+
+impl Default for RandomEmitter {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_EMITTER,
+            shape: EmitterShape::Arena,
+            rate: 1.0,
+            velocity: VectorDistribution::default(),
+            mass: Distribution::Constant(1.0),
+            lifetime: Distribution::Constant(INFINITE_LIFETIME),
+            radius: None,
+            drag: None,
+            color: [0xFF; 4],
+            target_count: None,
+            schedule: None,
+            carry: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl RandomEmitter {
+    /// One particle per unit of simulation time living exactly `lifetime`.
+    pub fn with_lifetime(lifetime: f32) -> Self {
+        Self { lifetime: Distribution::Constant(lifetime), ..Self::default() }
+    }
+}
+
+impl RandomEmitter {
+    /// Spawn `n` particles at once, as far as the system has room.
+    pub fn emit_burst(&self, n: usize, particles: &mut ParticleSystem) {
+        let (width, height) = particles.size();
+        for _ in 0..n.min(particles.free_slots()) {
+            let rng = particles.rng();
+            let mut vel = self.velocity.sample(rng);
+            let pos = match self.shape {
+                EmitterShape::Arena => Vec2::new(rng.random::<f32>() * width as f32, rng.random::<f32>() * height as f32),
+                EmitterShape::Point(position) => position,
+                EmitterShape::Line { start, end } => start.lerp(end, rng.random::<f32>()),
+                EmitterShape::Ring { center, radius } => {
+                    center + Vec2::from_angle(rng.random::<f32>() * std::f32::consts::TAU) * radius
+                }
+                EmitterShape::Cone { position, direction, spread, speed } => {
+                    let half = spread.to_radians() / 2.0;
+                    let angle = uniform(rng, -half, half);
+                    vel = Vec2::from_angle(angle).rotate(direction.normalize_or(Vec2::X)) * uniform(rng, speed.0, speed.1);
+                    position
+                }
+                EmitterShape::Scatter(distribution) => distribution.sample(rng),
+            };
+            let mass = self.mass.sample(rng).max(MIN_MASS);
+            let lifetime = self.lifetime.sample(rng);
+            let Some(index) = particles.spawn_owned(self.id, pos.to_array(), vel.to_array(), mass, lifetime) else {
+                break;
+            };
+            particles.set_color(index, self.color);
+            if let Some(radius) = self.radius {
+                let radius = radius.sample(particles.rng()).max(0.0);
+                particles.set_radius(index, radius);
+            }
+            if let Some(drag) = self.drag {
+                let drag = drag.sample(particles.rng()).max(0.0);
+                particles.set_drag(index, drag);
+            }
+        }
+    }
+
+    /// Particles due from the schedule between `elapsed` and `elapsed + dt`.
+    fn scheduled(&self, schedule: EmissionSchedule, dt: f32) -> usize {
+        let (start, end) = (self.elapsed, self.elapsed + dt);
+        match schedule {
+            EmissionSchedule::Every { count, interval } if interval > 0.0 => {
+                // bursts at multiples of `interval` in [start, end)
+                let bursts = (end / interval).ceil() - (start / interval).ceil();
+                count * bursts.max(0.0) as usize
+            }
+            EmissionSchedule::Every { .. } => 0,
+            EmissionSchedule::Once { count, at } => {
+                if (start..end).contains(&at) {
+                    count
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+impl Emitter for RandomEmitter {
+    fn emit(&mut self, dt: f32, particles: &mut ParticleSystem) {
+        let due = match (self.target_count, self.schedule) {
+            (Some(target), _) => target.saturating_sub(particles.emitter_stats(self.id).live),
+            (None, Some(schedule)) => self.scheduled(schedule, dt),
+            (None, None) => {
+                self.carry += self.rate * dt;
+                let due = self.carry.floor();
+                self.carry -= due;
+                due as usize
+            }
+        };
+        self.elapsed += dt;
+        self.emit_burst(due, particles);
+    }
+}
+
+pub(crate) fn never_expires(lifetime: &Distribution) -> bool {
+    *lifetime == Distribution::Constant(INFINITE_LIFETIME)
+}
@@ -0,0 +1,61 @@
+use crate::obstacle::Shape;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Region that absorbs every particle entering it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sink {
+    pub region: Shape,
+}
+
+/// Region emitting particles at random points inside it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    pub region: Shape,
+    #[serde(default)]
+    pub velocity: Vec2,
+    /// Particles per unit of simulation time. `None` re-emits what the sinks absorb,
+    /// shared between all such sources, keeping the population constant.
+    #[serde(default)]
+    pub rate: Option<f32>,
+    /// Lifetime of emitted particles; `None` means they never expire.
+    #[serde(default)]
+    pub lifetime: Option<f32>,
+}
+
+/// Throughput of a sink or source.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlowStats {
+    /// Particles absorbed or emitted in total.
+    pub total: u64,
+    /// Smoothed throughput in particles per unit of simulation time.
+    pub rate: f32,
+    this_update: u32,
+    /// Fractional particles owed by fixed-rate sources.
+    carry: f32,
+}
+
+impl FlowStats {
+    pub(crate) fn count(&mut self, n: usize) {
+        self.total += n as u64;
+        self.this_update += n as u32;
+    }
+
+    /// Fold the count since the last update into `rate`. Counts of an update that does
+    /// not advance time are carried over to the next one.
+    pub(crate) fn end_update(&mut self, dt: f32) {
+        if dt > 0.0 {
+            let rate = self.this_update as f32 / dt;
+            self.rate = 0.9 * self.rate + 0.1 * rate;
+            self.this_update = 0;
+        }
+    }
+
+    /// Whole particles a source emitting `rate` owes after `dt`.
+    pub(crate) fn due(&mut self, rate: f32, dt: f32) -> usize {
+        self.carry += rate * dt;
+        let due = self.carry.floor();
+        self.carry -= due;
+        due as usize
+    }
+}
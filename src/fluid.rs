@@ -0,0 +1,212 @@
+use crate::force::{Force, ParticleView};
+use crate::memory::vec_bytes;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Coarse fluid the particles move through, see `FluidGrid`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FluidConfig {
+    /// Width of a grid cell in pixels; coarser grids are cheaper and swirl in larger eddies.
+    pub cell: f32,
+    /// Kinematic viscosity in square pixels per unit of simulation time.
+    pub viscosity: f32,
+    /// How fast particles and fluid pull each other's velocity together, per unit of
+    /// simulation time.
+    pub coupling: f32,
+    /// Fluid mass per square pixel: the lighter the fluid, the more particles stir it up.
+    pub density: f32,
+    /// Relaxation passes of the diffusion and pressure solves.
+    pub iterations: u32,
+}
+
+impl Default for FluidConfig {
+    fn default() -> Self {
+        Self { cell: 16.0, viscosity: 0.5, coupling: 0.05, density: 0.002, iterations: 20 }
+    }
+}
+
+/// Eulerian velocity field on a coarse grid of cell centers, solved with stable fluids:
+/// implicit diffusion, semi-Lagrangian advection and a pressure projection that keeps it
+/// free of divergence, so momentum the particles put in turns into large-scale swirls.
+///
+/// Particles deposit momentum with `couple` and feel the drag of the fluid as a `Force`.
+#[derive(Clone, Debug)]
+pub struct FluidGrid {
+    pub config: FluidConfig,
+    cols: usize,
+    rows: usize,
+    velocity: Vec<Vec2>,
+    /// Velocity before the current solve pass.
+    previous: Vec<Vec2>,
+    pressure: Vec<f32>,
+    divergence: Vec<f32>,
+    /// Total coupling weight of the particles in each cell during `couple`.
+    weights: Vec<f32>,
+}
+
+impl FluidGrid {
+    /// Fluid at rest covering a `width` x `height` arena.
+    pub fn new(width: usize, height: usize, config: FluidConfig) -> Self {
+        let config = FluidConfig { cell: config.cell.max(1.0), ..config };
+        let cols = (width as f32 / config.cell).ceil().max(1.0) as usize;
+        let rows = (height as f32 / config.cell).ceil().max(1.0) as usize;
+        let cells = cols * rows;
+        Self {
+            config,
+            cols,
+            rows,
+            velocity: vec![Vec2::ZERO; cells],
+            previous: vec![Vec2::ZERO; cells],
+            pressure: vec![0.0; cells],
+            divergence: vec![0.0; cells],
+            weights: vec![0.0; cells],
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.velocity) + vec_bytes(&self.previous) + vec_bytes(&self.pressure)
+            + vec_bytes(&self.divergence)
+            + vec_bytes(&self.weights)
+    }
+
+    /// Cells per row and column.
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// The four cells around `pos` with their bilinear weights.
+    fn corners(&self, pos: Vec2) -> [(usize, f32); 4] {
+        let max = Vec2::new((self.cols - 1) as f32, (self.rows - 1) as f32);
+        let cell = (pos / self.config.cell - 0.5).clamp(Vec2::ZERO, max);
+        let x0 = (cell.x as usize).min(self.cols.saturating_sub(2));
+        let y0 = (cell.y as usize).min(self.rows.saturating_sub(2));
+        let (x1, y1) = ((x0 + 1).min(self.cols - 1), (y0 + 1).min(self.rows - 1));
+        let t = (cell - Vec2::new(x0 as f32, y0 as f32)).clamp(Vec2::ZERO, Vec2::ONE);
+        [
+            (y0 * self.cols + x0, (1.0 - t.x) * (1.0 - t.y)),
+            (y0 * self.cols + x1, t.x * (1.0 - t.y)),
+            (y1 * self.cols + x0, (1.0 - t.x) * t.y),
+            (y1 * self.cols + x1, t.x * t.y),
+        ]
+    }
+
+    /// Interpolated fluid velocity at `pos`.
+    pub fn sample(&self, pos: Vec2) -> Vec2 {
+        self.corners(pos).iter().map(|&(index, weight)| self.velocity[index] * weight).sum()
+    }
+
+    /// Exchange momentum with the particles for a step of `dt`: the fluid takes up the
+    /// reaction to the drag `apply` puts on each particle.
+    pub fn couple(&mut self, positions: &[Vec2], velocities: &[Vec2], masses: &[f32], dt: f32) {
+        let cell_mass = self.config.density * self.config.cell * self.config.cell;
+        let scale = self.config.coupling * dt / cell_mass.max(f32::EPSILON);
+        self.previous.copy_from_slice(&self.velocity);
+        // gather the weighted slip of all particles per cell in `velocity` first
+        self.velocity.fill(Vec2::ZERO);
+        self.weights.fill(0.0);
+        for ((&pos, &vel), &m) in positions.iter().zip(velocities).zip(masses) {
+            let corners = self.corners(pos);
+            let slip: Vec2 = corners.iter().map(|&(index, weight)| self.previous[index] * weight).sum::<Vec2>() - vel;
+            for (index, weight) in corners {
+                let w = m * scale * weight;
+                self.velocity[index] += slip * w;
+                self.weights[index] += w;
+            }
+        }
+        // implicit exchange, so however crowded a cell is it never overshoots the
+        // particles' velocity
+        for ((velocity, &previous), &weight) in self.velocity.iter_mut().zip(&self.previous).zip(&self.weights) {
+            *velocity = previous - *velocity / (1.0 + weight);
+        }
+    }
+
+    /// Advance the fluid by `dt`.
+    pub fn step(&mut self, dt: f32) {
+        self.diffuse(dt);
+        self.project();
+        self.advect(dt);
+        self.project();
+    }
+
+    pub fn clear(&mut self) {
+        self.velocity.fill(Vec2::ZERO);
+        self.pressure.fill(0.0);
+    }
+
+    /// Value of the neighbour of `index` at (`dx`, `dy`) cells away, or `outside` past the edge.
+    fn neighbour<T: Copy>(&self, values: &[T], index: usize, dx: isize, dy: isize, outside: T) -> T {
+        let (x, y) = ((index % self.cols) as isize + dx, (index / self.cols) as isize + dy);
+        if x < 0 || y < 0 || x >= self.cols as isize || y >= self.rows as isize {
+            outside
+        } else {
+            values[y as usize * self.cols + x as usize]
+        }
+    }
+
+    /// Implicit viscous diffusion, relaxed with Gauss-Seidel; free-slip at the edges.
+    fn diffuse(&mut self, dt: f32) {
+        let a = self.config.viscosity * dt / (self.config.cell * self.config.cell);
+        if a <= 0.0 {
+            return;
+        }
+        self.previous.copy_from_slice(&self.velocity);
+        for _ in 0..self.config.iterations {
+            for i in 0..self.velocity.len() {
+                let own = self.velocity[i];
+                let sum = self.neighbour(&self.velocity, i, -1, 0, own)
+                    + self.neighbour(&self.velocity, i, 1, 0, own)
+                    + self.neighbour(&self.velocity, i, 0, -1, own)
+                    + self.neighbour(&self.velocity, i, 0, 1, own);
+                self.velocity[i] = (self.previous[i] + a * sum) / (1.0 + 4.0 * a);
+            }
+        }
+    }
+
+    /// Move the velocities along themselves, tracing each cell center back over `dt`.
+    fn advect(&mut self, dt: f32) {
+        self.previous.copy_from_slice(&self.velocity);
+        let cell = self.config.cell;
+        for i in 0..self.velocity.len() {
+            let center = Vec2::new((i % self.cols) as f32 + 0.5, (i / self.cols) as f32 + 0.5) * cell;
+            let from = center - self.previous[i] * dt;
+            self.velocity[i] = self.corners(from).iter().map(|&(index, weight)| self.previous[index] * weight).sum();
+        }
+    }
+
+    /// Remove the divergent part of the velocity by solving for pressure; the arena edges
+    /// are solid walls.
+    fn project(&mut self) {
+        let cell = self.config.cell;
+        for i in 0..self.velocity.len() {
+            let dx = self.neighbour(&self.velocity, i, 1, 0, Vec2::ZERO).x - self.neighbour(&self.velocity, i, -1, 0, Vec2::ZERO).x;
+            let dy = self.neighbour(&self.velocity, i, 0, 1, Vec2::ZERO).y - self.neighbour(&self.velocity, i, 0, -1, Vec2::ZERO).y;
+            self.divergence[i] = (dx + dy) / (2.0 * cell);
+        }
+        self.pressure.fill(0.0);
+        for _ in 0..self.config.iterations {
+            for i in 0..self.pressure.len() {
+                let own = self.pressure[i];
+                let sum = self.neighbour(&self.pressure, i, -1, 0, own)
+                    + self.neighbour(&self.pressure, i, 1, 0, own)
+                    + self.neighbour(&self.pressure, i, 0, -1, own)
+                    + self.neighbour(&self.pressure, i, 0, 1, own);
+                self.pressure[i] = (sum - self.divergence[i] * cell * cell) / 4.0;
+            }
+        }
+        for i in 0..self.velocity.len() {
+            let own = self.pressure[i];
+            let gradient = Vec2::new(
+                self.neighbour(&self.pressure, i, 1, 0, own) - self.neighbour(&self.pressure, i, -1, 0, own),
+                self.neighbour(&self.pressure, i, 0, 1, own) - self.neighbour(&self.pressure, i, 0, -1, own),
+            );
+            self.velocity[i] -= gradient / (2.0 * cell);
+        }
+    }
+}
+
+impl Force for FluidGrid {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        (self.sample(particle.position) - particle.velocity) * self.config.coupling * particle.mass
+    }
+}
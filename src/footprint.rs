@@ -0,0 +1,67 @@
+/// Precomputed rasterization footprint: pixel offsets from the center and their coverage.
+#[derive(Default)]
+pub struct Footprint {
+    pub offsets: Vec<(i32, i32)>,
+    pub coverage: Vec<u8>,
+}
+
+/// Circle spans and Gaussian splat footprints, computed once per integer radius.
+#[derive(Default)]
+pub struct FootprintCache {
+    circle_spans: Vec<Option<Vec<i32>>>,
+    splats: Vec<Option<Footprint>>,
+}
+
+impl FootprintCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Solid disc as horizontal half-widths per scanline: entry `dy + r` covers
+    /// `-w..=w` around the center for the row at offset `dy`.
+    pub fn circle_spans(&mut self, radius: usize) -> &[i32] {
+        if radius >= self.circle_spans.len() {
+            self.circle_spans.resize_with(radius + 1, || None);
+        }
+        self.circle_spans[radius].get_or_insert_with(|| {
+            let r = radius as i32;
+            (-r..=r)
+                .map(|dy| ((r * r - dy * dy) as f32).sqrt() as i32)
+                .collect()
+        })
+    }
+
+    /// Gaussian splat with sigma = radius / 2; the peak stays below full white so
+    /// overlapping splats build up a glow.
+    pub fn splat(&mut self, radius: usize) -> &Footprint {
+        let sigma = (radius as f32 / 2.0).max(0.5);
+        cached(&mut self.splats, radius, |dx, dy| {
+            let d2 = (dx * dx + dy * dy) as f32;
+            let weight = (-d2 / (2.0 * sigma * sigma)).exp();
+            Some((weight * 96.0) as u8).filter(|&c| c > 0)
+        })
+    }
+}
+
+fn cached(
+    slots: &mut Vec<Option<Footprint>>,
+    radius: usize,
+    coverage: impl Fn(i32, i32) -> Option<u8>,
+) -> &Footprint {
+    if radius >= slots.len() {
+        slots.resize_with(radius + 1, || None);
+    }
+    slots[radius].get_or_insert_with(|| {
+        let r = radius as i32;
+        let mut footprint = Footprint::default();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if let Some(c) = coverage(dx, dy) {
+                    footprint.offsets.push((dx, dy));
+                    footprint.coverage.push(c);
+                }
+            }
+        }
+        footprint
+    })
+}
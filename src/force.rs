@@ -0,0 +1,111 @@
+use crate::world::EmitterId;
+use glam::Vec2;
+
+/// What a `Force` sees of the particle it acts on.
+pub struct ParticleView<'a> {
+    /// Position and velocity to evaluate at. Higher order integrators probe points
+    /// between the particle's stored state and its next one.
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+    pub radius: f32,
+    pub emitter: EmitterId,
+    /// Name of the particle's group, if it is in one.
+    pub group: Option<&'a str>,
+    /// Simulation time.
+    pub time: f32,
+    /// Positions of all live particles, indexed like the `index` passed to `Force::apply`;
+    /// those before it have already moved this step.
+    pub positions: &'a [Vec2],
+}
+
+/// Force on each particle, summed with the others before integrating. Register custom
+/// ones with `ParticleSystem::add_force`; closures taking the same arguments as `apply`
+/// are forces too.
+pub trait Force: Send + Sync {
+    /// Force on particle `index` (mass times acceleration).
+    fn apply(&self, index: usize, particle: &ParticleView) -> Vec2;
+}
+
+impl<F: Fn(usize, &ParticleView) -> Vec2 + Send + Sync> Force for F {
+    fn apply(&self, index: usize, particle: &ParticleView) -> Vec2 {
+        self(index, particle)
+    }
+}
+
+/// Gravity, wind, sensor acceleration and linear drag of `SimParams`, after the
+/// overrides of the particle's group.
+pub struct GlobalForces {
+    pub gravity: Vec2,
+    pub wind: Vec2,
+    pub acceleration: Vec2,
+    pub drag: Vec2,
+}
+
+impl Force for GlobalForces {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        let mut f = self.gravity * particle.mass;
+        f += self.wind;
+        f += self.acceleration * particle.mass;
+        f += -self.drag * particle.velocity; // simple drag: F = -k v
+        f
+    }
+}
+
+/// Placed force sources of one kind, such as the attractors of a scene, in the order
+/// they were added. Reads like a slice.
+#[derive(Clone, Debug)]
+pub struct ForceSources<T> {
+    sources: Vec<T>,
+}
+
+impl<T> Default for ForceSources<T> {
+    fn default() -> Self {
+        Self { sources: Vec::new() }
+    }
+}
+
+impl<T> ForceSources<T> {
+    /// Add `source` and return its index.
+    pub fn add(&mut self, source: T) -> usize {
+        self.sources.push(source);
+        self.sources.len() - 1
+    }
+
+    /// Take out the source at `index`; the indices of later sources drop by one.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        (index < self.sources.len()).then(|| self.sources.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.sources.clear();
+    }
+
+    /// The source at `index`, e.g. to move it or change its strength.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.sources.get_mut(index)
+    }
+}
+
+impl<T> From<Vec<T>> for ForceSources<T> {
+    fn from(sources: Vec<T>) -> Self {
+        Self { sources }
+    }
+}
+
+impl<T> std::ops::Deref for ForceSources<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.sources
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ForceSources<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.sources.iter()
+    }
+}
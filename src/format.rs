@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// How numbers are written in the HUD and in CSV exports, e.g. `1.234,5` in German.
+///
+/// Units stay fixed (ms, MB, FPS) whatever the locale; only the digits change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    /// Separator between the integer and the fractional digits.
+    pub decimal_separator: char,
+    /// Separator between groups of three integer digits in the HUD; `None` groups nothing.
+    /// Exports are never grouped so spreadsheets parse them back.
+    pub thousands_separator: Option<char>,
+    /// CSV field separator; `None` picks `;` when the decimal separator is a comma, else `,`.
+    pub csv_separator: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: None,
+            csv_separator: None,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// An integer count, grouped.
+    pub fn int(&self, n: usize) -> String {
+        self.group(&n.to_string())
+    }
+
+    /// `x` rounded to `decimals` places, grouped.
+    pub fn fixed(&self, x: f64, decimals: usize) -> String {
+        let text = format!("{x:.decimals$}");
+        match text.split_once('.') {
+            Some((int, frac)) => format!("{}{}{frac}", self.group(int), self.decimal_separator),
+            None => self.group(&text),
+        }
+    }
+
+    /// `x` with all the digits needed to read it back, ungrouped, for exports.
+    pub fn export(&self, x: f32) -> String {
+        let text = x.to_string();
+        if self.decimal_separator == '.' {
+            text
+        } else {
+            text.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+
+    pub fn csv_separator(&self) -> char {
+        self.csv_separator
+            .unwrap_or(if self.decimal_separator == ',' { ';' } else { ',' })
+    }
+
+    /// Insert the thousands separator into the integer digits of `int`.
+    fn group(&self, int: &str) -> String {
+        let Some(separator) = self.thousands_separator else {
+            return int.to_string();
+        };
+        let (sign, digits) = int.split_at(int.len() - int.trim_start_matches('-').len());
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return int.to_string();
+        }
+        let mut out = String::from(sign);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(separator);
+            }
+            out.push(digit);
+        }
+        out
+    }
+}
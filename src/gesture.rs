@@ -0,0 +1,139 @@
+use crate::input::{Action, ActionEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Recorded sequence of interactive force events, replayed frame by frame.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GestureMacro {
+    /// Length in frames.
+    pub frames: u32,
+    /// Events with the frame they happened in, relative to the start.
+    pub events: Vec<(u32, ActionEvent)>,
+}
+
+struct Recording {
+    start: u32,
+    events: Vec<(u32, ActionEvent)>,
+    touches: BTreeSet<(Action, u64)>,
+}
+
+struct Playback {
+    name: String,
+    start: u32,
+    next: usize,
+    looping: bool,
+}
+
+/// Records attractor and wind gestures as named macros and plays them back.
+#[derive(Default)]
+pub struct Gestures {
+    pub macros: BTreeMap<String, GestureMacro>,
+    recording: Option<Recording>,
+    playing: Option<Playback>,
+    /// Most recently recorded or played macro, for `toggle_loop`.
+    last: Option<String>,
+    frame: u32,
+}
+
+impl Gestures {
+    pub fn new(macros: BTreeMap<String, GestureMacro>) -> Self {
+        Self { macros, ..Self::default() }
+    }
+
+    /// Advance one frame.
+    pub fn tick(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Start recording, or stop and store the macro as `macro-N`, returning its name.
+    pub fn toggle_recording(&mut self) -> Option<String> {
+        let Some(mut recording) = self.recording.take() else {
+            self.recording = Some(Recording { start: self.frame, events: Vec::new(), touches: BTreeSet::new() });
+            return None;
+        };
+        // leave nothing held when playback ends
+        let end = self.frame - recording.start;
+        recording.events.push((end, ActionEvent::Released(Action::Attract)));
+        for (action, id) in recording.touches {
+            recording.events.push((end, ActionEvent::Touch(action, id, None)));
+        }
+        let name = (1..).map(|n| format!("macro-{n}")).find(|name| !self.macros.contains_key(name))?;
+        self.macros.insert(name.clone(), GestureMacro { frames: end, events: recording.events });
+        self.last = Some(name.clone());
+        Some(name)
+    }
+
+    /// Record `event` if recording and it is a force gesture.
+    pub fn record(&mut self, event: &ActionEvent) {
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+        match *event {
+            ActionEvent::Pointer(Action::Attract | Action::Wind | Action::TouchAttract, _)
+            | ActionEvent::Released(Action::Attract) => (),
+            ActionEvent::Touch(action, id, position) => {
+                if position.is_some() {
+                    recording.touches.insert((action, id));
+                } else {
+                    recording.touches.remove(&(action, id));
+                }
+            }
+            _ => return,
+        }
+        recording.events.push((self.frame - recording.start, *event));
+    }
+
+    /// Start playing the macro `name` from the beginning. Returns false if there is none.
+    pub fn play(&mut self, name: &str, looping: bool) -> bool {
+        if !self.macros.contains_key(name) {
+            return false;
+        }
+        self.playing = Some(Playback { name: name.to_string(), start: self.frame, next: 0, looping });
+        self.last = Some(name.to_string());
+        true
+    }
+
+    /// Loop the last recorded or played macro, or stop looping it.
+    pub fn toggle_loop(&mut self) {
+        match &mut self.playing {
+            Some(playback) if playback.looping => self.playing = None,
+            Some(playback) => playback.looping = true,
+            None => {
+                if let Some(name) = self.last.clone() {
+                    self.play(&name, true);
+                }
+            }
+        }
+    }
+
+    /// Append the events of the playing macro that are due this frame.
+    pub fn emit(&mut self, out: &mut Vec<ActionEvent>) {
+        let Some(playback) = &mut self.playing else {
+            return;
+        };
+        let Some(gesture) = self.macros.get(&playback.name) else {
+            self.playing = None;
+            return;
+        };
+        let elapsed = self.frame - playback.start;
+        while let Some(&(frame, event)) = gesture.events.get(playback.next) {
+            if frame > elapsed {
+                break;
+            }
+            out.push(event);
+            playback.next += 1;
+        }
+        if elapsed >= gesture.frames {
+            if playback.looping {
+                playback.start = self.frame;
+                playback.next = 0;
+            } else {
+                self.playing = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::colormap::Palette;
+use crate::world::HeatGrid;
+use pixels::{wgpu, Pixels};
+use serde::{Deserialize, Serialize};
+
+/// Colormap the density heatmap in a fragment shader instead of on the CPU.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuHeatmapConfig {
+    /// Number of isocontour lines drawn over the heatmap; 0 draws none.
+    pub contours: u32,
+}
+
+const SHADER: &str = r#"
+struct Params {
+    // view size in grid cells
+    cells: vec2<f32>,
+    contours: f32,
+    srgb: f32,
+    // colormap stops from `Palette::stops`, rgb in 0..1
+    stops: array<vec4<f32>, 5>,
+}
+
+@group(0) @binding(0) var density: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // one triangle covering the viewport
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn load(cell: vec2<i32>) -> f32 {
+    let last = vec2<i32>(textureDimensions(density)) - 1;
+    return textureLoad(density, clamp(cell, vec2<i32>(0), last), 0).r;
+}
+
+// same interpolation as `Palette::sample`
+fn heat(t: f32) -> vec3<f32> {
+    let scaled = clamp(t, 0.0, 1.0) * 4.0;
+    let i = min(u32(scaled), 3u);
+    return mix(params.stops[i].rgb, params.stops[i + 1u].rgb, scaled - f32(i));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.uv * params.cells;
+    var color = heat(load(vec2<i32>(floor(p))));
+
+    // contours of the field interpolated between cell centers
+    let q = p - 0.5;
+    let base = vec2<i32>(floor(q));
+    let f = fract(q);
+    let top = mix(load(base), load(base + vec2<i32>(1, 0)), f.x);
+    let bottom = mix(load(base + vec2<i32>(0, 1)), load(base + vec2<i32>(1, 1)), f.x);
+    let level = mix(top, bottom, f.y) * params.contours;
+    let to_line = abs(fract(level + 0.5) - 0.5);
+    let line = 1.0 - smoothstep(0.0, 1.5 * fwidth(level), to_line);
+    if params.contours > 0.0 && level > 0.5 {
+        color = max(color, vec3<f32>(line));
+    }
+
+    if params.srgb > 0.5 {
+        color = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    }
+    return vec4<f32>(color, 1.0);
+}
+"#;
+
+/// Size of the shader's `Params`: four scalars and five color stops.
+const PARAMS_BYTES: u64 = 4 * 4 + 5 * 16;
+
+/// Heatmap render pass drawn after the pixels frame, one viewport per view.
+///
+/// The CPU only bins particles into the coarse grid; the grid is uploaded as a small
+/// texture and colormapped per pixel here. It is max-blended over the frame so the
+/// obstacles and overlays drawn on the CPU stay visible.
+pub struct HeatmapPass {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    views: Vec<Option<ViewTarget>>,
+    contours: f32,
+    srgb: bool,
+    palette: Palette,
+    bytes: Vec<u8>,
+}
+
+struct ViewTarget {
+    texture: wgpu::Texture,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    visible: bool,
+}
+
+impl HeatmapPass {
+    pub fn new(pixels: &Pixels, config: &GpuHeatmapConfig, view_count: usize) -> Self {
+        let device = pixels.device();
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("heatmap_shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("heatmap_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("heatmap_pipeline_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        // Max keeps whatever the CPU drew where it is brighter than the heatmap
+        let max = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Max,
+        };
+        let format = pixels.render_texture_format();
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("heatmap_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState { module: &module, entry_point: "vs_main", buffers: &[] },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState { color: max, alpha: max }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        Self {
+            pipeline,
+            layout,
+            views: (0..view_count).map(|_| None).collect(),
+            contours: config.contours as f32,
+            srgb: format.is_srgb(),
+            palette: Palette::default(),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Colormap used from the next `upload` on.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Upload the density grid of `view`, or hide its heatmap with `None`.
+    pub fn upload(&mut self, pixels: &Pixels, view: usize, grid: Option<HeatGrid>) {
+        let Some(grid) = grid else {
+            if let Some(target) = &mut self.views[view] {
+                target.visible = false;
+            }
+            return;
+        };
+        let size = wgpu::Extent3d {
+            width: grid.cols as u32,
+            height: grid.rows as u32,
+            depth_or_array_layers: 1,
+        };
+        if self.views[view].as_ref().is_none_or(|target| target.texture.size() != size) {
+            self.views[view] = Some(self.create_target(pixels.device(), size));
+        }
+        let Some(target) = &mut self.views[view] else {
+            return;
+        };
+        target.visible = true;
+
+        self.bytes.clear();
+        self.bytes.extend(grid.values.iter().flat_map(|value| value.to_ne_bytes()));
+        let queue = pixels.queue();
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &target.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.bytes,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * 4),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+        let mut params = vec![
+            grid.width as f32 / grid.cell as f32,
+            grid.height as f32 / grid.cell as f32,
+            self.contours,
+            if self.srgb { 1.0 } else { 0.0 },
+        ];
+        for stop in self.palette.stops() {
+            params.extend(stop.iter().map(|&c| c as f32 / 255.0));
+            params.push(1.0);
+        }
+        let params: Vec<u8> = params.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        queue.write_buffer(&target.uniforms, 0, &params);
+    }
+
+    /// Draw the uploaded heatmaps over `clip_rect`, the frame's area on the surface,
+    /// split into equally wide views.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView, clip_rect: (u32, u32, u32, u32)) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("heatmap_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        let (x, y, width, height) = clip_rect;
+        let view_width = width / self.views.len() as u32;
+        for (view, target) in self.views.iter().enumerate() {
+            let Some(target) = target.as_ref().filter(|target| target.visible) else {
+                continue;
+            };
+            let view_x = x + view as u32 * view_width;
+            pass.set_viewport(view_x as f32, y as f32, view_width as f32, height as f32, 0.0, 1.0);
+            pass.set_scissor_rect(view_x, y, view_width, height);
+            pass.set_bind_group(0, &target.bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+
+    fn create_target(&self, device: &wgpu::Device, size: wgpu::Extent3d) -> ViewTarget {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("heatmap_density"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("heatmap_params"),
+            size: PARAMS_BYTES,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("heatmap_bind_group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: uniforms.as_entire_binding() },
+            ],
+        });
+        ViewTarget { texture, uniforms, bind_group, visible: false }
+    }
+}
@@ -0,0 +1,961 @@
+use crate::backend::SimBackend;
+use crate::integrator::IntegratorKind;
+use crate::spatial::{SpatialGrid, GRID_CELL};
+use crate::world::{Renderer, SimParams, MAX_SUBSTEPS};
+use glam::Vec2;
+use pixels::wgpu;
+
+const SHADER: &str = r#"
+struct Params {
+    gravity: vec2<f32>,
+    wind: vec2<f32>,
+    acceleration: vec2<f32>,
+    drag: vec2<f32>,
+    // arena size in pixels
+    extent: vec2<f32>,
+    dt: f32,
+    // simulation time at the end of this substep
+    time: f32,
+    restitution: f32,
+    keep_tangential: f32,
+    // `IntegratorKind` in declaration order
+    integrator: u32,
+    // slots in use, live or not
+    count: u32,
+    // neighbor grid: cell size in pixels and cells across and down
+    cell: f32,
+    cols: u32,
+    rows: u32,
+    // `Collisions::restitution`
+    collision_restitution: f32,
+}
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    mass: f32,
+    radius: f32,
+    expires: f32,
+    alive: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+// particles per cell while counting, then each cell's fill cursor while scattering
+@group(0) @binding(2) var<storage, read_write> cursors: array<atomic<u32>>;
+// offset of each cell's first entry in `sorted`, plus one past the last cell
+@group(0) @binding(3) var<storage, read_write> starts: array<u32>;
+// live slots bucketed by cell
+@group(0) @binding(4) var<storage, read_write> sorted: array<u32>;
+// particles after collisions, so every pair is resolved from the same positions
+@group(0) @binding(5) var<storage, read_write> resolved: array<Particle>;
+// live slots in slot order, the instances drawn
+@group(0) @binding(6) var<storage, read_write> alive: array<u32>;
+// `draw_indirect` arguments: vertex count, instance count, first vertex, first instance
+@group(0) @binding(7) var<storage, read_write> draw_args: array<u32, 4>;
+
+// same forces as `GlobalForces`, divided by the mass
+fn acceleration(mass: f32, velocity: vec2<f32>) -> vec2<f32> {
+    return params.gravity + params.acceleration + (params.wind - params.drag * velocity) / mass;
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count || particles[i].alive == 0u {
+        return;
+    }
+    var p = particles[i];
+    let dt = params.dt;
+    // the forces depend on the velocity only, so the stages need no positions
+    switch params.integrator {
+        case 1u: {
+            let a0 = acceleration(p.mass, p.velocity);
+            p.position += p.velocity * dt + 0.5 * a0 * dt * dt;
+            let a1 = acceleration(p.mass, p.velocity + a0 * dt);
+            p.velocity += 0.5 * (a0 + a1) * dt;
+        }
+        case 2u: {
+            let v1 = acceleration(p.mass, p.velocity);
+            let x2 = p.velocity + v1 * 0.5 * dt;
+            let v2 = acceleration(p.mass, x2);
+            let x3 = p.velocity + v2 * 0.5 * dt;
+            let v3 = acceleration(p.mass, x3);
+            let x4 = p.velocity + v3 * dt;
+            let v4 = acceleration(p.mass, x4);
+            p.position += (p.velocity + 2.0 * x2 + 2.0 * x3 + x4) * dt / 6.0;
+            p.velocity += (v1 + 2.0 * v2 + 2.0 * v3 + v4) * dt / 6.0;
+        }
+        default: {
+            p.velocity += acceleration(p.mass, p.velocity) * dt;
+            p.position += p.velocity * dt;
+        }
+    }
+    // bounce off the edges like `BoundaryBehavior::Bounce`
+    for (var axis = 0; axis < 2; axis++) {
+        let tangent = 1 - axis;
+        if p.position[axis] - p.radius <= 0.0 {
+            p.position[axis] = p.radius;
+            p.velocity[axis] = abs(p.velocity[axis]) * params.restitution;
+            p.velocity[tangent] *= params.keep_tangential;
+        } else if p.position[axis] + p.radius >= params.extent[axis] {
+            p.position[axis] = params.extent[axis] - p.radius;
+            p.velocity[axis] = -abs(p.velocity[axis]) * params.restitution;
+            p.velocity[tangent] *= params.keep_tangential;
+        }
+    }
+    if p.expires <= params.time {
+        p.alive = 0u;
+    }
+    particles[i] = p;
+}
+
+// positions outside the arena go to the border cells
+fn cell_of(position: vec2<f32>) -> vec2<u32> {
+    let last = vec2<f32>(f32(params.cols - 1u), f32(params.rows - 1u));
+    return vec2<u32>(clamp(position / params.cell, vec2<f32>(0.0), last));
+}
+
+fn cell_index(position: vec2<f32>) -> u32 {
+    let cell = cell_of(position);
+    return cell.y * params.cols + cell.x;
+}
+
+@compute @workgroup_size(64)
+fn clear_cells(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.cols * params.rows {
+        atomicStore(&cursors[id.x], 0u);
+    }
+}
+
+@compute @workgroup_size(64)
+fn count_cells(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count || particles[i].alive == 0u {
+        return;
+    }
+    atomicAdd(&cursors[cell_index(particles[i].position)], 1u);
+}
+
+// Prefix sums run in one workgroup: each thread sums a run of the items, the runs are
+// offset by a scan over the threads, then each thread walks its run again.
+const SCAN_THREADS: u32 = 256u;
+var<workgroup> chunk_offsets: array<u32, SCAN_THREADS>;
+
+// replace the per-thread sums with their exclusive prefix sum and return the total
+fn scan_chunks() -> u32 {
+    var total = 0u;
+    for (var t = 0u; t < SCAN_THREADS; t++) {
+        let count = chunk_offsets[t];
+        chunk_offsets[t] = total;
+        total += count;
+    }
+    return total;
+}
+
+@compute @workgroup_size(SCAN_THREADS)
+fn scan_cells(@builtin(local_invocation_index) thread: u32) {
+    let cells = params.cols * params.rows;
+    let chunk = (cells + SCAN_THREADS - 1u) / SCAN_THREADS;
+    let first = min(thread * chunk, cells);
+    let end = min(first + chunk, cells);
+    var sum = 0u;
+    for (var c = first; c < end; c++) {
+        sum += atomicLoad(&cursors[c]);
+    }
+    chunk_offsets[thread] = sum;
+    workgroupBarrier();
+    if thread == 0u {
+        starts[cells] = scan_chunks();
+    }
+    workgroupBarrier();
+    var offset = chunk_offsets[thread];
+    for (var c = first; c < end; c++) {
+        let count = atomicLoad(&cursors[c]);
+        starts[c] = offset;
+        atomicStore(&cursors[c], offset);
+        offset += count;
+    }
+}
+
+// compact the live slots into `alive` and set the draw to one instance per entry
+@compute @workgroup_size(SCAN_THREADS)
+fn compact(@builtin(local_invocation_index) thread: u32) {
+    let chunk = (params.count + SCAN_THREADS - 1u) / SCAN_THREADS;
+    let first = min(thread * chunk, params.count);
+    let end = min(first + chunk, params.count);
+    var live = 0u;
+    for (var i = first; i < end; i++) {
+        live += min(particles[i].alive, 1u);
+    }
+    chunk_offsets[thread] = live;
+    workgroupBarrier();
+    if thread == 0u {
+        draw_args[0] = 6u;
+        draw_args[1] = scan_chunks();
+        draw_args[2] = 0u;
+        draw_args[3] = 0u;
+    }
+    workgroupBarrier();
+    var offset = chunk_offsets[thread];
+    for (var i = first; i < end; i++) {
+        if particles[i].alive != 0u {
+            alive[offset] = i;
+            offset++;
+        }
+    }
+}
+
+@compute @workgroup_size(64)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count || particles[i].alive == 0u {
+        return;
+    }
+    sorted[atomicAdd(&cursors[cell_index(particles[i].position)], 1u)] = i;
+}
+
+// the cells are at least two radii wide, so touching particles are in adjacent cells
+@compute @workgroup_size(64)
+fn collide(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count || particles[i].alive == 0u {
+        return;
+    }
+    var p = particles[i];
+    let home = vec2<i32>(cell_of(p.position));
+    let inv_i = 1.0 / p.mass;
+    var shift = vec2<f32>(0.0);
+    var kick = vec2<f32>(0.0);
+    for (var y = max(home.y - 1, 0); y <= min(home.y + 1, i32(params.rows) - 1); y++) {
+        for (var x = max(home.x - 1, 0); x <= min(home.x + 1, i32(params.cols) - 1); x++) {
+            let c = u32(y) * params.cols + u32(x);
+            for (var k = starts[c]; k < starts[c + 1u]; k++) {
+                let j = sorted[k];
+                let other = particles[j];
+                let offset = other.position - p.position;
+                let distance = length(offset);
+                let overlap = p.radius + other.radius - distance;
+                if j == i || overlap <= 0.0 || distance == 0.0 {
+                    continue;
+                }
+                // this particle's half of the CPU's pairwise response
+                let normal = offset / distance;
+                let inv_sum = inv_i + 1.0 / other.mass;
+                shift -= normal * overlap / inv_sum * inv_i;
+                let approach = dot(other.velocity - p.velocity, normal);
+                if approach < 0.0 {
+                    let impulse = -(1.0 + params.collision_restitution) * approach / inv_sum;
+                    kick -= normal * impulse * inv_i;
+                }
+            }
+        }
+    }
+    p.position += shift;
+    p.velocity += kick;
+    resolved[i] = p;
+}
+
+@compute @workgroup_size(64)
+fn apply_collisions(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count || particles[i].alive == 0u {
+        return;
+    }
+    particles[i] = resolved[i];
+}
+"#;
+
+const DRAW_SHADER: &str = r#"
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    mass: f32,
+    radius: f32,
+    expires: f32,
+    alive: u32,
+}
+
+// arena size in pixels
+@group(0) @binding(0) var<uniform> extent: vec2<f32>;
+@group(0) @binding(1) var<storage, read> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read> alive: array<u32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // position in the particle's bounding square, -1..1 on both axes
+    @location(0) corner: vec2<f32>,
+}
+
+// one instance per live particle, each a square of two triangles
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let p = particles[alive[instance]];
+    let corner = corners[vertex];
+    let pixel = p.position + corner * p.radius;
+    var out: VertexOutput;
+    out.position = vec4<f32>(pixel / extent * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.corner = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if dot(in.corner, in.corner) > 1.0 {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+/// Format of the frame `render` draws into.
+const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Size of the shader's `Params`: five vectors and ten scalars.
+const PARAMS_BYTES: u64 = 5 * 8 + 10 * 4;
+/// Size of the shader's `Particle`.
+const PARTICLE_BYTES: u64 = 32;
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Particles stepped by compute shaders on a device of their own.
+///
+/// Covers the bulk motion of `SimParams`: gravity, wind at the current time, the
+/// acceleration sensor, drag, any of the integrators and substeps, walls bouncing with
+/// `restitution` and `wall_friction`, and `collisions`. Everything else (scenes, per-edge
+/// wall behaviors, turbulence, fading) only runs on the CPU, and expired particles vanish
+/// at once instead of fading out.
+///
+/// Slots are allocated on the CPU, which knows when every particle expires, so spawning
+/// and counting never wait for the device. Reading the particles back does.
+///
+/// Like `ParticleSystem`, the particles are bucketed into a grid by a counting sort,
+/// here in compute shaders: every substep for collisions and at the end of each step for
+/// `read_neighbors`.
+///
+/// `render` draws the live particles as white discs on transparent black into a frame of
+/// the arena's size. A prefix sum compacts the live slots first and sets up an indirect
+/// draw of just those, so dead and recycled slots cost nothing to draw. `read_frame` copies
+/// the frame back for the CPU post-processing and exports.
+pub struct GpuSimulation {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    params: SimParams,
+    extent: Vec2,
+    time: f32,
+    capacity: usize,
+    /// Slots handed out so far, the range the shaders visit.
+    slots: usize,
+    /// Simulation time at which each slot's particle expires; `f32::NEG_INFINITY` once freed.
+    expires: Vec<f32>,
+    free: Vec<u32>,
+    /// Largest radius spawned, which sets the grid cell size as for the CPU's collisions.
+    max_radius: f32,
+    /// Cell size and cells across and down the grid passes last bucketed with; spawns may
+    /// change `grid_layout` since.
+    grid_shape: (f32, usize, usize),
+    uniforms: wgpu::Buffer,
+    particles: wgpu::Buffer,
+    starts: wgpu::Buffer,
+    sorted: wgpu::Buffer,
+    draw_args: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipelines: Pipelines,
+    draw: wgpu::RenderPipeline,
+    draw_bind_group: wgpu::BindGroup,
+    frame: wgpu::Texture,
+    /// Receives the frame, with rows padded to the copy alignment.
+    frame_staging: wgpu::Buffer,
+    /// Frame as last read back, rows unpadded.
+    frame_pixels: Vec<u8>,
+    /// Live particles as last read back, or `None` when the device has moved on since.
+    readback: Option<Readback>,
+    /// Grid as last read back, or `None` when the device has moved on since.
+    grid: Option<SpatialGrid>,
+}
+
+struct Pipelines {
+    integrate: wgpu::ComputePipeline,
+    clear_cells: wgpu::ComputePipeline,
+    count_cells: wgpu::ComputePipeline,
+    scan_cells: wgpu::ComputePipeline,
+    scatter: wgpu::ComputePipeline,
+    collide: wgpu::ComputePipeline,
+    apply_collisions: wgpu::ComputePipeline,
+    compact: wgpu::ComputePipeline,
+}
+
+#[derive(Default)]
+struct Readback {
+    /// Slot of each live particle, in slot order.
+    slots: Vec<u32>,
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    masses: Vec<f32>,
+}
+
+/// Bytes per row of `width` RGBA pixels, padded as texture copies require.
+fn padded_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// Cells of the finest grid, `GRID_CELL` pixels wide, over a `width` x `height` arena.
+fn max_cells(width: usize, height: usize) -> usize {
+    let cells = |extent: usize| (extent as f32 / GRID_CELL).ceil().max(1.0) as usize;
+    cells(width) * cells(height)
+}
+
+impl GpuSimulation {
+    /// Simulation of up to `capacity` particles in a `width` x `height` arena on `device`.
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue, capacity: usize, width: usize, height: usize) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("simulation_shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+            count: None,
+        };
+        let storage = wgpu::BufferBindingType::Storage { read_only: false };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("simulation_bind_group_layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, storage),
+                entry(2, storage),
+                entry(3, storage),
+                entry(4, storage),
+                entry(5, storage),
+                entry(6, storage),
+                entry(7, storage),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("simulation_pipeline_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+            })
+        };
+        let pipelines = Pipelines {
+            integrate: pipeline("integrate"),
+            clear_cells: pipeline("clear_cells"),
+            count_cells: pipeline("count_cells"),
+            scan_cells: pipeline("scan_cells"),
+            scatter: pipeline("scatter"),
+            collide: pipeline("collide"),
+            apply_collisions: pipeline("apply_collisions"),
+            compact: pipeline("compact"),
+        };
+        let buffer = |label, size: u64, usage| {
+            // wgpu rejects empty bindings
+            device.create_buffer(&wgpu::BufferDescriptor { label: Some(label), size: size.max(16), usage, mapped_at_creation: false })
+        };
+        let storage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+        let particle_bytes = capacity as u64 * PARTICLE_BYTES;
+        let cell_bytes = (max_cells(width, height) as u64 + 1) * 4;
+        let sorted_bytes = capacity as u64 * 4;
+        let uniforms = buffer("simulation_params", PARAMS_BYTES, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let particles = buffer("simulation_particles", particle_bytes, storage);
+        let cursors = buffer("simulation_cursors", cell_bytes, storage);
+        let starts = buffer("simulation_starts", cell_bytes, storage);
+        let sorted = buffer("simulation_sorted", sorted_bytes, storage);
+        let resolved = buffer("simulation_resolved", particle_bytes, storage);
+        let alive = buffer("simulation_alive", sorted_bytes, storage);
+        let draw_args = buffer("simulation_draw_args", 16, wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT);
+        // large enough for either the particles or the grid
+        let staging = buffer(
+            "simulation_readback",
+            particle_bytes.max(cell_bytes + sorted_bytes),
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("simulation_bind_group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: uniforms.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: particles.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: cursors.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: starts.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: sorted.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: resolved.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 6, resource: alive.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 7, resource: draw_args.as_entire_binding() },
+            ],
+        });
+
+        let draw_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("simulation_draw_shader"),
+            source: wgpu::ShaderSource::Wgsl(DRAW_SHADER.into()),
+        });
+        let vertex_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+            count: None,
+        };
+        let read_only = wgpu::BufferBindingType::Storage { read_only: true };
+        let draw_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("simulation_draw_bind_group_layout"),
+            entries: &[
+                vertex_entry(0, wgpu::BufferBindingType::Uniform),
+                vertex_entry(1, read_only),
+                vertex_entry(2, read_only),
+            ],
+        });
+        let draw_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("simulation_draw_pipeline_layout"),
+            bind_group_layouts: &[&draw_layout],
+            push_constant_ranges: &[],
+        });
+        let draw = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("simulation_draw_pipeline"),
+            layout: Some(&draw_pipeline_layout),
+            vertex: wgpu::VertexState { module: &draw_module, entry_point: "vs_main", buffers: &[] },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &draw_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FRAME_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        let extent = buffer("simulation_extent", 8, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let extent_bytes: Vec<u8> = [width as f32, height as f32].iter().flat_map(|c| c.to_ne_bytes()).collect();
+        queue.write_buffer(&extent, 0, &extent_bytes);
+        let draw_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("simulation_draw_bind_group"),
+            layout: &draw_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: extent.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: particles.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: alive.as_entire_binding() },
+            ],
+        });
+        let frame = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("simulation_frame"),
+            size: wgpu::Extent3d {
+                width: width.max(1) as u32,
+                height: height.max(1) as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FRAME_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let frame_size = frame.size();
+        let frame_staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("simulation_frame_readback"),
+            size: padded_row(frame_size.width) as u64 * frame_size.height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut simulation = Self {
+            device,
+            queue,
+            params: SimParams::default(),
+            extent: Vec2::new(width as f32, height as f32),
+            time: 0.0,
+            capacity,
+            slots: 0,
+            expires: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            max_radius: 0.0,
+            grid_shape: (GRID_CELL, 1, 1),
+            uniforms,
+            particles,
+            starts,
+            sorted,
+            draw_args,
+            staging,
+            bind_group,
+            pipelines,
+            draw,
+            draw_bind_group,
+            frame,
+            frame_staging,
+            frame_pixels: Vec::new(),
+            readback: None,
+            grid: None,
+        };
+        simulation.grid_shape = simulation.grid_layout();
+        simulation
+    }
+
+    /// Simulation on a device of the default adapter, waiting for it to be set up.
+    ///
+    /// Fails when there is no adapter, it cannot run compute shaders, read storage buffers
+    /// in vertex shaders or draw indirectly, or its storage buffers are too small for
+    /// `capacity` particles.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn request(capacity: usize, width: usize, height: usize) -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok_or("no GPU adapter found")?;
+        let needed = wgpu::DownlevelFlags::COMPUTE_SHADERS
+            | wgpu::DownlevelFlags::VERTEX_STORAGE
+            | wgpu::DownlevelFlags::INDIRECT_EXECUTION;
+        if !adapter.get_downlevel_capabilities().flags.contains(needed) {
+            return Err(format!("{} lacks compute shaders, vertex storage or indirect draws", adapter.get_info().name));
+        }
+        let limits = adapter.limits();
+        if capacity as u64 * PARTICLE_BYTES > limits.max_storage_buffer_binding_size as u64 {
+            return Err(format!("{capacity} particles do not fit in a storage buffer of {}", adapter.get_info().name));
+        }
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("simulation_device"),
+            required_features: wgpu::Features::empty(),
+            required_limits: limits,
+        };
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&descriptor, None)).map_err(|err| err.to_string())?;
+        Ok(Self::new(device, queue, capacity, width, height))
+    }
+
+    /// Draw the live particles into the frame, see `frame`.
+    pub fn render(&mut self) {
+        // the slot count may have grown by spawns since the last step
+        self.write_params(0.0);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("simulation_draw_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("simulation_compact_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_pipeline(&self.pipelines.compact);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        let view = self.frame.create_view(&wgpu::TextureViewDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("simulation_draw_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.draw);
+            pass.set_bind_group(0, &self.draw_bind_group, &[]);
+            pass.draw_indirect(&self.draw_args, 0);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Texture `render` draws into: the arena's size in `Rgba8Unorm`, usable as a copy source.
+    pub fn frame(&self) -> &wgpu::Texture {
+        &self.frame
+    }
+
+    /// Copy the frame last drawn by `render` back from the device, waiting for it: RGBA
+    /// rows of the arena's width, as the CPU `Renderer` draws them.
+    pub fn read_frame(&mut self) -> &[u8] {
+        let size = self.frame.size();
+        let padded = padded_row(size.width);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("simulation_frame_readback_encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.frame,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.frame_staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        self.queue.submit(Some(encoder.finish()));
+        let data = self.map_read(&self.frame_staging, padded as u64 * size.height as u64);
+        let row = size.width as usize * 4;
+        self.frame_pixels.clear();
+        for padded_row in data.chunks_exact(padded as usize) {
+            self.frame_pixels.extend_from_slice(&padded_row[..row]);
+        }
+        &self.frame_pixels
+    }
+
+    /// Cell size and cells across and down of the grid, fitting the largest particle in a
+    /// cell like the CPU's collision grid.
+    fn grid_layout(&self) -> (f32, usize, usize) {
+        let cell = GRID_CELL.max(2.0 * self.max_radius);
+        let cells = |extent: f32| (extent / cell).ceil().max(1.0) as usize;
+        (cell, cells(self.extent.x), cells(self.extent.y))
+    }
+
+    /// Live particles, copied back from the device unless that was done since it last changed.
+    fn read_back(&mut self) -> &Readback {
+        if self.readback.is_none() {
+            self.readback = Some(self.copy_back());
+        }
+        self.readback.get_or_insert_with(Readback::default)
+    }
+
+    /// Copy the particle slots in use back from the device, waiting for it.
+    fn copy_back(&self) -> Readback {
+        let mut readback = Readback::default();
+        let data = self.download(&[(&self.particles, self.slots as u64 * PARTICLE_BYTES)]);
+        let value = |particle: &[u8], field: usize| {
+            let at = 4 * field;
+            f32::from_ne_bytes([particle[at], particle[at + 1], particle[at + 2], particle[at + 3]])
+        };
+        for (slot, particle) in data.chunks_exact(PARTICLE_BYTES as usize).enumerate() {
+            // expired and freed slots alike
+            if self.expires[slot] <= self.time {
+                continue;
+            }
+            readback.slots.push(slot as u32);
+            readback.positions.push(Vec2::new(value(particle, 0), value(particle, 1)));
+            readback.velocities.push(Vec2::new(value(particle, 2), value(particle, 3)));
+            readback.masses.push(value(particle, 4));
+        }
+        readback
+    }
+
+    /// Copy the grid back from the device, with its slots renumbered to index `live_slots`,
+    /// the order of `read_positions`.
+    fn copy_grid_back(&self, live_slots: &[u32]) -> SpatialGrid {
+        let (cell, cols, rows) = self.grid_shape;
+        let cell_bytes = (cols * rows + 1) as u64 * 4;
+        let data = self.download(&[(&self.starts, cell_bytes), (&self.sorted, self.slots as u64 * 4)]);
+        let mut words = data.chunks_exact(4).map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]));
+        let starts: Vec<u32> = words.by_ref().take(cols * rows + 1).collect();
+        let entries = starts.last().copied().unwrap_or(0) as usize;
+        let mut rank = vec![0; self.slots];
+        for (index, &slot) in live_slots.iter().enumerate() {
+            rank[slot as usize] = index as u32;
+        }
+        let indices = words.take(entries).map(|slot| rank[slot as usize]).collect();
+        SpatialGrid::from_sorted(cell, (cols, rows), self.extent, starts, indices)
+    }
+
+    /// Bytes of the first `len` bytes of each buffer, one after the other, waiting for the
+    /// device to finish the work submitted so far.
+    fn download(&self, sources: &[(&wgpu::Buffer, u64)]) -> Vec<u8> {
+        let total: u64 = sources.iter().map(|&(_, len)| len).sum();
+        if total == 0 {
+            return Vec::new();
+        }
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("simulation_readback_encoder"),
+        });
+        let mut offset = 0;
+        for &(source, len) in sources {
+            encoder.copy_buffer_to_buffer(source, 0, &self.staging, offset, len);
+            offset += len;
+        }
+        self.queue.submit(Some(encoder.finish()));
+        self.map_read(&self.staging, total)
+    }
+
+    /// The first `len` bytes of the mappable `buffer`, waiting for the device.
+    fn map_read(&self, buffer: &wgpu::Buffer, len: u64) -> Vec<u8> {
+        let slice = buffer.slice(..len);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            if let Err(err) = result {
+                log::error!("reading back from the GPU failed: {err}");
+            }
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        let data = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+        data
+    }
+
+    fn write_params(&self, dt: f32) {
+        let p = &self.params;
+        let wind = p.wind.at(self.time);
+        let integrator = match p.integrator {
+            IntegratorKind::SemiImplicitEuler => 0u32,
+            IntegratorKind::Verlet => 1,
+            IntegratorKind::Rk4 => 2,
+        };
+        let mut bytes = Vec::with_capacity(PARAMS_BYTES as usize);
+        for value in [p.gravity, wind, p.acceleration, p.global_drag, self.extent] {
+            bytes.extend(value.to_array().iter().flat_map(|c| c.to_ne_bytes()));
+        }
+        for value in [dt, self.time, p.restitution, 1.0 - p.wall_friction] {
+            bytes.extend(value.to_ne_bytes());
+        }
+        let (cell, cols, rows) = self.grid_layout();
+        for value in [integrator, self.slots as u32] {
+            bytes.extend(value.to_ne_bytes());
+        }
+        bytes.extend(cell.to_ne_bytes());
+        for value in [cols as u32, rows as u32] {
+            bytes.extend(value.to_ne_bytes());
+        }
+        bytes.extend(p.collisions.map_or(0.0, |collisions| collisions.restitution).to_ne_bytes());
+        self.queue.write_buffer(&self.uniforms, 0, &bytes);
+    }
+
+    /// Run `passes` in order, each over `invocations` threads of its pipeline.
+    fn dispatch(&self, passes: &[(&wgpu::ComputePipeline, usize)]) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("simulation_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("simulation_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            for &(pipeline, invocations) in passes {
+                pass.set_pipeline(pipeline);
+                pass.dispatch_workgroups((invocations as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+            }
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Passes bucketing the live particles into the grid.
+    fn grid_passes(&self) -> [(&wgpu::ComputePipeline, usize); 4] {
+        let (_, cols, rows) = self.grid_layout();
+        let p = &self.pipelines;
+        // `scan_cells` is a single workgroup, which one invocation dispatches
+        [(&p.clear_cells, cols * rows), (&p.count_cells, self.slots), (&p.scan_cells, 1), (&p.scatter, self.slots)]
+    }
+}
+
+impl SimBackend for GpuSimulation {
+    fn step(&mut self) {
+        let substeps = self.params.substeps.clamp(1, MAX_SUBSTEPS);
+        let dt = self.params.dt / substeps as f32;
+        for _ in 0..substeps {
+            self.time += dt;
+            // the uniforms are written before each submission, so one per substep
+            self.write_params(dt);
+            let p = &self.pipelines;
+            let mut passes = vec![(&p.integrate, self.slots)];
+            if self.params.collisions.is_some() {
+                passes.extend(self.grid_passes());
+                passes.extend([(&p.collide, self.slots), (&p.apply_collisions, self.slots)]);
+            }
+            self.dispatch(&passes);
+        }
+        // for `read_neighbors`, at the final positions
+        self.dispatch(&self.grid_passes());
+        self.grid_shape = self.grid_layout();
+        // the shader expires particles at the same times, so their slots can be reused
+        for (slot, expires) in self.expires.iter_mut().enumerate() {
+            if *expires != f32::NEG_INFINITY && *expires <= self.time {
+                *expires = f32::NEG_INFINITY;
+                self.free.push(slot as u32);
+            }
+        }
+        self.readback = None;
+        self.grid = None;
+    }
+
+    fn spawn(&mut self, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32) {
+        let slot = match self.free.pop() {
+            Some(slot) => slot as usize,
+            None if self.slots < self.capacity => {
+                self.slots += 1;
+                self.expires.push(f32::NEG_INFINITY);
+                self.slots - 1
+            }
+            None => return,
+        };
+        let expires = self.time + lifetime;
+        self.expires[slot] = expires;
+        let radius = self.params.radius_for_mass(mass);
+        self.max_radius = self.max_radius.max(radius);
+        let mut bytes = Vec::with_capacity(PARTICLE_BYTES as usize);
+        for value in [pos[0], pos[1], vel[0], vel[1], mass, radius, expires] {
+            bytes.extend(value.to_ne_bytes());
+        }
+        bytes.extend(1u32.to_ne_bytes());
+        self.queue.write_buffer(&self.particles, slot as u64 * PARTICLE_BYTES, &bytes);
+        // the grid is left as the last step bucketed it, but the live particles are renumbered
+        self.readback = None;
+        self.grid = None;
+    }
+
+    fn read_positions(&mut self) -> &[Vec2] {
+        &self.read_back().positions
+    }
+
+    fn read_velocities(&mut self) -> &[Vec2] {
+        &self.read_back().velocities
+    }
+
+    fn read_masses(&mut self) -> &[f32] {
+        &self.read_back().masses
+    }
+
+    fn render_frame(&mut self) -> Vec<u8> {
+        self.render();
+        let mut frame = self.read_frame().to_vec();
+        let size = self.frame.size();
+        Renderer::new(size.width as usize, size.height as usize).post_process_frame(&mut frame);
+        frame
+    }
+
+    fn read_neighbors(&mut self, point: Vec2, radius: f32) -> Vec<usize> {
+        let radius = radius.max(0.0);
+        self.read_back();
+        if self.grid.is_none() {
+            let live_slots = self.readback.as_ref().map_or(&[][..], |readback| &readback.slots);
+            self.grid = Some(self.copy_grid_back(live_slots));
+        }
+        let (Some(readback), Some(grid)) = (&self.readback, &self.grid) else {
+            return Vec::new();
+        };
+        grid.near(point, radius).filter(|&i| readback.positions[i].distance_squared(point) <= radius * radius).collect()
+    }
+
+    fn params(&self) -> &SimParams {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: SimParams) {
+        self.params = params;
+    }
+
+    fn live_count(&self) -> usize {
+        self.slots - self.free.len()
+    }
+
+    fn time(&self) -> f32 {
+        self.time
+    }
+}
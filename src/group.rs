@@ -0,0 +1,102 @@
+use crate::world::EmitterId;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Group index of particles in no group; at most this many groups take particles.
+pub const NO_GROUP: u8 = u8::MAX;
+
+/// Named subset of the particles, joined by those of its emitters when they spawn or
+/// moved in with `ParticleSystem::set_group`, with overrides of the simulation
+/// parameters and color.
+///
+/// Overrides blend in by the group's weight: 1 while enabled, 0 while disabled, and
+/// swinging between the two over `pulse_period` when that is set. Animate them from
+/// code through `ParticleSystem::group_mut`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleGroup {
+    pub name: String,
+    /// Emitters whose particles belong to the group; a particle is in at most the first
+    /// group listing its emitter.
+    pub emitters: Vec<EmitterId>,
+    pub enabled: bool,
+    /// Multiplies `SimParams::gravity` for the group.
+    pub gravity_scale: f32,
+    /// Replaces `SimParams::global_drag` for the group.
+    pub drag: Option<Vec2>,
+    /// Replaces `SimParams::restitution` for the group's bounces off walls without their
+    /// own behavior, obstacles and gates.
+    pub restitution: Option<f32>,
+    /// Multiplied into the particles' color.
+    pub color: Option<[u8; 4]>,
+    /// Simulation time of one fade in and out of the overrides; `None` holds them.
+    pub pulse_period: Option<f32>,
+}
+
+impl Default for ParticleGroup {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            emitters: Vec::new(),
+            enabled: true,
+            gravity_scale: 1.0,
+            drag: None,
+            restitution: None,
+            color: None,
+            pulse_period: None,
+        }
+    }
+}
+
+impl ParticleGroup {
+    /// How strongly the overrides apply at simulation time `time`, in `[0, 1]`.
+    pub fn weight(&self, time: f32) -> f32 {
+        match self.pulse_period {
+            _ if !self.enabled => 0.0,
+            Some(period) if period > 0.0 => 0.5 - 0.5 * (std::f32::consts::TAU * time / period).cos(),
+            _ => 1.0,
+        }
+    }
+
+    /// Gravity, drag and restitution for the group's particles at `weight`, from the global ones.
+    pub fn physics(&self, weight: f32, gravity: Vec2, drag: Vec2, restitution: f32) -> (Vec2, Vec2, f32) {
+        let scale = 1.0 + (self.gravity_scale - 1.0) * weight;
+        let drag = self.drag.map_or(drag, |own| drag.lerp(own, weight));
+        let restitution = self.restitution.map_or(restitution, |own| restitution + (own - restitution) * weight);
+        (gravity * scale, drag, restitution)
+    }
+
+    /// Color multiplied into the group's particles at `weight`, if any.
+    pub fn tint(&self, weight: f32) -> Option<[u8; 4]> {
+        let color = self.color?;
+        let mix = |c: usize| (255.0 + (color[c] as f32 - 255.0) * weight).round() as u8;
+        Some([mix(0), mix(1), mix(2), mix(3)])
+    }
+}
+
+/// Which particles a force zone acts on, by the names of their groups.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GroupFilter {
+    #[default]
+    All,
+    /// Only particles in one of these groups.
+    Only(Vec<String>),
+    /// Every particle except those in one of these groups.
+    Except(Vec<String>),
+}
+
+impl GroupFilter {
+    pub fn is_all(&self) -> bool {
+        matches!(self, GroupFilter::All)
+    }
+
+    /// Whether the zone acts on a particle in `group`, `None` for particles in no group.
+    pub fn applies(&self, group: Option<&str>) -> bool {
+        let listed = |names: &[String]| group.is_some_and(|group| names.iter().any(|name| name == group));
+        match self {
+            GroupFilter::All => true,
+            GroupFilter::Only(names) => listed(names),
+            GroupFilter::Except(names) => !listed(names),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::memory::vec_bytes;
+use glam::Vec2;
+
+/// Cell size of the impact grid in pixels.
+pub const IMPACT_CELL: usize = 4;
+
+/// Normal speed a particle must hit a surface with to count as an impact,
+/// so particles resting on a floor are not counted every step.
+pub const IMPACT_SPEED: f32 = 1.0;
+
+/// Simulation time for recent impact energy to halve.
+pub const ENERGY_HALF_LIFE: f32 = 10.0;
+
+/// Recent impact energy per cell at which the glow is fully hot.
+pub const GLOW_ENERGY: f32 = 50.0;
+
+/// Wall and obstacle impact counts accumulated over time on a coarse grid,
+/// plus the kinetic energy of recent impacts, decaying over time.
+#[derive(Clone, Debug)]
+pub struct ImpactMap {
+    cols: usize,
+    rows: usize,
+    counts: Vec<u32>,
+    max: u32,
+    energy: Vec<f32>,
+}
+
+impl ImpactMap {
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.counts) + vec_bytes(&self.energy)
+    }
+
+    pub fn new(width: usize, height: usize) -> Self {
+        let (cols, rows) = (width.div_ceil(IMPACT_CELL), height.div_ceil(IMPACT_CELL));
+        Self { cols, rows, counts: vec![0; cols * rows], max: 0, energy: vec![0.0; cols * rows] }
+    }
+
+    /// Count an impact at `pos` releasing `energy`.
+    pub fn record(&mut self, pos: Vec2, energy: f32) {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return;
+        }
+        let (x, y) = (pos.x as usize / IMPACT_CELL, pos.y as usize / IMPACT_CELL);
+        if x < self.cols && y < self.rows {
+            let count = &mut self.counts[y * self.cols + x];
+            *count = count.saturating_add(1);
+            self.max = self.max.max(*count);
+            self.energy[y * self.cols + x] += energy;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.max = 0;
+        self.energy.fill(0.0);
+    }
+
+    /// Let recent impact energy fade for `dt` of simulation time.
+    pub fn decay(&mut self, dt: f32) {
+        let factor = 0.5f32.powf(dt / ENERGY_HALF_LIFE);
+        for energy in &mut self.energy {
+            *energy *= factor;
+        }
+    }
+
+    /// Recent impact energy in the cell containing pixel (`x`, `y`).
+    pub fn energy_at(&self, x: usize, y: usize) -> f32 {
+        let (x, y) = (x / IMPACT_CELL, y / IMPACT_CELL);
+        if x < self.cols && y < self.rows {
+            self.energy[y * self.cols + x]
+        } else {
+            0.0
+        }
+    }
+
+    /// Impacts counted in the cell containing pixel (`x`, `y`).
+    pub fn count_at(&self, x: usize, y: usize) -> u32 {
+        let (x, y) = (x / IMPACT_CELL, y / IMPACT_CELL);
+        if x < self.cols && y < self.rows {
+            self.counts[y * self.cols + x]
+        } else {
+            0
+        }
+    }
+
+    /// Highest count of any cell.
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+}
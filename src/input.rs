@@ -0,0 +1,328 @@
+use glam::Vec2;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+/// Everything the user can trigger, independent of the device it came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Quit,
+    Pause,
+    Reset,
+    Attract,
+    Wind,
+    /// An attractor under each finger touching the canvas.
+    TouchAttract,
+    Copy,
+    Paste,
+    /// Start/stop recording a reference replay.
+    Record,
+    /// Toggle the ghost overlay of the recorded replay.
+    Ghost,
+    /// Cycle through the draw modes (points, circles, density heatmaps).
+    CycleDrawMode,
+    /// Step back through recent history while held.
+    Rewind,
+    /// Toggle obstacle edit mode.
+    Edit,
+    /// In edit mode: draw a new obstacle (Shift for a circle) or drag an existing one.
+    EditDraw,
+    /// In edit mode: delete the obstacle under the cursor.
+    EditDelete,
+    /// In edit mode: switch between placing shapes and sketching freehand walls.
+    WallTool,
+    /// Toggle the overlay of accumulated wall and obstacle impacts.
+    ImpactOverlay,
+    /// Trace the particle under the cursor, or clear all traces if there is none.
+    Trace,
+    /// Copy the traced paths to the clipboard as CSV.
+    ExportTraces,
+    /// Start/stop recording attractor and wind gestures as a macro.
+    RecordMacro,
+    /// Loop the last recorded or played macro, or stop looping.
+    LoopMacro,
+    /// Log the memory used by particles, renderers, spatial grids and history.
+    MemoryReport,
+    /// Log the journal of recent inputs and parameter changes and copy it to the clipboard.
+    DumpJournal,
+    /// Switch to the next colormap, see `Palette`.
+    CyclePalette,
+    /// Toggle high-contrast overlays.
+    HighContrast,
+    /// Toggle the streamlines of the imported flow field.
+    FlowOverlay,
+}
+
+/// A physical input that can be bound to an `Action`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    /// Key pressed while Ctrl is held.
+    Ctrl(KeyCode),
+    /// Mouse button index as used by `WinitInputHelper` (0 = left, 1 = right, 2 = middle).
+    Mouse(usize),
+    /// Cursor hovering over the canvas.
+    Hover,
+    /// Touch or touch-slide on the canvas.
+    Touch,
+    /// Button of any connected gamepad.
+    Gamepad(GamepadButton),
+    /// Left stick of any connected gamepad, steering a pointer across the canvas.
+    GamepadStick,
+}
+
+/// Gamepad button by its place on a standard pad, e.g. `South` is A on Xbox controllers
+/// and Cross on PlayStation ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// Pressing the left stick in.
+    LeftStick,
+    /// Pressing the right stick in.
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Action events produced by the keyboard, mouse, touch, gamepads or the JS API.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ActionEvent {
+    Pressed(Action),
+    Released(Action),
+    /// Pointer-driven action at a position in window coordinates.
+    Pointer(Action, Vec2),
+    /// Touch point `id` of a touch-bound action moved to a position, or lifted (`None`).
+    Touch(Action, u64, Option<Vec2>),
+}
+
+/// Maps actions to the bindings that trigger them.
+///
+/// When loaded from the config, listed actions replace their default bindings
+/// and unlisted actions keep them.
+#[derive(Clone, Debug, Serialize)]
+#[serde(transparent)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = BTreeMap::from([
+            (Action::Quit, vec![Binding::Key(KeyCode::Escape)]),
+            (Action::Pause, vec![Binding::Key(KeyCode::Space), Binding::Gamepad(GamepadButton::Start)]),
+            (Action::Reset, vec![Binding::Key(KeyCode::KeyR), Binding::Gamepad(GamepadButton::Select)]),
+            (Action::Attract, vec![Binding::Hover, Binding::GamepadStick]),
+            (Action::Wind, vec![Binding::Touch]),
+            (Action::TouchAttract, vec![Binding::Touch]),
+            (Action::Copy, vec![Binding::Ctrl(KeyCode::KeyC)]),
+            (Action::Paste, vec![Binding::Ctrl(KeyCode::KeyV)]),
+            (Action::Record, vec![Binding::Key(KeyCode::KeyB)]),
+            (Action::Ghost, vec![Binding::Key(KeyCode::KeyG)]),
+            (Action::CycleDrawMode, vec![Binding::Key(KeyCode::KeyM), Binding::Gamepad(GamepadButton::North)]),
+            (Action::Rewind, vec![Binding::Key(KeyCode::Backspace), Binding::Gamepad(GamepadButton::LeftTrigger)]),
+            (Action::Edit, vec![Binding::Key(KeyCode::KeyE)]),
+            (Action::EditDraw, vec![Binding::Mouse(0)]),
+            (Action::EditDelete, vec![Binding::Mouse(1)]),
+            (Action::WallTool, vec![Binding::Key(KeyCode::KeyW)]),
+            (Action::ImpactOverlay, vec![Binding::Key(KeyCode::KeyH)]),
+            (Action::Trace, vec![Binding::Key(KeyCode::KeyT)]),
+            (Action::ExportTraces, vec![Binding::Key(KeyCode::KeyX)]),
+            (Action::RecordMacro, vec![Binding::Key(KeyCode::KeyK)]),
+            (Action::LoopMacro, vec![Binding::Key(KeyCode::KeyL)]),
+            (Action::MemoryReport, vec![Binding::Key(KeyCode::KeyU)]),
+            (Action::DumpJournal, vec![Binding::Key(KeyCode::KeyJ)]),
+            (Action::CyclePalette, vec![Binding::Key(KeyCode::KeyP), Binding::Gamepad(GamepadButton::West)]),
+            (Action::HighContrast, vec![Binding::Key(KeyCode::KeyO)]),
+            (Action::FlowOverlay, vec![Binding::Key(KeyCode::KeyF)]),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl<'de> Deserialize<'de> for InputMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let overrides = BTreeMap::<Action, Vec<Binding>>::deserialize(deserializer)?;
+        let mut map = Self::default();
+        map.bindings.extend(overrides);
+        Ok(map)
+    }
+}
+
+impl InputMap {
+    /// Replace all bindings of `action`.
+    pub fn bind(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Collect pressed/released events for key and mouse button bindings.
+    pub fn button_events(&self, input: &WinitInputHelper, out: &mut Vec<ActionEvent>) {
+        for (&action, bindings) in &self.bindings {
+            for binding in bindings {
+                let (pressed, released) = match *binding {
+                    Binding::Key(key) => (input.key_pressed(key), input.key_released(key)),
+                    Binding::Ctrl(key) => {
+                        let ctrl = input.held_control();
+                        (ctrl && input.key_pressed(key), ctrl && input.key_released(key))
+                    }
+                    Binding::Mouse(button) => {
+                        (input.mouse_pressed(button), input.mouse_released(button))
+                    }
+                    Binding::Hover | Binding::Touch | Binding::Gamepad(_) | Binding::GamepadStick => continue,
+                };
+                if pressed {
+                    out.push(ActionEvent::Pressed(action));
+                }
+                if released {
+                    out.push(ActionEvent::Released(action));
+                }
+            }
+        }
+    }
+
+    /// Collect a pressed or released event for every action bound to `button`.
+    pub fn gamepad_events(&self, button: GamepadButton, pressed: bool, out: &mut Vec<ActionEvent>) {
+        for action in self.actions_for(Binding::Gamepad(button)) {
+            out.push(if pressed { ActionEvent::Pressed(action) } else { ActionEvent::Released(action) });
+        }
+    }
+
+    /// Collect pointer events for every action bound to `source` (`Hover`, `Touch` or
+    /// `GamepadStick`).
+    pub fn pointer_events(&self, source: Binding, position: Vec2, out: &mut Vec<ActionEvent>) {
+        for action in self.actions_for(source) {
+            out.push(ActionEvent::Pointer(action, position));
+        }
+    }
+
+    /// Collect events for every action bound to `Touch`: a pointer event while the touch
+    /// point `id` is down and a per-touch event, with `None` once it is lifted.
+    pub fn touch_events(&self, id: u64, position: Option<Vec2>, out: &mut Vec<ActionEvent>) {
+        for action in self.actions_for(Binding::Touch) {
+            if let Some(position) = position {
+                out.push(ActionEvent::Pointer(action, position));
+            }
+            out.push(ActionEvent::Touch(action, id, position));
+        }
+    }
+
+    /// Collect release events for every action bound to `source` once the pointer is gone.
+    pub fn pointer_released(&self, source: Binding, out: &mut Vec<ActionEvent>) {
+        for action in self.actions_for(source) {
+            out.push(ActionEvent::Released(action));
+        }
+    }
+
+    fn actions_for(&self, source: Binding) -> impl Iterator<Item = Action> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bindings)| bindings.contains(&source))
+            .map(|(&action, _)| action)
+    }
+}
+
+/// Stick deflection below which the stick counts as centered.
+#[cfg(feature = "gamepad")]
+const STICK_DEADZONE: f32 = 0.2;
+/// Pixels the stick pointer moves per frame at full deflection.
+#[cfg(feature = "gamepad")]
+const STICK_SPEED: f32 = 8.0;
+
+/// Connected gamepads, polled once per frame for action events.
+#[cfg(feature = "gamepad")]
+pub struct Gamepads {
+    /// `None` when gamepads cannot be read on this platform.
+    gilrs: Option<gilrs::Gilrs>,
+    /// Where the stick pointer is, in window coordinates.
+    pointer: Vec2,
+    extent: Vec2,
+    /// Whether a stick is deflected, so that the pointer is released once it is centered.
+    steering: bool,
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepads {
+    /// Gamepads steering a pointer across a `width` x `height` canvas, starting at its center.
+    pub fn new(width: f32, height: f32) -> Self {
+        let gilrs = gilrs::Gilrs::new()
+            .map_err(|err| log::warn!("gamepads are unavailable: {err}"))
+            .ok();
+        let extent = Vec2::new(width, height);
+        Self { gilrs, pointer: extent / 2.0, extent, steering: false }
+    }
+
+    /// Collect events for the buttons pressed and released since the last call, and for
+    /// the stick pointer.
+    pub fn events(&mut self, map: &InputMap, out: &mut Vec<ActionEvent>) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+            match event {
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = gamepad_button(button) {
+                        map.gamepad_events(button, true, out);
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = gamepad_button(button) {
+                        map.gamepad_events(button, false, out);
+                    }
+                }
+                _ => (),
+            }
+        }
+        // the most deflected stick steers; gilrs points y up, the canvas down
+        let deflection = gilrs
+            .gamepads()
+            .map(|(_, pad)| Vec2::new(pad.value(gilrs::Axis::LeftStickX), -pad.value(gilrs::Axis::LeftStickY)))
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or(Vec2::ZERO);
+        if deflection.length() > STICK_DEADZONE {
+            self.pointer = (self.pointer + deflection * STICK_SPEED).clamp(Vec2::ZERO, self.extent);
+            self.steering = true;
+            map.pointer_events(Binding::GamepadStick, self.pointer, out);
+        } else if std::mem::take(&mut self.steering) {
+            map.pointer_released(Binding::GamepadStick, out);
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn gamepad_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button;
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
@@ -0,0 +1,87 @@
+use crate::input::ActionEvent;
+use crate::world::{ParticleSystem, SimParams};
+use glam::Vec2;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Entries kept unless the config says otherwise.
+pub const DEFAULT_JOURNAL_ENTRIES: usize = 1024;
+
+/// Something that changed the simulation from outside.
+#[derive(Clone, Debug, Serialize)]
+pub enum JournalEvent {
+    Action(ActionEvent),
+    /// Accelerometer reading from the sensor board.
+    Sensor(Vec2),
+    /// Simulation parameters replaced, e.g. by pasting a scene.
+    Params(Box<SimParams>),
+    /// An error or other notable condition.
+    Note(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JournalEntry {
+    /// Wall-clock time in ms.
+    pub time_ms: f64,
+    /// Simulation time of the first view when it happened.
+    pub sim_time: f32,
+    pub event: JournalEvent,
+}
+
+/// Ring buffer of the most recent inputs, sensor readings and parameter changes.
+///
+/// Cheap enough to always run; `dump` turns it into a JSON trace to attach to bug reports,
+/// together with the seed and parameters needed to reproduce the run.
+pub struct Journal {
+    entries: VecDeque<JournalEntry>,
+    max_entries: usize,
+}
+
+#[derive(Serialize)]
+struct Dump<'a> {
+    seed: u64,
+    sim_time: f32,
+    live: usize,
+    params: &'a SimParams,
+    events: &'a VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(max_entries),
+            max_entries,
+        }
+    }
+
+    /// Append an event, dropping the oldest once full.
+    pub fn record(&mut self, time_ms: f64, particles: &ParticleSystem, event: JournalEvent) {
+        if self.max_entries == 0 {
+            return;
+        }
+        if self.entries.len() == self.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(JournalEntry {
+            time_ms,
+            sim_time: particles.time(),
+            event,
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
+
+    /// The recorded events with the current seed, time and parameters of `particles`, as JSON.
+    pub fn dump(&self, particles: &ParticleSystem) -> String {
+        let dump = Dump {
+            seed: particles.seed(),
+            sim_time: particles.time(),
+            live: particles.live_count(),
+            params: &particles.simulation,
+            events: &self.entries,
+        };
+        serde_json::to_string_pretty(&dump).unwrap_or_default()
+    }
+}
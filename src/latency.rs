@@ -0,0 +1,106 @@
+use crate::format::NumberFormat;
+use std::collections::VecDeque;
+
+/// Samples in the rolling estimate.
+pub const LATENCY_WINDOW: usize = 60;
+
+/// Milliseconds between rolling estimate reports.
+pub const REPORT_INTERVAL_MS: f64 = 500.0;
+
+/// Latency of one sensor reading through the pipeline, in milliseconds.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stages {
+    /// Sensor timestamp to receipt; needs the sender's clock in sync with ours.
+    pub transport: Option<f64>,
+    /// Receipt to the first simulation step using the reading.
+    pub apply: f64,
+    /// That step to the frame showing its result being presented.
+    pub present: f64,
+}
+
+impl Stages {
+    /// Receipt to presentation, plus transport when known.
+    pub fn total(&self) -> f64 {
+        self.transport.unwrap_or(0.0) + self.apply + self.present
+    }
+}
+
+/// Measures sensor → force applied → frame presented latency.
+///
+/// Call `received` when a reading arrives, `applied` after stepping and `presented`
+/// after a frame is presented; only the newest reading in flight is tracked.
+#[derive(Default)]
+pub struct LatencyMeter {
+    sensor_ms: Option<f64>,
+    received_ms: Option<f64>,
+    applied_ms: Option<f64>,
+    samples: VecDeque<Stages>,
+    last_report_ms: f64,
+}
+
+impl LatencyMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn received(&mut self, sensor_ms: Option<f64>, now_ms: f64) {
+        // a reading not yet applied is superseded
+        if self.applied_ms.is_none() {
+            self.sensor_ms = sensor_ms;
+            self.received_ms = Some(now_ms);
+        }
+    }
+
+    pub fn applied(&mut self, now_ms: f64) {
+        if self.received_ms.is_some() && self.applied_ms.is_none() {
+            self.applied_ms = Some(now_ms);
+        }
+    }
+
+    pub fn presented(&mut self, now_ms: f64) {
+        let (Some(received), Some(applied)) = (self.received_ms, self.applied_ms) else {
+            return;
+        };
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Stages {
+            transport: self.sensor_ms.map(|sensor| received - sensor),
+            apply: applied - received,
+            present: now_ms - applied,
+        });
+        self.sensor_ms = None;
+        self.received_ms = None;
+        self.applied_ms = None;
+    }
+
+    /// Mean of the recent samples; transport only if every sample has it.
+    pub fn mean(&self) -> Option<Stages> {
+        let n = self.samples.len() as f64;
+        if n == 0.0 {
+            return None;
+        }
+        let transport: Option<f64> = self.samples.iter().map(|s| s.transport).sum();
+        Some(Stages {
+            transport: transport.map(|sum| sum / n),
+            apply: self.samples.iter().map(|s| s.apply).sum::<f64>() / n,
+            present: self.samples.iter().map(|s| s.present).sum::<f64>() / n,
+        })
+    }
+
+    /// Rolling estimate as text in `format`, at most every `REPORT_INTERVAL_MS`.
+    pub fn report(&mut self, now_ms: f64, format: &NumberFormat) -> Option<String> {
+        if now_ms - self.last_report_ms < REPORT_INTERVAL_MS {
+            return None;
+        }
+        let mean = self.mean()?;
+        self.last_report_ms = now_ms;
+        let transport = mean.transport.map_or("?".to_string(), |ms| format.fixed(ms, 1));
+        Some(format!(
+            "latency {} ms (transport {transport}, apply {}, present {})",
+            format.fixed(mean.total(), 1),
+            format.fixed(mean.apply, 1),
+            format.fixed(mean.present, 1)
+        ))
+    }
+}
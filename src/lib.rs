@@ -4,16 +4,87 @@
 use error_iter::ErrorIter as _;
 use log::error;
 use pixels::{PixelsBuilder, SurfaceTexture};
+use glam::Vec2;
+use std::collections::BTreeMap;
+#[cfg(target_arch = "wasm32")]
 use std::rc::Rc;
+use std::sync::Arc;
 use winit::dpi::LogicalSize;
-use winit::event::{Event, WindowEvent};
-use winit::event_loop::EventLoop;
-use winit::keyboard::KeyCode;
-use winit::window::WindowBuilder;
+use winit::event::{Event, TouchPhase, WindowEvent};
+use winit::event_loop::{EventLoop, EventLoopWindowTarget};
+use winit::window::{Window, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 
+pub mod aggregation;
+pub mod arena;
+pub mod backend;
+pub mod batch;
+pub mod clipboard;
+pub mod clock;
+pub mod colormap;
+pub mod config;
+pub mod constraint;
+pub mod decal;
+pub mod digest;
+pub mod distribution;
+pub mod editor;
+pub mod emitter;
+pub mod flow;
+pub mod fluid;
+pub mod footprint;
+pub mod force;
+pub mod format;
+pub mod gesture;
+pub mod gpu_heatmap;
+pub mod gpu_sim;
+pub mod group;
+pub mod impacts;
+pub mod input;
+pub mod integrator;
+pub mod journal;
+pub mod latency;
+pub mod life;
+pub mod memory;
+pub mod noise;
+pub mod obstacle;
+pub mod optimize;
+pub mod png;
+pub mod ramp;
+pub mod reckoning;
+pub mod replay;
+pub mod rewind;
+pub mod rules;
+pub mod scene;
+pub mod sdf;
+pub mod sensor;
+pub mod share;
+pub mod spatial;
+pub mod swarm;
+pub mod sweep;
+pub mod trace;
+pub mod tunnel;
+pub mod validate;
+pub mod vector_field;
+pub mod wetness;
 pub mod world;
-pub use world::{ParticleSystem, Renderer};
+pub use backend::{BackendKind, SimBackend};
+pub use batch::{Batch, BatchStats};
+pub use clipboard::Clipboard;
+pub use clock::{FixedTimestep, FrameTimer, StepClock};
+pub use config::Config;
+pub use gesture::Gestures;
+pub use gpu_heatmap::HeatmapPass;
+pub use editor::ObstacleEditor;
+pub use gpu_sim::GpuSimulation;
+pub use input::{Action, ActionEvent, Binding, GamepadButton, InputMap};
+pub use journal::{Journal, JournalEvent};
+pub use memory::{MemoryBudget, MemoryReport};
+pub use ramp::RampScheduler;
+pub use replay::Replay;
+pub use rewind::RewindBuffer;
+pub use scene::Scene;
+pub use trace::PathTracer;
+pub use world::{FrameView, ParticleSystem, Renderer};
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*; // at top, gated only for wasm32
@@ -26,26 +97,294 @@ pub fn wasm_start() {
 }
 
 
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    /// Wakes the page's event loop, which all instances share since winit allows one per page.
+    static WEB_LOOP: std::cell::RefCell<Option<winit::event_loop::EventLoopProxy<()>>> = const { std::cell::RefCell::new(None) };
+    /// Instances waiting for the event loop to create their window.
+    static PENDING: std::cell::RefCell<Vec<(Config, web_sys::HtmlCanvasElement, Rc<Bridge>)>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// Instances whose window exists but whose GPU setup is still running.
+    static STARTING: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    /// Running instances with the window they draw to.
+    static INSTANCES: std::cell::RefCell<Vec<(winit::window::WindowId, Instance)>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// The instance on the page's `#pixels-canvas`, driven by the module-level functions.
+    static DEFAULT_INSTANCE: std::cell::RefCell<Option<Rc<Bridge>>> = const { std::cell::RefCell::new(None) };
+}
+
+/// State shared between a running instance and JavaScript.
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+struct Bridge {
+    /// Actions sent from JavaScript, drained by the instance.
+    actions: std::cell::RefCell<Vec<ActionEvent>>,
+    /// Latest scene of the simulation, for share URLs.
+    scene: std::cell::RefCell<Option<Scene>>,
+    /// Address and length of the last presented frame in linear memory.
+    frame: std::cell::Cell<(usize, usize)>,
+    /// Set by `destroy`; the instance shuts down on its next event.
+    destroyed: std::cell::Cell<bool>,
+    /// `preset` query parameter of share URLs, see `share::preset_query`.
+    preset: Option<String>,
+    /// Started on `#pixels-canvas`: reads a shared scene from the URL and fills in the
+    /// page's stats elements.
+    owns_page: bool,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Bridge {
+    fn send_action(&self, name: &str) -> Result<(), JsValue> {
+        let action: Action = serde_json::from_value(serde_json::Value::String(name.to_owned()))
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.actions.borrow_mut().push(ActionEvent::Pressed(action));
+        Ok(())
+    }
+
+    fn share_url(&self, full_scene: bool) -> Option<String> {
+        let scene = self.scene.borrow().clone()?;
+        let location = web_sys::window()?.location();
+        let mut url = format!(
+            "{}{}?{}",
+            location.origin().ok()?,
+            location.pathname().ok()?,
+            share::to_query(&scene)
+        );
+        if let Some(preset) = &self.preset {
+            url.push('&');
+            url.push_str(preset);
+        }
+        if full_scene {
+            let json = serde_json::to_string(&scene).ok()?;
+            url.push_str("#scene=");
+            url.push_str(&share::encode_payload(&json));
+        }
+        Some(url)
+    }
+
+    fn frame_view(&self) -> Option<js_sys::Uint8Array> {
+        let (address, len) = self.frame.get();
+        if len == 0 {
+            return None;
+        }
+        let memory = wasm_bindgen::memory().dyn_into::<js_sys::WebAssembly::Memory>().ok()?;
+        Some(js_sys::Uint8Array::new_with_byte_offset_and_length(&memory.buffer(), address as u32, len as u32))
+    }
+
+    fn destroy(&self) {
+        self.destroyed.set(true);
+        self.frame.set((0, 0));
+        wake_web_loop();
+    }
+
+    /// Set the text of a page element, if this instance owns the page.
+    fn page_text(&self, id: &str, text: &str) {
+        if self.owns_page {
+            set_id_text(id, text);
+        }
+    }
+}
+
+/// A simulation started by `embed`, with its own canvas, config and state.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub struct WavesHandle {
+    bridge: Rc<Bridge>,
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl WavesHandle {
+    /// Trigger an action by name, e.g. `handle.send_action("Pause")`.
+    pub fn send_action(&self, name: &str) -> Result<(), JsValue> {
+        self.bridge.send_action(name)
+    }
+
+    /// See `get_share_url`.
+    pub fn share_url(&self, full_scene: bool) -> Option<String> {
+        self.bridge.share_url(full_scene)
+    }
+
+    /// The last presented RGBA frame, see `frame_view`.
+    pub fn frame_view(&self) -> Option<js_sys::Uint8Array> {
+        self.bridge.frame_view()
+    }
+
+    /// Stop the instance and release its GPU resources; see the module-level `destroy`.
+    pub fn destroy(&self) {
+        self.bridge.destroy();
+    }
+}
+
+/// Stop the `#pixels-canvas` instance: flush its metric logs and release its GPU
+/// resources. The event loop stops once no instance is left, and `embed` starts afresh.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn destroy() {
+    if let Some(bridge) = DEFAULT_INSTANCE.with(|default| default.borrow_mut().take()) {
+        bridge.destroy();
+    }
+}
+
+/// The last presented RGBA frame of the `#pixels-canvas` instance as a view into wasm
+/// memory, `WIDTH` pixels per row (stride `frame_stride()` bytes), without copying. Views
+/// are invalidated when the memory grows, so read it right away rather than keeping it.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn frame_view() -> Option<js_sys::Uint8Array> {
+    DEFAULT_INSTANCE.with(|bridge| bridge.borrow().as_ref()?.frame_view())
+}
+
+/// Bytes per row of `frame_view`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn frame_stride() -> u32 {
+    WIDTH * 4
+}
+
+/// Trigger an action of the `#pixels-canvas` instance by name, e.g. `send_action("Pause")`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn send_action(name: &str) -> Result<(), JsValue> {
+    DEFAULT_INSTANCE.with(|bridge| match bridge.borrow().as_ref() {
+        Some(bridge) => bridge.send_action(name),
+        None => Err(JsValue::from_str("send_action: the page has no #pixels-canvas")),
+    })
+}
+
+/// Build a URL reproducing the setup of the `#pixels-canvas` instance from the seed and
+/// key parameters. With `full_scene`, the complete scene is also stored in the fragment.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn get_share_url(full_scene: bool) -> Option<String> {
+    DEFAULT_INSTANCE.with(|bridge| bridge.borrow().as_ref()?.share_url(full_scene))
+}
+
 pub const WIDTH: u32 = 640;
 pub const HEIGHT: u32 = 480;
+pub const INITIAL_PARTICLES: usize = 10000;
+/// Longest replay kept for the ghost overlay, in frames.
+pub const MAX_REPLAY_FRAMES: usize = 600;
+/// Simulation steps between rewind snapshots.
+pub const REWIND_INTERVAL: usize = 4;
+/// Simulation acceleration per m/s² measured by a connected sensor.
+pub const SENSOR_ACCEL_SCALE: f32 = 0.05;
+/// How close the cursor must be to a particle to start tracing it.
+pub const TRACE_PICK_RADIUS: f32 = 20.0;
 
-/// Representation of the application state. In this example, a box will bounce around the screen.
-
-fn main() {
+/// Set up logging and run the app. On the web this is called by `wasm_start` and
+/// takes over the page's `#pixels-canvas`, if it has one; other pages call `embed`.
+pub fn main() {
     #[cfg(target_arch = "wasm32")]
     {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
         console_log::init_with_level(log::Level::Trace).expect("error initializing logger");
 
-        wasm_bindgen_futures::spawn_local(run());
+        if let Some(canvas) = find_canvas(&JsValue::from_str("#pixels-canvas")) {
+            let bridge = launch(canvas, Config::load(), true);
+            DEFAULT_INSTANCE.with(|default| *default.borrow_mut() = Some(bridge));
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
         env_logger::init();
 
-        pollster::block_on(run());
+        pollster::block_on(run(Config::load()));
+    }
+}
+
+/// Run a simulation in `target`, a canvas element or a CSS selector for one.
+/// `options` takes the keys of the native config file (e.g. `{ lifetime: 300 }`);
+/// `undefined` uses the defaults. Each call starts an independent instance.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn embed(target: JsValue, options: JsValue) -> Result<WavesHandle, JsValue> {
+    let canvas = find_canvas(&target).ok_or_else(|| JsValue::from_str("embed: target is not a canvas"))?;
+    let config = if options.is_undefined() || options.is_null() {
+        Config::default()
+    } else {
+        let json = String::from(js_sys::JSON::stringify(&options)?);
+        Config::from_json(&json).map_err(|err| JsValue::from_str(&format!("embed: invalid options: {err}")))?
+    };
+    Ok(WavesHandle { bridge: launch(canvas, config, false) })
+}
+
+/// Queue an instance on `canvas` and wake the event loop, starting it if needed.
+#[cfg(target_arch = "wasm32")]
+fn launch(canvas: web_sys::HtmlCanvasElement, config: Config, owns_page: bool) -> Rc<Bridge> {
+    let preset = share::preset_query(&config);
+    let bridge = Rc::new(Bridge { owns_page, preset, ..Bridge::default() });
+    PENDING.with(|pending| pending.borrow_mut().push((config, canvas, Rc::clone(&bridge))));
+    WEB_LOOP.with(|proxy| {
+        proxy.borrow_mut().get_or_insert_with(spawn_web_loop);
+    });
+    wake_web_loop();
+    bridge
+}
+
+/// Send the event loop an event so it handles pending and destroyed instances now.
+#[cfg(target_arch = "wasm32")]
+fn wake_web_loop() {
+    WEB_LOOP.with(|proxy| {
+        if let Some(proxy) = proxy.borrow().as_ref() {
+            if proxy.send_event(()).is_err() {
+                log::warn!("event loop has exited");
+            }
+        }
+    });
+}
+
+/// Start the page's event loop: create the windows of pending instances and pass each
+/// instance the events of its window plus the loop-wide ones. Exits once the last
+/// instance has shut down, so a later `launch` spawns a new loop.
+#[cfg(target_arch = "wasm32")]
+fn spawn_web_loop() -> winit::event_loop::EventLoopProxy<()> {
+    use winit::platform::web::EventLoopExtWebSys;
+
+    let event_loop = EventLoop::new().unwrap();
+    let proxy = event_loop.create_proxy();
+    event_loop.spawn(|event, elwt| {
+        for (config, canvas, bridge) in PENDING.with(|pending| std::mem::take(&mut *pending.borrow_mut())) {
+            let window = build_window(elwt, canvas);
+            STARTING.with(|starting| starting.set(starting.get() + 1));
+            wasm_bindgen_futures::spawn_local(async move {
+                let id = window.id();
+                let instance = start(window, config, bridge).await;
+                INSTANCES.with(|instances| instances.borrow_mut().push((id, instance)));
+                STARTING.with(|starting| starting.set(starting.get() - 1));
+                wake_web_loop();
+            });
+        }
+        let target = match &event {
+            Event::WindowEvent { window_id, .. } => Some(*window_id),
+            _ => None,
+        };
+        // instances started while dispatching are appended after the running ones
+        let mut running = INSTANCES.with(|instances| std::mem::take(&mut *instances.borrow_mut()));
+        running.retain_mut(|(id, instance)| target.is_some_and(|target| target != *id) || instance(event.clone()));
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            running.append(&mut instances);
+            *instances = running;
+        });
+        let idle = INSTANCES.with(|instances| instances.borrow().is_empty())
+            && PENDING.with(|pending| pending.borrow().is_empty())
+            && STARTING.with(std::cell::Cell::get) == 0;
+        if idle {
+            WEB_LOOP.with(|proxy| proxy.borrow_mut().take());
+            elwt.exit();
+        }
+    });
+    proxy
+}
+
+/// The canvas `target` refers to, either directly or as a selector.
+#[cfg(target_arch = "wasm32")]
+fn find_canvas(target: &JsValue) -> Option<web_sys::HtmlCanvasElement> {
+    if let Some(selector) = target.as_string() {
+        let element = web_sys::window()?.document()?.query_selector(&selector).ok()??;
+        return element.dyn_into().ok();
     }
+    target.clone().dyn_into().ok()
 }
 
 // dynamic window size retrieval for wasm32 targets
@@ -61,39 +400,65 @@ fn get_window_size() -> LogicalSize<f64> {
 }
 */
 
-async fn run() {
-    let event_loop = EventLoop::new().unwrap();
-    let window = {
-        let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
-        WindowBuilder::new()
-            .with_title("Hello Pixels + Web")
-            .with_inner_size(size)
-            .with_min_inner_size(size)
-            .build(&event_loop)
-            .expect("WindowBuilder error")
-    };
-
-    let window = Rc::new(window);
+/// A running simulation, fed the events of its window. Returns `false` once it has quit.
+type Instance = Box<dyn FnMut(Event<()>) -> bool>;
 
+fn build_window(
+    target: &EventLoopWindowTarget<()>,
+    #[cfg(target_arch = "wasm32")] canvas: web_sys::HtmlCanvasElement,
+) -> Arc<Window> {
+    let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+    let builder = WindowBuilder::new()
+        .with_title("Hello Pixels + Web")
+        .with_inner_size(size)
+        .with_min_inner_size(size);
     #[cfg(target_arch = "wasm32")]
-    {
-        //use wasm_bindgen::JsCast;
-        use winit::platform::web::WindowExtWebSys;
+    let builder = winit::platform::web::WindowBuilderExtWebSys::with_canvas(builder, Some(canvas));
+    Arc::new(builder.build(target).expect("WindowBuilder error"))
+}
 
-        // Attach winit canvas to body element
-        web_sys::window()
-            .and_then(|win| win.document())
-            .and_then(|doc| doc.get_element_by_id("pixels-canvas"))
-            .and_then(|old_canvas| {
-                old_canvas.replace_with_with_node_1(&web_sys::Element::from(window.canvas().unwrap()))
-                    .ok()
-            })
-            .expect("couldn't append canvas to document body");
+#[cfg(not(target_arch = "wasm32"))]
+async fn run(config: Config) {
+    let event_loop = EventLoop::new().unwrap();
+    let _ = NATIVE_LOOP.set(std::sync::Mutex::new(event_loop.create_proxy()));
+    let window = build_window(&event_loop);
+    let mut instance = Some(start(window, config).await);
+    let res = event_loop.run(move |event, elwt| {
+        // the instance shuts down once, on quit or when the loop exits for another reason
+        if instance.as_mut().is_some_and(|instance| !instance(event)) {
+            instance = None;
+            elwt.exit();
+        }
+    });
+    res.unwrap();
+}
 
-        let winit_canvas = window.canvas().unwrap();
-            winit_canvas.set_class_name("pixels-surface");
+/// Wakes the native event loop, e.g. for `shutdown`.
+#[cfg(not(target_arch = "wasm32"))]
+static NATIVE_LOOP: std::sync::OnceLock<std::sync::Mutex<winit::event_loop::EventLoopProxy<()>>> =
+    std::sync::OnceLock::new();
+/// Set by `shutdown`; the native instance shuts down on its next event.
+#[cfg(not(target_arch = "wasm32"))]
+static SHUTDOWN_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
+/// Stop the native app, e.g. from a signal handler or another thread: it flushes its
+/// metric logs, disconnects the sensor, releases its GPU resources and `main` returns.
+/// The native counterpart of the web `destroy`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn shutdown() {
+    SHUTDOWN_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+    if let Some(proxy) = NATIVE_LOOP.get().and_then(|proxy| proxy.lock().ok()) {
+        if proxy.send_event(()).is_err() {
+            log::warn!("event loop has exited");
+        }
+    }
+}
+
+/// Set up the simulation drawing to `window`.
+async fn start(window: Arc<Window>, config: Config, #[cfg(target_arch = "wasm32")] bridge: Rc<Bridge>) -> Instance {
 
+    #[cfg(target_arch = "wasm32")]
+    {
         let _ = window.request_inner_size(LogicalSize::new(WIDTH as f64, HEIGHT as f64));
         // dynamic resize handling for browser client
         /*
@@ -126,7 +491,7 @@ async fn run() {
         let window_size = winit::dpi::PhysicalSize::new(WIDTH, HEIGHT);
 
         let surface_texture =
-            SurfaceTexture::new(window_size.width, window_size.height, window.as_ref());
+            SurfaceTexture::new(window_size.width, window_size.height, Arc::clone(&window));
         let builder = PixelsBuilder::new(WIDTH, HEIGHT, surface_texture);
 
         #[cfg(target_arch = "wasm32")]
@@ -140,29 +505,229 @@ async fn run() {
 
         builder.build_async().await.expect("Pixels error")
     };
-    let mut particles = ParticleSystem::new(10000, WIDTH as usize, HEIGHT as usize);
-    let mut renderer = Renderer::new(WIDTH as usize, HEIGHT as usize);
-    for _ in 0..10000 {
-        particles.spawn_random(1.0, 1.0);
+    let lifetime = config.particle_lifetime();
+    let initial_particles = config.initial_particles();
+    let emitters = config.particle_emitters();
+    if !config.validate_dts.is_empty() {
+        validate::log_report(&validate::run_all(&config.validate_dts));
+    }
+    let input_map = config.input;
+    let mut actions = Vec::new();
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = input::Gamepads::new(WIDTH as f32, HEIGHT as f32);
+    let mut paused = false;
+    let clipboard = Clipboard::new();
+    let mut replay = load_replay(config.replay_path.as_deref());
+    let mut recording = false;
+    let mut ghost_frame: Option<usize> = None;
+    let mut ramp = config.ramp.clone().map(RampScheduler::new);
+    let mut step_clock = match &config.fixed_timestep {
+        Some(timestep) => StepClock::accumulated(FixedTimestep::new(timestep.hz)),
+        None => config.sim_rate_hz.map_or(StepClock::PerFrame, StepClock::fixed_rate),
+    };
+    let interpolate = config.fixed_timestep.as_ref().is_some_and(|timestep| timestep.interpolate);
+    // Real-time dt only applies when stepping once per frame
+    let mut frame_timer = config
+        .frame_timer
+        .clone()
+        .filter(|_| config.sim_rate_hz.is_none() && config.fixed_timestep.is_none())
+        .map(FrameTimer::new);
+    let mut capacity = ramp.as_ref().map_or(0, RampScheduler::max_level).max(INITIAL_PARTICLES);
+
+    // One system, or two side by side sharing seed and input when comparing parameters
+    let view_count = if config.compare.is_some() { 2 } else { 1 };
+    let view_width = WIDTH as usize / view_count;
+    let mut renderers: Vec<Renderer> = (0..view_count)
+        .map(|_| Renderer::new(view_width, HEIGHT as usize))
+        .collect();
+    if let Some(budget) = &config.memory_budget {
+        let frame_bytes = (WIDTH * HEIGHT * 4) as usize;
+        let fixed = frame_bytes + renderers.iter().map(Renderer::memory_bytes).sum::<usize>();
+        let max = budget.max_particles(fixed, view_count);
+        if max < capacity {
+            log::warn!("memory budget limits each view to {max} particles instead of {capacity}");
+            capacity = max;
+        }
+    }
+    let mut systems: Vec<ParticleSystem> = (0..view_count)
+        .map(|_| ParticleSystem::new(capacity, view_width, HEIGHT as usize))
+        .collect();
+    let mut heatmap_pass = config
+        .gpu_heatmap
+        .as_ref()
+        .map(|heatmap| HeatmapPass::new(&pixels, heatmap, view_count));
+    for renderer in &mut renderers {
+        renderer.set_heat_glow(config.heat_glow);
+        renderer.set_fade_out(config.fade_out);
+        renderer.set_theme(config.theme);
+        renderer.set_gpu_heatmap(heatmap_pass.is_some());
+        for (&emitter, chain) in &config.post_routes {
+            renderer.route_emitter(emitter, chain.clone());
+        }
+        for (&emitter, gradient) in &config.color_gradients {
+            renderer.set_color_gradient(emitter, gradient.clone());
+        }
+    }
+    if let Some(scene) = load_scene(config.scene_path.as_deref()) {
+        scene.apply(&mut systems[0]);
+    }
+    // after the scene, which would replace the tunnel's source, sink and obstacle
+    if let Some(tunnel) = &config.wind_tunnel {
+        for particles in &mut systems {
+            tunnel.apply(particles);
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    if bridge.owns_page {
+        apply_location(&mut systems[0]);
+    }
+    let seed = systems[0].seed();
+    if let Some(params) = &config.compare {
+        systems[1].simulation = params.clone();
+    }
+    let steps_per_second = config
+        .fixed_timestep
+        .as_ref()
+        .map(|timestep| timestep.hz)
+        .or(config.sim_rate_hz)
+        .unwrap_or(60.0) as f32;
+    let mut rewind: Vec<RewindBuffer> = (0..view_count)
+        .map(|_| RewindBuffer::new(config.rewind_seconds, steps_per_second, REWIND_INTERVAL))
+        .collect();
+    if let Some(budget) = &config.memory_budget {
+        // Split evenly between the rewind buffers and the replay
+        let share = budget.history_bytes() / (view_count + 1);
+        for history in &mut rewind {
+            history.limit_bytes(share, world::Snapshot::bytes_for(capacity));
+        }
+        replay.limit_bytes(share, capacity * std::mem::size_of::<Vec2>());
+    }
+    let mut rewinding = false;
+    let mut editor = ObstacleEditor::default();
+    // Cursor in view coordinates, for obstacle editing
+    let mut cursor = Vec2::ZERO;
+    // Wall grids are only copied to the other views when they change
+    let mut walls_changed = true;
+    let mut tracer = PathTracer::new();
+    tracer.set_palette(config.theme.palette);
+    if let Some(pass) = &mut heatmap_pass {
+        pass.set_palette(config.theme.palette);
+    }
+    // Active touch points by id, each with its own attractor
+    let mut touches: BTreeMap<u64, Vec2> = BTreeMap::new();
+    let mut gestures = Gestures::new(load_macros(config.macros_path.as_deref()));
+    let mut journal = Journal::new(config.journal_entries);
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut sensor = config.sensor_addr.as_deref().and_then(|addr| {
+        sensor::TcpSensor::connect(addr)
+            .map_err(|err| log::warn!("cannot connect to sensor at {addr}: {err}"))
+            .ok()
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut packets = Vec::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut sensor_time = None;
+    // Latest timestamp of the sensor, kept for the dead reckoner
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut sensor_clock = None;
+    // The dead reckoner sticks to the clock of its first reading, the sensor's when the
+    // stream carries timestamps and the page's otherwise, so it never mixes the two
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut reckon_on_sensor_clock = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut latency = config.measure_latency.then(latency::LatencyMeter::new);
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut reckoner = config.dead_reckoning.clone().map(reckoning::DeadReckoner::new);
+    let flow_field = config.flow_field.as_ref().and_then(|flow| load_flow_field(flow, view_width, HEIGHT as usize));
+    for particles in &mut systems {
+        particles.reseed(seed);
+        particles.spawn_velocity = config.spawn_velocity;
+        particles.groups.clone_from(&config.groups);
+        particles.flow_field.clone_from(&flow_field);
+        particles.decals = config.decals.map(|decals| decal::DecalLayer::new(view_width, HEIGHT as usize, decals));
+        particles.wetness = config.wetness.map(|wetness| wetness::Wetness::new(view_width, wetness));
+        particles.fluid = config.fluid.map(|fluid| fluid::FluidGrid::new(view_width, HEIGHT as usize, fluid));
+        particles.aggregate = config.aggregation.clone().map(|aggregation| aggregation::Aggregate::new(view_width, HEIGHT as usize, aggregation));
+        particles.life = config.life.clone().map(|life| {
+            let mut layer = life::LifeLayer::new(view_width, HEIGHT as usize, life);
+            layer.randomize(particles.rng());
+            layer
+        });
+        for emitter in emitters.iter().cloned() {
+            particles.add_emitter(emitter);
+        }
+        for _ in 0..initial_particles {
+            particles.spawn_random(1.0, lifetime);
+        }
+        // Start presenting (and benchmarking) from a settled state
+        for _ in 0..config.warmup_steps {
+            particles.update();
+        }
     }
+    log::info!("memory: {}", MemoryReport::measure(&systems, &renderers, &rewind, &replay));
     
     #[cfg(target_arch = "wasm32")]
     let mut frame_count = 0u32;
     #[cfg(target_arch = "wasm32")]
     let mut last_fps_update = get_time_ms();
 
-    let res = event_loop.run(|event, elwt| {
+    Box::new(move |event| {
+        let mut open = true;
         match event {
             Event::WindowEvent {
                 event: WindowEvent::RedrawRequested,
                 ..
             } => {
+                gestures.tick();
+                let ghost = ghost_frame.map_or(&[][..], |frame| replay.frame(frame));
+                for renderer in &mut renderers {
+                    renderer.set_ghost(ghost);
+                }
+
                 // Draw the current frame
-                renderer.draw(pixels.frame_mut(), &particles);
-                if let Err(err) = pixels.render() {
+                if let [particles] = systems.as_slice() {
+                    renderers[0].draw(pixels.frame_mut(), particles);
+                } else {
+                    for (view, (renderer, particles)) in renderers.iter_mut().zip(&systems).enumerate() {
+                        renderer.draw_into(pixels.frame_mut(), WIDTH as usize, view * view_width, particles);
+                    }
+                }
+                // Traces follow particles of the first view
+                tracer.draw(pixels.frame_mut(), WIDTH as usize);
+                let rendered = match &mut heatmap_pass {
+                    Some(pass) => {
+                        for (view, renderer) in renderers.iter().enumerate() {
+                            pass.upload(&pixels, view, renderer.heat_grid());
+                        }
+                        pixels.render_with(|encoder, target, context| {
+                            context.scaling_renderer.render(encoder, target);
+                            pass.render(encoder, target, context.scaling_renderer.clip_rect());
+                            Ok(())
+                        })
+                    }
+                    None => pixels.render(),
+                };
+                if let Err(err) = rendered {
+                    journal.record(get_time_ms(), &systems[0], JournalEvent::Note(format!("pixels.render() failed: {err}")));
                     log_error("pixels.render", err);
-                    elwt.exit();
-                    return;
+                    error!("recent events: {}", journal.dump(&systems[0]));
+                    shut_down(
+                        &mut ramp,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        &mut latency,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        &mut sensor,
+                        &config.number_format,
+                    );
+                    return false;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(meter) = &mut latency {
+                    let now = get_time_ms();
+                    meter.presented(now);
+                    if let Some(report) = meter.report(now, &config.number_format) {
+                        window.set_title(&report);
+                    }
                 }
 
                #[cfg(target_arch = "wasm32")]
@@ -175,17 +740,108 @@ async fn run() {
                     if elapsed >= 250.0 {
                         let fps = (frame_count as f64 * 1000.0) / elapsed;
                         let used_mb = get_memory_usage_mb();
-                        update_stats(particles.count, fps as f32, used_mb);
+                        let live = systems.iter().map(ParticleSystem::live_count).sum();
+                        if bridge.owns_page {
+                            update_stats(live, fps as f32, used_mb, &config.number_format);
+                        }
                         frame_count = 0;
                         last_fps_update = now;
                     }
+                    *bridge.scene.borrow_mut() = Some(Scene::capture(&systems[0]));
+                    let frame = pixels.frame();
+                    bridge.frame.set((frame.as_ptr() as usize, frame.len()));
                 }
 
-                particles.spawn_random(1.0, 1.0);
+                // The board's tilt drives the particles like the touch wind
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(link) = &mut sensor {
+                    let connected = link.poll(&mut packets);
+                    // only the latest reading matters
+                    let mut accel = None;
+                    for packet in packets.drain(..) {
+                        match packet {
+                            sensor::Packet::Time(micros) => {
+                                sensor_time = Some(micros as f64 / 1000.0);
+                                sensor_clock = sensor_time;
+                            }
+                            sensor::Packet::Accel([x, y, _]) => {
+                                accel = Some(Vec2::new(x, y));
+                                if let Some(reckoner) = &mut reckoner {
+                                    let on_sensor_clock = *reckon_on_sensor_clock.get_or_insert(sensor_clock.is_some());
+                                    let time = sensor_clock.filter(|_| on_sensor_clock).unwrap_or_else(get_time_ms);
+                                    reckoner.sample(Vec2::new(x, y), time);
+                                }
+                            }
+                        }
+                    }
+                    if let Some(accel) = accel {
+                        journal.record(get_time_ms(), &systems[0], JournalEvent::Sensor(accel));
+                        // moving the board drags a virtual magnet, or tilting it pushes everything
+                        let center = Vec2::new(view_width as f32 / 2.0, HEIGHT as f32 / 2.0);
+                        for particles in &mut systems {
+                            match &reckoner {
+                                Some(reckoner) => {
+                                    particles.pointer_attractor = Some(world::Attractor::interactive(reckoner.position(center)));
+                                }
+                                None => particles.simulation.acceleration = accel * SENSOR_ACCEL_SCALE,
+                            }
+                        }
+                        if let Some(meter) = &mut latency {
+                            meter.received(sensor_time.take(), get_time_ms());
+                        }
+                    }
+                    if !connected {
+                        journal.record(get_time_ms(), &systems[0], JournalEvent::Note("sensor disconnected".into()));
+                        log::warn!("sensor disconnected");
+                        sensor = None;
+                    }
+                }
 
+                // A running ramp sets the population instead of the steady trickle
+                let now = get_time_ms();
+                let ramp_target = ramp.as_mut().and_then(|ramp| ramp.frame(now));
+                let steps = step_clock.take_steps(now);
+                let dt_scale = frame_timer.as_mut().map_or(1.0, |timer| timer.tick_scale(now));
 
-                // Update internal state and request a redraw
-                particles.update();
+                if rewinding {
+                    for (particles, history) in systems.iter_mut().zip(&mut rewind) {
+                        particles.set_interpolation(1.0);
+                        history.rewind(particles);
+                    }
+                } else if !paused {
+                    if recording {
+                        replay.record(&systems[0]);
+                    }
+                    if let Some(frame) = &mut ghost_frame {
+                        *frame += 1;
+                    }
+                    let alpha = if interpolate { step_clock.alpha() } else { 1.0 };
+                    for (view, (particles, history)) in systems.iter_mut().zip(&mut rewind).enumerate() {
+                        particles.set_interpolation(alpha);
+                        // the ramp sets the population directly
+                        particles.pause_emitters(ramp_target.is_some());
+                        if let Some(target) = ramp_target {
+                            particles.limit_live(target);
+                            for _ in particles.live_count()..target {
+                                particles.spawn_random(1.0, lifetime);
+                            }
+                        }
+                        for _ in 0..steps {
+                            // Update internal state
+                            particles.update_with_dt(particles.simulation.dt * dt_scale);
+                            if config.rewind_seconds > 0.0 {
+                                history.record(particles);
+                            }
+                            if view == 0 {
+                                tracer.record(particles);
+                            }
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(meter) = latency.as_mut().filter(|_| steps > 0) {
+                        meter.applied(get_time_ms());
+                    }
+                }
                 window.request_redraw();
             }
             Event::WindowEvent {
@@ -194,76 +850,424 @@ async fn run() {
             } => {
                 // Resize the window
                 if let Err(err) = pixels.resize_surface(size.width, size.height) {
+                    journal.record(get_time_ms(), &systems[0], JournalEvent::Note(format!("pixels.resize_surface() failed: {err}")));
                     log_error("pixels.resize_surface", err);
-                    elwt.exit();
-                    return;
+                    error!("recent events: {}", journal.dump(&systems[0]));
+                    open = false;
                 }
             }
             Event::WindowEvent {
                 event: WindowEvent::CursorMoved { device_id: _, position },
                 ..
             } => {
-                // Update cursor position
-                let cursor_x = position.x as i16;
-                let cursor_y = position.y as i16;
-                #[cfg(target_arch = "wasm32")]
-                set_id_text("debug-text", &format!("Cursor: ({}, {})", cursor_x, cursor_y));
-                particles.attractor = Some(world::Attractor {
-                    position: glam::Vec2::new(cursor_x as f32, cursor_y as f32),
-                    strength: 6.0,
-                    radius: 50,
-                });
+                let position = Vec2::new(position.x as f32, position.y as f32);
+                cursor = Vec2::new(position.x % view_width as f32, position.y);
+                input_map.pointer_events(Binding::Hover, position, &mut actions);
             }
             Event::WindowEvent {
                 event: WindowEvent::CursorLeft { device_id: _,},
                 ..
             } => {
-                // clear cursor position
-                #[cfg(target_arch = "wasm32")]
-                set_id_text("debug-text", "");
-                particles.attractor = None;
+                input_map.pointer_released(Binding::Hover, &mut actions);
             }
             Event::WindowEvent {
                 event: WindowEvent::Touch (touch),
                 ..
             } => {
-                use glam::Vec2;
-                let touch_x = touch.location.x as f32 / (3.0 * WIDTH as f32) - 0.5;
-                let touch_y = touch.location.y as f32 / (3.0 * HEIGHT as f32) - 0.5;
-                // update axis motion
-                #[cfg(target_arch = "wasm32")]
-                set_id_text("debug-text", &format!("Touch: {:?}", touch));
-                particles.simulation.acceleration = Vec2::new(touch_x,touch_y);
+                let position = Vec2::new(touch.location.x as f32, touch.location.y as f32);
+                let down = matches!(touch.phase, TouchPhase::Started | TouchPhase::Moved);
+                input_map.touch_events(touch.id, down.then_some(position), &mut actions);
             }
+            // the loop is going away without a quit, e.g. `shutdown` or the OS ending it
+            Event::LoopExiting => open = false,
             
 
             _ => (),
         }
 
         // Handle input events
-        if input.update(&event) && (input.key_pressed(KeyCode::Escape) || input.close_requested()) {
-            elwt.exit();
+        if input.update(&event) {
+            if input.close_requested() {
+                open = false;
+            }
+            input_map.button_events(&input, &mut actions);
+            #[cfg(feature = "gamepad")]
+            gamepads.events(&input_map, &mut actions);
+            for (name, &key) in &config.macro_keys {
+                if input.key_pressed(key) && !gestures.play(name, false) {
+                    log::warn!("no gesture macro named {name}");
+                }
+            }
+            for (name, &key) in &config.group_keys {
+                if input.key_pressed(key) && !systems.iter_mut().all(|particles| particles.toggle_group(name)) {
+                    log::warn!("no particle group named {name}");
+                }
+            }
         }
-    });
-    res.unwrap();
+        #[cfg(target_arch = "wasm32")]
+        actions.append(&mut bridge.actions.borrow_mut());
+
+        gestures.emit(&mut actions);
+
+        for action in actions.drain(..) {
+            gestures.record(&action);
+            journal.record(get_time_ms(), &systems[0], JournalEvent::Action(action));
+            match action {
+                ActionEvent::Pressed(Action::Quit) => open = false,
+                ActionEvent::Pressed(Action::Pause) => paused = !paused,
+                ActionEvent::Pressed(Action::Rewind) => rewinding = true,
+                ActionEvent::Released(Action::Rewind) => rewinding = false,
+                ActionEvent::Pressed(Action::Reset) => {
+                    rewind.iter_mut().for_each(RewindBuffer::clear);
+                    for particles in &mut systems {
+                        particles.clear();
+                        particles.reseed(seed);
+                        for _ in 0..initial_particles {
+                            particles.spawn_random(1.0, lifetime);
+                        }
+                    }
+                }
+                ActionEvent::Pressed(Action::Edit) => {
+                    editor.active = !editor.active;
+                    if editor.active {
+                        for particles in &mut systems {
+                            particles.pointer_attractor = None;
+                        }
+                    } else {
+                        save_scene(config.scene_path.as_deref(), &Scene::capture(&systems[0]));
+                    }
+                }
+                ActionEvent::Pressed(Action::EditDraw) if editor.active => {
+                    editor.press(&mut systems[0], cursor, input.held_shift());
+                }
+                ActionEvent::Released(Action::EditDraw) if editor.active => {
+                    editor.release(&mut systems[0], cursor);
+                }
+                ActionEvent::Pressed(Action::EditDelete) if editor.active => {
+                    editor.delete_at(&mut systems[0], cursor);
+                }
+                ActionEvent::Pressed(Action::WallTool) if editor.active => editor.toggle_tool(),
+                // the cursor edits obstacles instead of attracting
+                ActionEvent::Pointer(Action::Attract, _) if editor.active => (),
+                ActionEvent::Pointer(Action::Attract, position) => {
+                    #[cfg(target_arch = "wasm32")]
+                    bridge.page_text("debug-text", &format!("Cursor: ({}, {})", position.x as i16, position.y as i16));
+                    // every view reacts as if the cursor were over it
+                    let position = Vec2::new(position.x % view_width as f32, position.y);
+                    for particles in &mut systems {
+                        particles.pointer_attractor = Some(world::Attractor::interactive(position));
+                    }
+                }
+                ActionEvent::Released(Action::Attract) => {
+                    // clear cursor position
+                    #[cfg(target_arch = "wasm32")]
+                    bridge.page_text("debug-text", "");
+                    for particles in &mut systems {
+                        particles.pointer_attractor = None;
+                    }
+                }
+                ActionEvent::Touch(Action::TouchAttract, id, position) => {
+                    match position {
+                        Some(position) => touches.insert(id, Vec2::new(position.x % view_width as f32, position.y)),
+                        None => touches.remove(&id),
+                    };
+                    for particles in &mut systems {
+                        particles.touch_attractors.clear();
+                        particles.touch_attractors.extend(touches.values().copied().map(world::Attractor::interactive));
+                    }
+                }
+                ActionEvent::Pointer(Action::Wind, position) => {
+                    // update axis motion
+                    #[cfg(target_arch = "wasm32")]
+                    bridge.page_text("debug-text", &format!("Touch: ({}, {})", position.x as i16, position.y as i16));
+                    for particles in &mut systems {
+                        particles.simulation.acceleration = Vec2::new(
+                            position.x / (3.0 * WIDTH as f32) - 0.5,
+                            position.y / (3.0 * HEIGHT as f32) - 0.5,
+                        );
+                    }
+                }
+                ActionEvent::Pressed(Action::Copy) => {
+                    clipboard.copy(Scene::capture(&systems[0]).to_json());
+                }
+                ActionEvent::Pressed(Action::Paste) => clipboard.request_paste(),
+                ActionEvent::Pressed(Action::Record) => {
+                    if recording {
+                        save_replay(config.replay_path.as_deref(), &replay);
+                    } else {
+                        replay.clear();
+                    }
+                    recording = !recording;
+                }
+                ActionEvent::Pressed(Action::CycleDrawMode) => {
+                    for renderer in &mut renderers {
+                        renderer.cycle_mode();
+                    }
+                }
+                ActionEvent::Pressed(Action::Trace) => {
+                    let picked = tracer.select_near(&systems[0], cursor, TRACE_PICK_RADIUS);
+                    if !picked {
+                        tracer.clear();
+                    }
+                }
+                ActionEvent::Pressed(Action::ExportTraces) if !tracer.is_empty() => {
+                    clipboard.copy(tracer.to_csv(&config.number_format));
+                }
+                ActionEvent::Pressed(Action::RecordMacro) => {
+                    if let Some(name) = gestures.toggle_recording() {
+                        log::info!("recorded gesture macro {name}");
+                        save_macros(config.macros_path.as_deref(), &gestures.macros);
+                    }
+                }
+                ActionEvent::Pressed(Action::LoopMacro) => gestures.toggle_loop(),
+                ActionEvent::Pressed(Action::MemoryReport) => {
+                    log::info!("memory: {}", MemoryReport::measure(&systems, &renderers, &rewind, &replay));
+                }
+                ActionEvent::Pressed(Action::CyclePalette) | ActionEvent::Pressed(Action::HighContrast) => {
+                    let mut theme = renderers[0].theme();
+                    if action == ActionEvent::Pressed(Action::CyclePalette) {
+                        theme.palette = theme.palette.next();
+                    } else {
+                        theme.high_contrast = !theme.high_contrast;
+                    }
+                    log::info!("palette {:?}, high contrast {}", theme.palette, theme.high_contrast);
+                    for renderer in &mut renderers {
+                        renderer.set_theme(theme);
+                    }
+                    tracer.set_palette(theme.palette);
+                    if let Some(pass) = &mut heatmap_pass {
+                        pass.set_palette(theme.palette);
+                    }
+                }
+                ActionEvent::Pressed(Action::DumpJournal) => {
+                    let dump = journal.dump(&systems[0]);
+                    log::info!("recent events: {dump}");
+                    clipboard.copy(dump);
+                }
+                ActionEvent::Pressed(Action::ImpactOverlay) => {
+                    for renderer in &mut renderers {
+                        renderer.toggle_impacts();
+                    }
+                }
+                ActionEvent::Pressed(Action::FlowOverlay) => {
+                    for renderer in &mut renderers {
+                        renderer.toggle_streamlines();
+                    }
+                }
+                ActionEvent::Pressed(Action::Ghost) => {
+                    ghost_frame = match ghost_frame {
+                        Some(_) => None,
+                        None if !replay.is_empty() => Some(0),
+                        None => None,
+                    };
+                }
+                _ => (),
+            }
+        }
+
+        // Restore a pasted scene once the clipboard has delivered it
+        if let Some(text) = clipboard.take_pasted() {
+            match Scene::from_json(&text) {
+                Ok(scene) => {
+                    scene.apply(&mut systems[0]);
+                    if let Some(tunnel) = &config.wind_tunnel {
+                        tunnel.apply(&mut systems[0]);
+                    }
+                    journal.record(get_time_ms(), &systems[0], JournalEvent::Params(Box::new(systems[0].simulation.clone())));
+                    walls_changed = true;
+                }
+                Err(err) => log::warn!("clipboard does not contain a scene: {err}"),
+            }
+        }
+
+        if editor.active {
+            editor.drag(&mut systems[0], cursor);
+        }
+        for renderer in &mut renderers {
+            renderer.set_preview(editor.preview(cursor));
+        }
+        // Obstacles are edited on the first view and shared by all
+        walls_changed |= editor.take_walls_changed();
+        if let Some((first, rest)) = systems.split_first_mut() {
+            for particles in rest {
+                particles.obstacles.clone_from(&first.obstacles);
+                particles.portals.clone_from(&first.portals);
+                particles.gates.clone_from(&first.gates);
+                particles.sinks.clone_from(&first.sinks);
+                particles.sources.clone_from(&first.sources);
+                if walls_changed {
+                    particles.wall_sdf.clone_from(&first.wall_sdf);
+                }
+            }
+        }
+        walls_changed = false;
+        #[cfg(target_arch = "wasm32")]
+        if bridge.destroyed.get() {
+            open = false;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if SHUTDOWN_REQUESTED.load(std::sync::atomic::Ordering::Relaxed) {
+            open = false;
+        }
+        if !open {
+            shut_down(
+                &mut ramp,
+                #[cfg(not(target_arch = "wasm32"))]
+                &mut latency,
+                #[cfg(not(target_arch = "wasm32"))]
+                &mut sensor,
+                &config.number_format,
+            );
+        }
+        open
+    })
+}
+
+/// Flush the metric logs of a quitting instance and let go of its sensor; GPU
+/// resources go with `pixels` when the instance is dropped.
+fn shut_down(
+    ramp: &mut Option<RampScheduler>,
+    #[cfg(not(target_arch = "wasm32"))] latency: &mut Option<latency::LatencyMeter>,
+    #[cfg(not(target_arch = "wasm32"))] sensor: &mut Option<sensor::TcpSensor>,
+    number_format: &format::NumberFormat,
+) {
+    if let Some(ramp) = ramp {
+        ramp.finish();
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(report) = latency.as_mut().and_then(|meter| meter.report(f64::INFINITY, number_format)) {
+            log::info!("{report}");
+        }
+        if let Some(link) = sensor.take() {
+            link.disconnect();
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = number_format;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_replay(path: Option<&str>) -> Replay {
+    path.and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| Replay::from_bytes(&bytes, MAX_REPLAY_FRAMES))
+        .unwrap_or_else(|| Replay::new(MAX_REPLAY_FRAMES))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_replay(path: Option<&str>, replay: &Replay) {
+    if let Some(path) = path {
+        if let Err(err) = std::fs::write(path, replay.to_bytes()) {
+            log_error("save_replay", err);
+        }
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn load_scene(path: Option<&str>) -> Option<Scene> {
+    let text = std::fs::read_to_string(path?).ok()?;
+    Scene::from_json(&text)
+        .map_err(|err| log::warn!("invalid scene file: {err}"))
+        .ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_flow_field(config: &vector_field::FlowFieldConfig, width: usize, height: usize) -> Option<vector_field::FlowField> {
+    let bytes = std::fs::read(&config.path)
+        .map_err(|err| log::warn!("cannot read vector field {}: {err}", config.path))
+        .ok()?;
+    let mut field = vector_field::VectorField::parse(&bytes)
+        .map_err(|err| log::warn!("invalid vector field {}: {err}", config.path))
+        .ok()?;
+    if config.fit {
+        field.fit(width, height);
+    }
+    Some(vector_field::FlowField { field, speed: config.speed, coupling: config.coupling })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_scene(path: Option<&str>, scene: &Scene) {
+    if let Some(path) = path {
+        if let Err(err) = std::fs::write(path, scene.to_json()) {
+            log_error("save_scene", err);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_macros(path: Option<&str>) -> BTreeMap<String, gesture::GestureMacro> {
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| {
+            serde_json::from_str(&text)
+                .map_err(|err| log::warn!("invalid gesture macros: {err}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_macros(path: Option<&str>, macros: &BTreeMap<String, gesture::GestureMacro>) {
+    if let Some(path) = path {
+        match serde_json::to_string(macros) {
+            Ok(json) => {
+                if let Err(err) = std::fs::write(path, json) {
+                    log_error("save_macros", err);
+                }
+            }
+            Err(err) => log_error("save_macros", err),
+        }
+    }
+}
+
+// Gesture macros only live in memory on the web
 #[cfg(target_arch = "wasm32")]
-fn update_stats(particle_count: usize, fps: f32, used_mb: f64) {
+fn load_macros(_path: Option<&str>) -> BTreeMap<String, gesture::GestureMacro> {
+    BTreeMap::new()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_macros(_path: Option<&str>, _macros: &BTreeMap<String, gesture::GestureMacro>) {}
+
+// Scenes are shared via clipboard and URL on the web
+#[cfg(target_arch = "wasm32")]
+fn load_scene(_path: Option<&str>) -> Option<Scene> {
+    None
+}
+
+// There is no file system to import vector fields from on the web
+#[cfg(target_arch = "wasm32")]
+fn load_flow_field(_config: &vector_field::FlowFieldConfig, _width: usize, _height: usize) -> Option<vector_field::FlowField> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_scene(_path: Option<&str>, _scene: &Scene) {}
+
+// Replays only live in memory on the web
+#[cfg(target_arch = "wasm32")]
+fn load_replay(_path: Option<&str>) -> Replay {
+    Replay::new(MAX_REPLAY_FRAMES)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_replay(_path: Option<&str>, _replay: &Replay) {}
+
+#[cfg(target_arch = "wasm32")]
+fn update_stats(particle_count: usize, fps: f32, used_mb: f64, format: &format::NumberFormat) {
     if let Some(window) = web_sys::window() {
         if let Some(document) = window.document() {
             // Update particle count
             if let Some(elem) = document.get_element_by_id("particle-count") {
-                elem.set_text_content(Some(&particle_count.to_string()));
+                elem.set_text_content(Some(&format.int(particle_count)));
             }
             
             // Update FPS
             if let Some(elem) = document.get_element_by_id("fps") {
-                elem.set_text_content(Some(&format!("{:.0}", fps)));
+                elem.set_text_content(Some(&format.fixed(fps as f64, 0)));
             }
             // Update memory usage  
             if let Some(elem) = document.get_element_by_id("memory-usage") {
-                elem.set_text_content(Some(&format!("{:.2}", used_mb)));
+                elem.set_text_content(Some(&format.fixed(used_mb, 2)));
             }
         }
     }
@@ -280,6 +1284,29 @@ fn set_id_text(id: &str, text: &str) {
     }
 }
 
+/// Apply a scene shared via `get_share_url`: the fragment scene first, then query overrides.
+#[cfg(target_arch = "wasm32")]
+fn apply_location(particles: &mut ParticleSystem) {
+    let Some(location) = web_sys::window().map(|w| w.location()) else {
+        return;
+    };
+    let mut scene = Scene::capture(particles);
+    if let Some(encoded) = location.hash().ok().as_deref().and_then(|h| h.strip_prefix("#scene=")) {
+        // links from before scenes were compressed hold URI-encoded JSON
+        let json = share::decode_payload(encoded)
+            .or_else(|| js_sys::decode_uri_component(encoded).map(String::from).ok())
+            .unwrap_or_default();
+        match Scene::from_json(&json) {
+            Ok(shared) => scene = shared,
+            Err(err) => log::warn!("invalid shared scene: {err}"),
+        }
+    }
+    if let Ok(search) = location.search() {
+        share::apply_query(&mut scene, &search);
+    }
+    scene.apply(particles);
+}
+
 #[cfg(target_arch = "wasm32")]
 fn get_memory_usage_mb() -> f64 {
     // Update memory usage (read from the wasm linear memory)
@@ -299,6 +1326,13 @@ fn get_memory_usage_mb() -> f64 {
     }
 }   
 
+#[cfg(not(target_arch = "wasm32"))]
+fn get_time_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
 #[cfg(target_arch = "wasm32")]
 fn get_time_ms() -> f64 {
     web_sys::window()
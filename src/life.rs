@@ -0,0 +1,195 @@
+use crate::force::{Force, ParticleView};
+use crate::memory::vec_bytes;
+use crate::world::{EmitterId, DEFAULT_EMITTER};
+use glam::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Cellular automaton layer coupled to the particles, see `LifeLayer`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifeConfig {
+    /// Width of a cell in pixels.
+    pub cell: f32,
+    /// Simulation time between generations; must be positive.
+    #[serde(deserialize_with = "crate::config::positive")]
+    pub period: f32,
+    /// Live neighbour counts that bring a dead cell to life; Conway's rule is `[3]`.
+    pub birth: Vec<u8>,
+    /// Live neighbour counts that keep a live cell alive; Conway's rule is `[2, 3]`.
+    pub survive: Vec<u8>,
+    /// Fraction of the cells alive at the start.
+    pub fill: f32,
+    /// Particles each newborn cell emits, owned by `emitter`.
+    pub emit: u32,
+    pub emitter: EmitterId,
+    /// Speed of the emitted particles, in random directions.
+    pub emit_speed: f32,
+    /// Mass and lifetime of the emitted particles.
+    pub mass: f32,
+    pub lifetime: f32,
+    /// Pull of the particles towards crowds of live cells.
+    pub attraction: f32,
+    /// Normal speed from which a particle hitting a wall or obstacle brings the cell it
+    /// hit in to life.
+    pub seed_speed: f32,
+    pub color: [u8; 3],
+}
+
+impl Default for LifeConfig {
+    fn default() -> Self {
+        Self {
+            cell: 8.0,
+            period: 10.0,
+            birth: vec![3],
+            survive: vec![2, 3],
+            fill: 0.2,
+            emit: 1,
+            emitter: DEFAULT_EMITTER,
+            emit_speed: 1.0,
+            mass: 0.5,
+            lifetime: 200.0,
+            attraction: 0.05,
+            seed_speed: 2.0,
+            color: [0x18, 0x30, 0x28],
+        }
+    }
+}
+
+/// Game of life on a coarse grid wrapping around the arena edges, stepped every
+/// `period`: newborn cells emit particles, live cells pull particles in, and particles
+/// hitting walls or obstacles seed cells, so each keeps stirring the other.
+#[derive(Clone, Debug)]
+pub struct LifeLayer {
+    pub config: LifeConfig,
+    cols: usize,
+    rows: usize,
+    alive: Vec<bool>,
+    next: Vec<bool>,
+    /// Live cells among each cell and its eight neighbours, for the attraction.
+    crowd: Vec<u8>,
+    /// Cells born in the last generation.
+    births: Vec<usize>,
+    elapsed: f32,
+}
+
+impl LifeLayer {
+    /// All cells dead over a `width` x `height` arena; see `randomize`.
+    pub fn new(width: usize, height: usize, config: LifeConfig) -> Self {
+        let config = LifeConfig { cell: config.cell.max(1.0), ..config };
+        let cols = (width as f32 / config.cell).ceil().max(1.0) as usize;
+        let rows = (height as f32 / config.cell).ceil().max(1.0) as usize;
+        Self {
+            config,
+            cols,
+            rows,
+            alive: vec![false; cols * rows],
+            next: vec![false; cols * rows],
+            crowd: vec![0; cols * rows],
+            births: Vec::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.alive) + vec_bytes(&self.next) + vec_bytes(&self.crowd) + vec_bytes(&self.births)
+    }
+
+    /// Bring a `fill` fraction of the cells to life at random.
+    pub fn randomize(&mut self, rng: &mut impl Rng) {
+        for cell in &mut self.alive {
+            *cell = rng.random::<f32>() < self.config.fill;
+        }
+        self.count_crowds();
+    }
+
+    /// Cells per row and column.
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    pub fn is_alive(&self, col: usize, row: usize) -> bool {
+        col < self.cols && row < self.rows && self.alive[row * self.cols + col]
+    }
+
+    fn index_of(&self, pos: Vec2) -> usize {
+        let col = ((pos.x / self.config.cell).max(0.0) as usize).min(self.cols - 1);
+        let row = ((pos.y / self.config.cell).max(0.0) as usize).min(self.rows - 1);
+        row * self.cols + col
+    }
+
+    fn center(&self, index: usize) -> Vec2 {
+        Vec2::new((index % self.cols) as f32 + 0.5, (index / self.cols) as f32 + 0.5) * self.config.cell
+    }
+
+    /// Bring the cell at `pos` to life.
+    pub fn seed(&mut self, pos: Vec2) {
+        let index = self.index_of(pos);
+        self.alive[index] = true;
+    }
+
+    /// Advance by `dt` of simulation time, running a generation when one is due.
+    /// Returns the centers of the cells born in it.
+    pub fn advance(&mut self, dt: f32) -> impl Iterator<Item = Vec2> + '_ {
+        self.births.clear();
+        self.elapsed += dt;
+        if self.elapsed >= self.config.period {
+            self.elapsed = 0.0;
+            self.generation();
+        }
+        self.births.iter().map(|&index| self.center(index))
+    }
+
+    fn generation(&mut self) {
+        let (cols, rows) = (self.cols, self.rows);
+        for row in 0..rows {
+            for col in 0..cols {
+                let mut neighbours = 0u8;
+                for (dx, dy) in [(cols - 1, rows - 1), (0, rows - 1), (1, rows - 1), (cols - 1, 0), (1, 0), (cols - 1, 1), (0, 1), (1, 1)] {
+                    neighbours += self.alive[(row + dy) % rows * cols + (col + dx) % cols] as u8;
+                }
+                let index = row * cols + col;
+                let alive = if self.alive[index] {
+                    self.config.survive.contains(&neighbours)
+                } else {
+                    self.config.birth.contains(&neighbours)
+                };
+                if alive && !self.alive[index] {
+                    self.births.push(index);
+                }
+                self.next[index] = alive;
+            }
+        }
+        std::mem::swap(&mut self.alive, &mut self.next);
+        self.count_crowds();
+    }
+
+    fn count_crowds(&mut self) {
+        let (cols, rows) = (self.cols, self.rows);
+        for row in 0..rows {
+            for col in 0..cols {
+                let mut crowd = 0;
+                for dy in [rows - 1, 0, 1] {
+                    for dx in [cols - 1, 0, 1] {
+                        crowd += self.alive[(row + dy) % rows * cols + (col + dx) % cols] as u8;
+                    }
+                }
+                self.crowd[row * cols + col] = crowd;
+            }
+        }
+    }
+
+    /// Acceleration at `pos` towards where live cells crowd.
+    pub fn attraction(&self, pos: Vec2) -> Vec2 {
+        let cell = self.config.cell;
+        let crowd = |offset: Vec2| self.crowd[self.index_of(pos + offset)] as f32;
+        let gradient = Vec2::new(crowd(Vec2::X * cell) - crowd(-Vec2::X * cell), crowd(Vec2::Y * cell) - crowd(-Vec2::Y * cell));
+        gradient * (self.config.attraction / 18.0)
+    }
+}
+
+impl Force for LifeLayer {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        self.attraction(particle.position) * particle.mass
+    }
+}
@@ -0,0 +1,86 @@
+use crate::replay::Replay;
+use crate::rewind::RewindBuffer;
+use crate::world::{ParticleSystem, Renderer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Upper bound on the memory of the simulation, mostly for wasm where the heap is small.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryBudget {
+    pub megabytes: f32,
+    /// Part of the budget set aside for rewind snapshots and the replay recording.
+    pub history_share: f32,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self { megabytes: 256.0, history_share: 0.5 }
+    }
+}
+
+impl MemoryBudget {
+    pub fn bytes(&self) -> usize {
+        (self.megabytes.max(0.0) as f64 * MEGABYTE) as usize
+    }
+
+    pub fn history_bytes(&self) -> usize {
+        (self.bytes() as f64 * self.history_share.clamp(0.0, 1.0) as f64) as usize
+    }
+
+    /// Most particles each of `views` systems can hold in what is left of the budget
+    /// after the history share and `fixed` bytes (frame and render buffers).
+    pub fn max_particles(&self, fixed: usize, views: usize) -> usize {
+        let available = self.bytes().saturating_sub(self.history_bytes() + fixed);
+        available / views.max(1) / ParticleSystem::BYTES_PER_PARTICLE
+    }
+}
+
+/// Heap bytes held by the large buffers, grouped by owner.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryReport {
+    /// Particle columns of all systems.
+    pub particles: usize,
+    pub renderer: usize,
+    /// Neighbor grids, step scratch arenas, wall distance fields and impact maps.
+    pub spatial: usize,
+    /// Rewind snapshots and the replay recording.
+    pub history: usize,
+}
+
+impl MemoryReport {
+    pub fn measure(systems: &[ParticleSystem], renderers: &[Renderer], rewind: &[RewindBuffer], replay: &Replay) -> Self {
+        Self {
+            particles: systems.iter().map(ParticleSystem::memory_bytes).sum(),
+            renderer: renderers.iter().map(Renderer::memory_bytes).sum(),
+            spatial: systems.iter().map(ParticleSystem::spatial_memory_bytes).sum(),
+            history: rewind.iter().map(RewindBuffer::memory_bytes).sum::<usize>() + replay.memory_bytes(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.particles + self.renderer + self.spatial + self.history
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mb = |bytes: usize| bytes as f64 / MEGABYTE;
+        write!(
+            f,
+            "{:.1} MB (particles {:.1}, renderer {:.1}, spatial {:.1}, history {:.1})",
+            mb(self.total()),
+            mb(self.particles),
+            mb(self.renderer),
+            mb(self.spatial),
+            mb(self.history)
+        )
+    }
+}
+
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+/// Heap bytes of `vec`'s allocation.
+pub(crate) fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * std::mem::size_of::<T>()
+}
@@ -0,0 +1,55 @@
+/// Improved Perlin gradient noise at (`x`, `y`, `z`), roughly in `[-1, 1]`.
+///
+/// Smooth in all three coordinates; sampling a 2D field with time as `z` makes it
+/// evolve without jumps. Lattice gradients come from a hash instead of a
+/// permutation table, so the field is the same everywhere and never repeats.
+pub fn perlin3(x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let h = hash(ix + dx, iy + dy, iz + dz);
+        grad(h, fx - dx as f32, fy - dy as f32, fz - dz as f32)
+    };
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+/// Quintic smoothstep, continuous up to the second derivative.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h
+}
+
+/// Dot product with one of the 12 cube edge directions picked by `hash`.
+fn grad(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
@@ -0,0 +1,177 @@
+use glam::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Static collision geometry particles bounce off.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    Circle { center: Vec2, radius: f32 },
+    /// Axis-aligned box between `min` and `max`.
+    Rect { min: Vec2, max: Vec2 },
+    /// Line segment with no thickness; it has no inside, so it only
+    /// collides and never counts as containing a point. Particles moving more
+    /// than their diameter per step can pass through; raise `substeps` if so.
+    Segment { start: Vec2, end: Vec2 },
+}
+
+impl Shape {
+    /// Rectangle spanned by two corners in any order.
+    pub fn rect(a: Vec2, b: Vec2) -> Self {
+        Shape::Rect { min: a.min(b), max: a.max(b) }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        match *self {
+            Shape::Circle { center, radius } => point.distance_squared(center) <= radius * radius,
+            Shape::Rect { min, max } => point.cmpge(min).all() && point.cmple(max).all(),
+            Shape::Segment { .. } => false,
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        match *self {
+            Shape::Circle { center, .. } => center,
+            Shape::Rect { min, max } => (min + max) * 0.5,
+            Shape::Segment { start, end } => (start + end) * 0.5,
+        }
+    }
+
+    /// Corners of the axis-aligned bounding box.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Shape::Circle { center, radius } => (center - Vec2::splat(radius), center + Vec2::splat(radius)),
+            Shape::Rect { min, max } => (min, max),
+            Shape::Segment { start, end } => (start.min(end), start.max(end)),
+        }
+    }
+
+    /// Closest point to `point` on a segment.
+    fn closest_on_segment(start: Vec2, end: Vec2, point: Vec2) -> Vec2 {
+        let dir = end - start;
+        let t = (point - start).dot(dir) / dir.length_squared().max(f32::EPSILON);
+        start + dir * t.clamp(0.0, 1.0)
+    }
+
+    /// Uniformly distributed point inside the shape.
+    pub fn random_point(&self, rng: &mut impl Rng) -> Vec2 {
+        match *self {
+            Shape::Circle { center, radius } => {
+                let angle = rng.random::<f32>() * std::f32::consts::TAU;
+                let r = radius * rng.random::<f32>().sqrt();
+                center + Vec2::from_angle(angle) * r
+            }
+            Shape::Rect { min, max } => min + (max - min) * Vec2::new(rng.random(), rng.random()),
+            Shape::Segment { start, end } => start.lerp(end, rng.random::<f32>()),
+        }
+    }
+
+    pub fn translate(&mut self, delta: Vec2) {
+        match self {
+            Shape::Circle { center, .. } => *center += delta,
+            Shape::Rect { min, max } => {
+                *min += delta;
+                *max += delta;
+            }
+            Shape::Segment { start, end } => {
+                *start += delta;
+                *end += delta;
+            }
+        }
+    }
+
+    /// Contact of a disc at `pos` with `radius` against this shape:
+    /// the outward surface normal and penetration depth, if they overlap.
+    pub fn contact(&self, pos: Vec2, radius: f32) -> Option<(Vec2, f32)> {
+        match *self {
+            Shape::Circle { center, radius: r } => {
+                let d = pos - center;
+                let distance = d.length();
+                let depth = r + radius - distance;
+                (depth > 0.0).then(|| (d.try_normalize().unwrap_or(Vec2::NEG_Y), depth))
+            }
+            Shape::Rect { min, max } => {
+                let closest = pos.clamp(min, max);
+                let d = pos - closest;
+                if d != Vec2::ZERO {
+                    let distance = d.length();
+                    return (distance < radius).then(|| (d / distance, radius - distance));
+                }
+                // center inside: push out through the nearest face
+                let faces = [
+                    (pos.x - min.x, Vec2::NEG_X),
+                    (max.x - pos.x, Vec2::X),
+                    (pos.y - min.y, Vec2::NEG_Y),
+                    (max.y - pos.y, Vec2::Y),
+                ];
+                let (distance, normal) = faces
+                    .into_iter()
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .expect("four faces");
+                Some((normal, distance + radius))
+            }
+            Shape::Segment { start, end } => {
+                let d = pos - Self::closest_on_segment(start, end, pos);
+                let distance = d.length();
+                // a center exactly on the line leaves on the side it came from
+                let normal = d.try_normalize().unwrap_or_else(|| (end - start).perp().normalize_or(Vec2::NEG_Y));
+                (distance < radius).then_some((normal, radius - distance))
+            }
+        }
+    }
+}
+
+/// Pair of linked regions: a particle entering one reappears at the same offset
+/// in the other, keeping its velocity.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Portal {
+    pub a: Shape,
+    pub b: Shape,
+    /// When false, only `a` leads to `b`.
+    #[serde(default = "two_way")]
+    pub two_way: bool,
+}
+
+fn two_way() -> bool {
+    true
+}
+
+impl Portal {
+    /// Where a particle that moved from `from` to `to` should be, if it entered a portal region.
+    pub fn teleport(&self, from: Vec2, to: Vec2) -> Option<Vec2> {
+        let enters = |region: &Shape| !region.contains(from) && region.contains(to);
+        if enters(&self.a) {
+            Some(self.b.center() + (to - self.a.center()))
+        } else if self.two_way && enters(&self.b) {
+            Some(self.a.center() + (to - self.b.center()))
+        } else {
+            None
+        }
+    }
+}
+
+/// One-way line segment: particles cross it only towards `(end - start).perp()`
+/// and bounce off the other side.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gate {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Gate {
+    /// Unit normal of the allowed crossing direction.
+    pub fn normal(&self) -> Vec2 {
+        (self.end - self.start).perp().normalize_or_zero()
+    }
+
+    /// Whether moving from `from` to `to` crosses the segment against its direction.
+    pub fn blocks(&self, from: Vec2, to: Vec2) -> bool {
+        let dir = self.end - self.start;
+        let (s0, s1) = (dir.perp_dot(from - self.start), dir.perp_dot(to - self.start));
+        if s0 < 0.0 || s1 >= 0.0 {
+            return false;
+        }
+        let crossing = from + (to - from) * (s0 / (s0 - s1));
+        let t = (crossing - self.start).dot(dir) / dir.length_squared();
+        (0.0..=1.0).contains(&t)
+    }
+}
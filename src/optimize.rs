@@ -0,0 +1,141 @@
+use crate::batch::Batch;
+use crate::sweep;
+use crate::world::SimParams;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Range a tuned parameter is kept in; parsing rejects empty or non-finite ranges.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawBounds")]
+pub struct ParamBounds {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ParamBounds {
+    /// Bounds from `min` to `max`; an error unless both are finite and `min <= max`.
+    pub fn new(min: f32, max: f32) -> Result<Self, String> {
+        if !min.is_finite() || !max.is_finite() {
+            return Err(format!("bounds {min}..{max} are not finite"));
+        }
+        if min > max {
+            return Err(format!("bounds {min}..{max} are empty, min is above max"));
+        }
+        Ok(Self { min, max })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawBounds {
+    min: f32,
+    max: f32,
+}
+
+impl TryFrom<RawBounds> for ParamBounds {
+    type Error = String;
+
+    fn try_from(raw: RawBounds) -> Result<Self, String> {
+        Self::new(raw.min, raw.max)
+    }
+}
+
+/// Tunes simulation parameters to maximize (or minimize) a batch metric by random search.
+///
+/// The first round samples the bounds uniformly; every later round perturbs the best
+/// candidate so far by a shrinking fraction of each range. Parameters are paths into
+/// `SimParams` as in `Sweep`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Optimizer {
+    /// Evaluation run; its `simulation` (or the default parameters) is the starting point.
+    pub batch: Batch,
+    pub params: BTreeMap<String, ParamBounds>,
+    /// `BatchStats` field to optimize, e.g. `"absorbed_rate"` for particles collected by sinks.
+    pub metric: String,
+    pub minimize: bool,
+    pub rounds: usize,
+    /// Candidates evaluated per round, in parallel.
+    pub population: usize,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self {
+            batch: Batch::default(),
+            params: BTreeMap::new(),
+            metric: "absorbed_rate".into(),
+            minimize: false,
+            rounds: 20,
+            population: 8,
+        }
+    }
+}
+
+/// Parameter values, in the order of `Optimizer::params`, and the metric they reached.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub values: Vec<f32>,
+    pub score: f32,
+}
+
+impl Optimizer {
+    /// Search on up to `threads` threads, calling `on_round` with the best candidate after
+    /// each round. All evaluations share one seed so scores differ only by the parameters.
+    pub fn run(&self, threads: usize, mut on_round: impl FnMut(usize, &Candidate)) -> Result<Candidate, String> {
+        // the fields are public, so bounds set from code are checked here
+        for (name, b) in &self.params {
+            ParamBounds::new(b.min, b.max).map_err(|err| format!("{name}: {err}"))?;
+        }
+        let mut batch = self.batch.clone();
+        let seed = *batch.seed.get_or_insert_with(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let bounds: Vec<&ParamBounds> = self.params.values().collect();
+
+        let mut best: Option<Candidate> = None;
+        for round in 0..self.rounds {
+            // perturbation width as a fraction of each range
+            let width = 0.5 * 0.8f32.powi(round as i32);
+            let candidates: Vec<Vec<f32>> = (0..self.population.max(1))
+                .map(|_| {
+                    bounds
+                        .iter()
+                        .enumerate()
+                        .map(|(k, b)| match &best {
+                            None => rng.random_range(b.min..=b.max),
+                            Some(best) => {
+                                let step = (b.max - b.min) * width * rng.random_range(-1.0..=1.0);
+                                (best.values[k] + step).clamp(b.min, b.max)
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let batches = candidates
+                .iter()
+                .map(|values| sweep::with_params(&batch, self.params.keys(), values))
+                .collect::<Result<Vec<_>, String>>()?;
+            for (values, stats) in candidates.into_iter().zip(sweep::run_parallel(&batches, threads)) {
+                let score = stats.metric(&self.metric).ok_or_else(|| format!("unknown metric {}", self.metric))?;
+                let better = best.as_ref().is_none_or(|best| match self.minimize {
+                    true => score < best.score,
+                    false => score > best.score,
+                });
+                if better {
+                    best = Some(Candidate { values, score });
+                }
+            }
+            if let Some(best) = &best {
+                on_round(round, best);
+            }
+        }
+        best.ok_or_else(|| "no rounds run".into())
+    }
+
+    /// The full simulation parameters of `candidate`.
+    pub fn params_for(&self, candidate: &Candidate) -> Result<SimParams, String> {
+        let batch = sweep::with_params(&self.batch, self.params.keys(), &candidate.values)?;
+        Ok(batch.simulation.unwrap_or_default())
+    }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+/// How the particle target moves between levels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RampMode {
+    /// Jump to each level and hold it.
+    Step,
+    /// Interpolate from each level to the next over the hold duration.
+    Linear,
+}
+
+/// Capacity test schedule, e.g. 10k → 50k → 100k particles held for 5 s each.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RampConfig {
+    pub levels: Vec<usize>,
+    pub hold_seconds: f64,
+    pub mode: RampMode,
+}
+
+/// Frame timing collected while a level was active.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LevelStats {
+    pub target: usize,
+    pub frames: u32,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LevelStats {
+    pub fn mean_ms(&self) -> f64 {
+        self.total_ms / self.frames.max(1) as f64
+    }
+
+    pub fn fps(&self) -> f64 {
+        1000.0 / self.mean_ms()
+    }
+}
+
+/// Drives the live particle target through the configured levels and records
+/// frame statistics per level.
+pub struct RampScheduler {
+    config: RampConfig,
+    start_ms: Option<f64>,
+    last_frame_ms: Option<f64>,
+    stats: Vec<LevelStats>,
+    finished: bool,
+}
+
+impl RampScheduler {
+    pub fn new(config: RampConfig) -> Self {
+        let stats = config
+            .levels
+            .iter()
+            .map(|&target| LevelStats { target, ..LevelStats::default() })
+            .collect();
+        Self {
+            config,
+            start_ms: None,
+            last_frame_ms: None,
+            stats,
+            finished: false,
+        }
+    }
+
+    /// Highest level, i.e. the capacity the particle system needs.
+    pub fn max_level(&self) -> usize {
+        self.config.levels.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn stats(&self) -> &[LevelStats] {
+        &self.stats
+    }
+
+    /// Record a frame presented at `now_ms` and return the particle target,
+    /// or `None` once every level has been held.
+    pub fn frame(&mut self, now_ms: f64) -> Option<usize> {
+        if self.finished {
+            return None;
+        }
+        let start = *self.start_ms.get_or_insert(now_ms);
+        let hold_ms = self.config.hold_seconds.max(0.001) * 1000.0;
+        let position = (now_ms - start) / hold_ms;
+        let level = position as usize;
+        if level >= self.config.levels.len() {
+            self.finished = true;
+            self.log_report();
+            return None;
+        }
+
+        if let Some(last) = self.last_frame_ms.replace(now_ms) {
+            let stats = &mut self.stats[level];
+            let frame_ms = now_ms - last;
+            stats.frames += 1;
+            stats.total_ms += frame_ms;
+            stats.max_ms = stats.max_ms.max(frame_ms);
+        }
+
+        let current = self.config.levels[level];
+        Some(match self.config.mode {
+            RampMode::Step => current,
+            RampMode::Linear => {
+                let next = self.config.levels.get(level + 1).copied().unwrap_or(current);
+                let t = position.fract();
+                (current as f64 + (next as f64 - current as f64) * t) as usize
+            }
+        })
+    }
+
+    /// Stop early, logging the stats of the levels reached so far.
+    pub fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            self.log_report();
+        }
+    }
+
+    fn log_report(&self) {
+        log::info!("particle ramp finished:");
+        for stats in &self.stats {
+            log::info!(
+                "  {:>7} particles: {:>5} frames, {:.2} ms mean, {:.2} ms max, {:.1} FPS",
+                stats.target,
+                stats.frames,
+                stats.mean_ms(),
+                stats.max_ms,
+                stats.fps()
+            );
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Longest gap between readings that is integrated; longer gaps (reconnects) count as this long.
+const MAX_SAMPLE_GAP_S: f32 = 0.1;
+
+/// Tuning of `DeadReckoner`. All time constants are in seconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeadReckoningConfig {
+    /// Screen pixels per meter the board moves.
+    pub pixels_per_meter: f32,
+    /// How slowly gravity and sensor bias are tracked and removed (high-pass cutoff).
+    pub bias_seconds: f32,
+    /// How fast integrated velocity leaks away, bounding drift.
+    pub velocity_seconds: f32,
+    /// How fast the position drifts back to the center when the board rests.
+    pub recenter_seconds: f32,
+}
+
+impl Default for DeadReckoningConfig {
+    fn default() -> Self {
+        Self {
+            pixels_per_meter: 2000.0,
+            bias_seconds: 1.0,
+            velocity_seconds: 0.5,
+            recenter_seconds: 3.0,
+        }
+    }
+}
+
+/// Position estimated by double-integrating acceleration, with drift correction:
+/// the slowly varying part (gravity, bias) is removed first, and velocity and
+/// displacement leak away so errors don't accumulate.
+pub struct DeadReckoner {
+    config: DeadReckoningConfig,
+    bias: Option<Vec2>,
+    velocity: Vec2,
+    /// Displacement from the rest position in meters.
+    displacement: Vec2,
+    last_ms: Option<f64>,
+}
+
+impl DeadReckoner {
+    pub fn new(config: DeadReckoningConfig) -> Self {
+        Self { config, bias: None, velocity: Vec2::ZERO, displacement: Vec2::ZERO, last_ms: None }
+    }
+
+    /// Integrate an acceleration reading in m/s² sampled at `time_ms`.
+    pub fn sample(&mut self, accel: Vec2, time_ms: f64) {
+        let dt = self.last_ms.map_or(0.0, |last| ((time_ms - last) / 1000.0) as f32).clamp(0.0, MAX_SAMPLE_GAP_S);
+        self.last_ms = Some(time_ms);
+        // the first reading is taken as the resting bias
+        let bias = self.bias.get_or_insert(accel);
+        *bias += (accel - *bias) * (dt / self.config.bias_seconds.max(f32::EPSILON)).min(1.0);
+        let motion = accel - *bias;
+
+        self.velocity += motion * dt;
+        self.velocity *= (-dt / self.config.velocity_seconds.max(f32::EPSILON)).exp();
+        self.displacement += self.velocity * dt;
+        self.displacement *= (-dt / self.config.recenter_seconds.max(f32::EPSILON)).exp();
+    }
+
+    /// Estimated position in pixels relative to `center`.
+    pub fn position(&self, center: Vec2) -> Vec2 {
+        center + self.displacement * self.config.pixels_per_meter
+    }
+}
@@ -0,0 +1,96 @@
+use crate::memory::vec_bytes;
+use crate::world::ParticleSystem;
+use glam::Vec2;
+
+/// Recorded particle positions per frame, used as a baseline to compare against.
+pub struct Replay {
+    frames: Vec<Vec<Vec2>>,
+    max_frames: usize,
+}
+
+impl Replay {
+    pub fn new(max_frames: usize) -> Self {
+        Self {
+            frames: Vec::new(),
+            max_frames,
+        }
+    }
+
+    /// Append the live particle positions as the next frame, until `max_frames` is reached.
+    pub fn record(&mut self, particles: &ParticleSystem) {
+        if self.is_full() {
+            return;
+        }
+        self.frames.push(particles.positions().to_vec());
+    }
+
+    /// Shorten the recording to fit `bytes`, assuming each frame takes `frame_bytes`.
+    pub fn limit_bytes(&mut self, bytes: usize, frame_bytes: usize) {
+        self.max_frames = self.max_frames.min(bytes / frame_bytes.max(1));
+        self.frames.truncate(self.max_frames);
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.frames.iter().map(vec_bytes).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.max_frames
+    }
+
+    /// Positions of frame `index`, looping over the recording.
+    pub fn frame(&self, index: usize) -> &[Vec2] {
+        if self.frames.is_empty() {
+            return &[];
+        }
+        &self.frames[index % self.frames.len()]
+    }
+
+    /// Serialize as little-endian `u32` frame count, then per frame a `u32` length and `f32` x/y pairs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for frame in &self.frames {
+            bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            for p in frame {
+                bytes.extend_from_slice(&p.x.to_le_bytes());
+                bytes.extend_from_slice(&p.y.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Parse the format written by `to_bytes`; `None` if it is truncated or a frame claims
+    /// more positions than the bytes left hold.
+    pub fn from_bytes(bytes: &[u8], max_frames: usize) -> Option<Self> {
+        let mut words = bytes.chunks_exact(4).map(|w| [w[0], w[1], w[2], w[3]]);
+        let frame_count = u32::from_le_bytes(words.next()?) as usize;
+        let mut replay = Self::new(max_frames);
+        for _ in 0..frame_count.min(max_frames) {
+            let len = u32::from_le_bytes(words.next()?) as usize;
+            if len > words.len() / 2 {
+                return None;
+            }
+            let mut frame = Vec::new();
+            for _ in 0..len {
+                let x = f32::from_le_bytes(words.next()?);
+                let y = f32::from_le_bytes(words.next()?);
+                frame.push(Vec2::new(x, y));
+            }
+            replay.frames.push(frame);
+        }
+        Some(replay)
+    }
+}
@@ -0,0 +1,67 @@
+use crate::world::{ParticleSystem, Snapshot};
+use std::collections::VecDeque;
+
+/// Ring buffer of recent snapshots for stepping the simulation back in time.
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+    max_snapshots: usize,
+    interval: usize,
+    steps_since_snapshot: usize,
+}
+
+impl RewindBuffer {
+    /// Keep `seconds` of history at `steps_per_second`, snapshotting every `interval` steps.
+    pub fn new(seconds: f32, steps_per_second: f32, interval: usize) -> Self {
+        let interval = interval.max(1);
+        Self {
+            snapshots: VecDeque::new(),
+            max_snapshots: ((seconds * steps_per_second) as usize / interval).max(1),
+            interval,
+            steps_since_snapshot: 0,
+        }
+    }
+
+    /// Call once per simulation step; takes a snapshot every `interval` steps,
+    /// dropping the oldest when full.
+    pub fn record(&mut self, particles: &ParticleSystem) {
+        self.steps_since_snapshot += 1;
+        if self.steps_since_snapshot < self.interval {
+            return;
+        }
+        self.steps_since_snapshot = 0;
+        if self.snapshots.len() >= self.max_snapshots {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(particles.snapshot());
+    }
+
+    /// Restore the most recent snapshot and drop it; `false` once the history is exhausted.
+    pub fn rewind(&mut self, particles: &mut ParticleSystem) -> bool {
+        self.steps_since_snapshot = 0;
+        match self.snapshots.pop_back() {
+            Some(snapshot) => {
+                particles.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shorten the history to fit `bytes`, assuming each snapshot takes `snapshot_bytes`.
+    /// At least one snapshot is always kept.
+    pub fn limit_bytes(&mut self, bytes: usize, snapshot_bytes: usize) {
+        self.max_snapshots = self.max_snapshots.min(bytes / snapshot_bytes.max(1)).max(1);
+        while self.snapshots.len() > self.max_snapshots {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.snapshots.iter().map(Snapshot::memory_bytes).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.steps_since_snapshot = 0;
+    }
+}
@@ -0,0 +1,151 @@
+use crate::distribution::{Distribution, VectorDistribution};
+use crate::emitter::never_expires;
+use crate::world::{EmitterId, DEFAULT_EMITTER, INFINITE_LIFETIME};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Events recorded per `update`; later ones in the same update are dropped.
+pub const MAX_EVENTS: usize = 4096;
+
+/// Lifecycle events kept until they are dispatched by an `update` or drained; later ones
+/// are dropped, so a paused system that is never drained stays bounded.
+pub const MAX_LIFECYCLE_EVENTS: usize = 1 << 20;
+
+/// Callback run for each event at the end of an `update`, see
+/// `ParticleSystem::add_event_listener`.
+pub type EventListener = Box<dyn FnMut(&ParticleEvent) + Send + Sync>;
+
+/// Something that happened to a particle during the last `update`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParticleEvent {
+    /// Hit a wall, obstacle or gate faster than `IMPACT_SPEED`; of several contacts in a
+    /// step, the fastest.
+    Hit {
+        particle: u64,
+        /// Index of the particle when it hit; particles dying later in the same update
+        /// can move it, so look it up by `particle` with `ParticleSystem::index_of`.
+        index: usize,
+        emitter: EmitterId,
+        position: Vec2,
+        velocity: Vec2,
+        /// Point of the surface touched.
+        contact: Vec2,
+        /// Surface normal at `contact`, pointing towards the particle.
+        normal: Vec2,
+        /// Normal speed the particle hit with.
+        speed: f32,
+        /// Magnitude of the momentum change along the normal, mass times normal speed change.
+        impulse: f32,
+    },
+    /// Lifetime ran out; the particle now shrinks out.
+    Expired { particle: u64, emitter: EmitterId, position: Vec2, velocity: Vec2 },
+    /// Joined the system, or came back with `ParticleSystem::restore`; only recorded with
+    /// `ParticleSystem::record_lifecycle`, in `ParticleSystem::lifecycle_events`.
+    Spawned { particle: u64, emitter: EmitterId, position: Vec2, velocity: Vec2 },
+    /// Left the system; only recorded with `ParticleSystem::record_lifecycle`.
+    Died { particle: u64, emitter: EmitterId, position: Vec2, velocity: Vec2, cause: DeathCause },
+}
+
+/// Why a particle left the system, see `ParticleEvent::Died`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    /// Shrank out after its lifetime ran out.
+    Expired,
+    /// Crossed an edge set to kill.
+    Boundary,
+    /// Taken up by a sink, the floor water or the aggregate.
+    Absorbed,
+    /// Removed from code, e.g. with `kill_emitter`, `limit_live` or `clear`.
+    Removed,
+    /// Not in the snapshot passed to `ParticleSystem::restore`, e.g. when rewinding.
+    Rewound,
+}
+
+impl ParticleEvent {
+    pub fn emitter(&self) -> EmitterId {
+        match *self {
+            ParticleEvent::Hit { emitter, .. }
+            | ParticleEvent::Expired { emitter, .. }
+            | ParticleEvent::Spawned { emitter, .. }
+            | ParticleEvent::Died { emitter, .. } => emitter,
+        }
+    }
+
+    /// Where the particle was and how it moved when the event happened.
+    pub fn motion(&self) -> (Vec2, Vec2) {
+        match *self {
+            ParticleEvent::Hit { position, velocity, .. }
+            | ParticleEvent::Expired { position, velocity, .. }
+            | ParticleEvent::Spawned { position, velocity, .. }
+            | ParticleEvent::Died { position, velocity, .. } => (position, velocity),
+        }
+    }
+}
+
+/// Event kind a `SpawnRule` reacts to.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// Hits at least `min_speed` fast along the contact normal.
+    Hit { min_speed: f32 },
+    Expired,
+}
+
+impl Trigger {
+    fn matches(&self, event: &ParticleEvent) -> bool {
+        match (*self, *event) {
+            (Trigger::Hit { min_speed }, ParticleEvent::Hit { speed, .. }) => speed >= min_speed,
+            (Trigger::Expired, ParticleEvent::Expired { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Declarative secondary effect: when a particle event matches, spawn `count` particles
+/// where it happened, e.g. sparks on hard hits or a smoke puff when a rocket burns out.
+///
+/// Spawned particles belong to `emitter`, so further rules, groups and color gradients
+/// can single them out. Keep `from` and `emitter` apart to avoid endless cascades.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnRule {
+    pub on: Trigger,
+    /// Only react to particles of this emitter; `None` for all.
+    pub from: Option<EmitterId>,
+    pub count: u32,
+    /// Emitter the spawned particles are attributed to.
+    pub emitter: EmitterId,
+    /// Fraction of the triggering particle's velocity the spawned ones start with.
+    pub inherit: f32,
+    /// Added to the inherited velocity.
+    pub velocity: VectorDistribution,
+    pub mass: Distribution,
+    /// Left out when saved if particles never expire, since JSON has no infinity.
+    #[serde(default = "infinite_lifetime", skip_serializing_if = "never_expires")]
+    pub lifetime: Distribution,
+}
+
+impl Default for SpawnRule {
+    fn default() -> Self {
+        Self {
+            on: Trigger::Hit { min_speed: 4.0 },
+            from: None,
+            count: 5,
+            emitter: DEFAULT_EMITTER,
+            inherit: 0.0,
+            velocity: VectorDistribution::default(),
+            mass: Distribution::Constant(0.25),
+            lifetime: Distribution::Constant(30.0),
+        }
+    }
+}
+
+impl SpawnRule {
+    pub fn matches(&self, event: &ParticleEvent) -> bool {
+        self.from.is_none_or(|from| from == event.emitter()) && self.on.matches(event)
+    }
+}
+
+/// A lifetime left out of a saved rule never ran out.
+fn infinite_lifetime() -> Distribution {
+    Distribution::Constant(INFINITE_LIFETIME)
+}
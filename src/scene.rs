@@ -0,0 +1,115 @@
+use crate::flow::{Sink, Source};
+use crate::obstacle::{Gate, Portal, Shape};
+use crate::rules::SpawnRule;
+use crate::sdf::SdfGrid;
+use crate::swarm::Swarm;
+use crate::world::{Attractor, GravityWell, ParticleSystem, Repeller, SimParams, Vortex};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Shareable description of a simulation setup (parameters and interactive elements).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scene {
+    /// Random seed; when set, applying the scene restarts the random sequence.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub simulation: SimParams,
+    /// Fixed attractors, see `ParticleSystem::attractors`. Also read from the single
+    /// `attractor` of older scenes.
+    #[serde(default, alias = "attractor", deserialize_with = "one_or_many")]
+    pub attractors: Vec<Attractor>,
+    #[serde(default)]
+    pub repellers: Vec<Repeller>,
+    #[serde(default)]
+    pub vortices: Vec<Vortex>,
+    #[serde(default)]
+    pub gravity_wells: Vec<GravityWell>,
+    #[serde(default)]
+    pub swarms: Vec<Swarm>,
+    #[serde(default)]
+    pub obstacles: Vec<Shape>,
+    #[serde(default)]
+    pub portals: Vec<Portal>,
+    #[serde(default)]
+    pub gates: Vec<Gate>,
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// Secondary particles spawned on hits and expiries.
+    #[serde(default)]
+    pub spawn_rules: Vec<SpawnRule>,
+    /// Freehand walls; omitted when there are none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub walls: Option<SdfGrid>,
+}
+
+/// Accepts the single, optional attractor scenes had before they could have several.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AttractorsRepr {
+    Many(Vec<Attractor>),
+    One(Option<Attractor>),
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Attractor>, D::Error> {
+    Ok(match AttractorsRepr::deserialize(deserializer)? {
+        AttractorsRepr::Many(attractors) => attractors,
+        AttractorsRepr::One(attractor) => attractor.into_iter().collect(),
+    })
+}
+
+impl Scene {
+    /// Capture the current setup of `particles`.
+    pub fn capture(particles: &ParticleSystem) -> Self {
+        Self {
+            seed: Some(particles.seed()),
+            simulation: particles.simulation.clone(),
+            attractors: particles.attractors.to_vec(),
+            repellers: particles.repellers.to_vec(),
+            vortices: particles.vortices.to_vec(),
+            gravity_wells: particles.gravity_wells.to_vec(),
+            swarms: particles.swarms().to_vec(),
+            obstacles: particles.obstacles.clone(),
+            portals: particles.portals.clone(),
+            gates: particles.gates.clone(),
+            sinks: particles.sinks.clone(),
+            sources: particles.sources.clone(),
+            spawn_rules: particles.spawn_rules.clone(),
+            walls: (!particles.wall_sdf.is_empty()).then(|| particles.wall_sdf.clone()),
+        }
+    }
+
+    /// Apply this setup to `particles`, keeping the live particles.
+    pub fn apply(self, particles: &mut ParticleSystem) {
+        if let Some(seed) = self.seed {
+            particles.reseed(seed);
+        }
+        particles.simulation = self.simulation;
+        particles.attractors = self.attractors.into();
+        particles.repellers = self.repellers.into();
+        particles.vortices = self.vortices.into();
+        particles.gravity_wells = self.gravity_wells.into();
+        particles.clear_swarms();
+        for swarm in self.swarms {
+            particles.add_swarm(swarm);
+        }
+        particles.obstacles = self.obstacles;
+        particles.portals = self.portals;
+        particles.gates = self.gates;
+        particles.sinks = self.sinks;
+        particles.sources = self.sources;
+        particles.spawn_rules = self.spawn_rules;
+        match self.walls {
+            Some(walls) => particles.wall_sdf = walls,
+            None => particles.wall_sdf.clear(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("scene serialization")
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::memory::vec_bytes;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Cell size of the wall grid in pixels.
+pub const SDF_CELL: f32 = 2.0;
+
+/// Brush radius of freehand wall strokes in pixels.
+pub const WALL_BRUSH: f32 = 4.0;
+
+/// Most cells a deserialized grid may have, far above any arena, so a corrupt or hostile
+/// scene file cannot exhaust memory.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// Rasterized walls with a signed distance field for collision:
+/// negative inside walls, positive outside, in pixels.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "SdfData", into = "SdfData")]
+pub struct SdfGrid {
+    cols: usize,
+    rows: usize,
+    cell: f32,
+    solid: Vec<bool>,
+    distance: Vec<f32>,
+    empty: bool,
+}
+
+/// Serialized form: the solid mask as alternating empty/solid run lengths.
+#[derive(Serialize, Deserialize)]
+struct SdfData {
+    cols: usize,
+    rows: usize,
+    cell: f32,
+    runs: Vec<u32>,
+}
+
+impl SdfGrid {
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.solid) + vec_bytes(&self.distance)
+    }
+
+    /// Empty grid covering `width` x `height` pixels.
+    pub fn new(width: usize, height: usize, cell: f32) -> Self {
+        let cols = (width as f32 / cell).ceil() as usize;
+        let rows = (height as f32 / cell).ceil() as usize;
+        Self {
+            cols,
+            rows,
+            cell,
+            solid: vec![false; cols * rows],
+            distance: vec![f32::INFINITY; cols * rows],
+            empty: true,
+        }
+    }
+
+    /// Grid sampling the signed distance function `sdf` (negative inside, in pixels) at the
+    /// cell centers over `width` x `height` pixels, e.g. text or a logo built from primitives.
+    /// `None` for an empty area, a cell size that is not positive, or more than `MAX_CELLS` cells.
+    pub fn from_fn(width: usize, height: usize, cell: f32, sdf: impl Fn(Vec2) -> f32) -> Option<Self> {
+        if !(cell.is_finite() && cell > 0.0) {
+            return None;
+        }
+        let cols = (width as f32 / cell).ceil() as usize;
+        let rows = (height as f32 / cell).ceil() as usize;
+        if cols.checked_mul(rows).is_none_or(|cells| cells > MAX_CELLS) {
+            return None;
+        }
+        let distances = (0..rows)
+            .flat_map(|y| (0..cols).map(move |x| (Vec2::new(x as f32, y as f32) + 0.5) * cell))
+            .map(sdf)
+            .collect();
+        Self::from_distances(cols, rows, cell, distances)
+    }
+
+    /// Grid from precomputed signed distances at the cell centers, row by row;
+    /// `None` unless there is one per cell. The distances are kept as given until
+    /// walls are painted, which recomputes them from the solid cells.
+    pub fn from_distances(cols: usize, rows: usize, cell: f32, distances: Vec<f32>) -> Option<Self> {
+        if cols.checked_mul(rows) != Some(distances.len()) || cols == 0 || rows == 0 {
+            return None;
+        }
+        let solid: Vec<bool> = distances.iter().map(|&d| d <= 0.0).collect();
+        let empty = !solid.contains(&true);
+        Some(Self { cols, rows, cell, solid, distance: distances, empty })
+    }
+
+    /// True when there are no walls.
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    pub fn clear(&mut self) {
+        self.solid.fill(false);
+        self.rebuild();
+    }
+
+    /// Whether `p` lies inside a wall.
+    pub fn is_solid(&self, p: Vec2) -> bool {
+        self.cell_index(p).is_some_and(|i| self.solid[i])
+    }
+
+    /// Paint (or erase, with `solid == false`) a stroke segment from `a` to `b`
+    /// with a round brush of `radius`, then update the distance field.
+    pub fn paint_segment(&mut self, a: Vec2, b: Vec2, radius: f32, solid: bool) {
+        let min = ((a.min(b) - radius) / self.cell).floor().max(Vec2::ZERO);
+        let max = ((a.max(b) + radius) / self.cell).ceil();
+        let ab = b - a;
+        for y in min.y as usize..(max.y as usize).min(self.rows) {
+            for x in min.x as usize..(max.x as usize).min(self.cols) {
+                let center = (Vec2::new(x as f32, y as f32) + 0.5) * self.cell;
+                let t = if ab == Vec2::ZERO {
+                    0.0
+                } else {
+                    ((center - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+                };
+                if center.distance_squared(a + ab * t) <= radius * radius {
+                    self.solid[y * self.cols + x] = solid;
+                }
+            }
+        }
+        self.rebuild();
+    }
+
+    /// Signed distance to the nearest wall surface at `p`, bilinearly interpolated.
+    pub fn distance(&self, p: Vec2) -> f32 {
+        let g = (p / self.cell - 0.5).clamp(Vec2::ZERO, Vec2::new(self.cols as f32 - 1.0, self.rows as f32 - 1.0));
+        let (x0, y0) = (g.x as usize, g.y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.cols - 1), (y0 + 1).min(self.rows - 1));
+        let (fx, fy) = (g.x.fract(), g.y.fract());
+        let d = |x: usize, y: usize| self.distance[y * self.cols + x];
+        let top = d(x0, y0) + (d(x1, y0) - d(x0, y0)) * fx;
+        let bottom = d(x0, y1) + (d(x1, y1) - d(x0, y1)) * fx;
+        top + (bottom - top) * fy
+    }
+
+    /// Direction of increasing distance at `p`, pointing out of walls.
+    pub fn gradient(&self, p: Vec2) -> Vec2 {
+        let h = self.cell;
+        Vec2::new(
+            self.distance(p + Vec2::X * h) - self.distance(p - Vec2::X * h),
+            self.distance(p + Vec2::Y * h) - self.distance(p - Vec2::Y * h),
+        )
+    }
+
+    /// Contact of a disc at `pos` with `radius` against the walls:
+    /// the outward normal and penetration depth, if they overlap.
+    pub fn contact(&self, pos: Vec2, radius: f32) -> Option<(Vec2, f32)> {
+        if self.empty {
+            return None;
+        }
+        let d = self.distance(pos);
+        (d < radius).then(|| (self.gradient(pos).try_normalize().unwrap_or(Vec2::NEG_Y), radius - d))
+    }
+
+    fn cell_index(&self, p: Vec2) -> Option<usize> {
+        let (x, y) = ((p.x / self.cell).floor(), (p.y / self.cell).floor());
+        (x >= 0.0 && y >= 0.0 && (x as usize) < self.cols && (y as usize) < self.rows)
+            .then(|| y as usize * self.cols + x as usize)
+    }
+
+    /// Recompute the distance field from the solid mask.
+    fn rebuild(&mut self) {
+        self.empty = !self.solid.contains(&true);
+        if self.empty {
+            self.distance.fill(f32::INFINITY);
+            return;
+        }
+        // cell distances to the nearest solid cell, and inside walls to the nearest empty one
+        let mut outside: Vec<f32> = self.solid.iter().map(|&s| if s { 0.0 } else { f32::INFINITY }).collect();
+        let mut inside: Vec<f32> = self.solid.iter().map(|&s| if s { f32::INFINITY } else { 0.0 }).collect();
+        chamfer(&mut outside, self.cols, self.rows);
+        chamfer(&mut inside, self.cols, self.rows);
+        // surfaces lie half a cell from the cell centers
+        for (i, d) in self.distance.iter_mut().enumerate() {
+            let cells = if self.solid[i] { 0.5 - inside[i] } else { outside[i] - 0.5 };
+            *d = cells * self.cell;
+        }
+    }
+}
+
+/// Two-pass chamfer distance transform in cell units; zero cells are the seeds.
+fn chamfer(d: &mut [f32], cols: usize, rows: usize) {
+    const DIAGONAL: f32 = std::f32::consts::SQRT_2;
+    let forward = [(-1, 0, 1.0), (-1, -1, DIAGONAL), (0, -1, 1.0), (1, -1, DIAGONAL)];
+    let backward = [(1, 0, 1.0), (1, 1, DIAGONAL), (0, 1, 1.0), (-1, 1, DIAGONAL)];
+    let mut relax = |x: usize, y: usize, kernel: &[(isize, isize, f32)]| {
+        for &(dx, dy, w) in kernel {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx >= 0 && ny >= 0 && (nx as usize) < cols && (ny as usize) < rows {
+                let candidate = d[ny as usize * cols + nx as usize] + w;
+                if candidate < d[y * cols + x] {
+                    d[y * cols + x] = candidate;
+                }
+            }
+        }
+    };
+    for y in 0..rows {
+        for x in 0..cols {
+            relax(x, y, &forward);
+        }
+    }
+    for y in (0..rows).rev() {
+        for x in (0..cols).rev() {
+            relax(x, y, &backward);
+        }
+    }
+}
+
+impl TryFrom<SdfData> for SdfGrid {
+    type Error = String;
+
+    /// Checks the sizes before allocating: at least one cell, at most `MAX_CELLS`, and
+    /// runs covering exactly `cols * rows` cells.
+    fn try_from(data: SdfData) -> Result<Self, String> {
+        let cells = data
+            .cols
+            .checked_mul(data.rows)
+            .filter(|&cells| cells > 0 && cells <= MAX_CELLS)
+            .ok_or_else(|| format!("wall grid of {} x {} cells is empty or too large", data.cols, data.rows))?;
+        if !(data.cell.is_finite() && data.cell > 0.0) {
+            return Err(format!("wall grid cell size {} is not positive", data.cell));
+        }
+        let covered: u64 = data.runs.iter().map(|&len| u64::from(len)).sum();
+        if covered != cells as u64 {
+            return Err(format!("wall grid runs cover {covered} cells instead of {cells}"));
+        }
+        let mut grid = Self {
+            cols: data.cols,
+            rows: data.rows,
+            cell: data.cell,
+            solid: Vec::with_capacity(cells),
+            distance: vec![f32::INFINITY; cells],
+            empty: true,
+        };
+        for (run, &len) in data.runs.iter().enumerate() {
+            grid.solid.extend(std::iter::repeat_n(run % 2 == 1, len as usize));
+        }
+        grid.rebuild();
+        Ok(grid)
+    }
+}
+
+impl From<SdfGrid> for SdfData {
+    fn from(grid: SdfGrid) -> Self {
+        let mut runs = Vec::new();
+        let mut current = false;
+        let mut len = 0u32;
+        for &solid in &grid.solid {
+            if solid != current {
+                runs.push(len);
+                current = solid;
+                len = 0;
+            }
+            len += 1;
+        }
+        runs.push(len);
+        Self { cols: grid.cols, rows: grid.rows, cell: grid.cell, runs }
+    }
+}
@@ -0,0 +1,161 @@
+//! Wire format of sensor packets streamed over serial or WebSocket.
+//!
+//! Frame: `A5 5A | kind | len | payload[len] | checksum`, where the checksum is the
+//! wrapping byte sum of kind, len and payload. Multi-byte values are little endian.
+
+/// Start of every frame.
+pub const SYNC: [u8; 2] = [0xA5, 0x5A];
+
+/// Largest payload a frame may carry; longer length bytes are treated as corruption.
+pub const MAX_PAYLOAD: usize = 64;
+
+/// Largest complete frame in bytes.
+pub const MAX_FRAME: usize = SYNC.len() + 2 + MAX_PAYLOAD + 1;
+
+const KIND_ACCEL: u8 = 1;
+const KIND_TIME: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Packet {
+    /// Acceleration in m/s² along the sensor's x, y and z axes.
+    Accel([f32; 3]),
+    /// Sender clock in microseconds since the Unix epoch when the following readings were sampled.
+    Time(u64),
+}
+
+/// Append the framed `packet` to `out`.
+pub fn encode(packet: &Packet, out: &mut Vec<u8>) {
+    let (kind, payload) = match packet {
+        Packet::Accel(axes) => {
+            let mut payload = Vec::with_capacity(12);
+            for axis in axes {
+                payload.extend_from_slice(&axis.to_le_bytes());
+            }
+            (KIND_ACCEL, payload)
+        }
+        Packet::Time(micros) => (KIND_TIME, micros.to_le_bytes().to_vec()),
+    };
+    out.extend_from_slice(&SYNC);
+    out.push(kind);
+    out.push(payload.len() as u8);
+    out.extend_from_slice(&payload);
+    out.push(checksum(kind, &payload));
+}
+
+/// Incremental frame decoder. Accepts arbitrary byte chunks; corrupt or truncated
+/// frames are skipped byte by byte until the next valid frame, so a bad stream never
+/// desynchronizes it for longer than one frame.
+#[derive(Clone, Debug, Default)]
+pub struct PacketDecoder {
+    buffer: Vec<u8>,
+    /// Frames rejected for a bad checksum, length or payload.
+    pub dropped: u64,
+}
+
+impl PacketDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bytes held back waiting for the rest of a frame; at most `MAX_FRAME`.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Feed received bytes, appending every completed packet to `out`.
+    pub fn push(&mut self, bytes: &[u8], out: &mut Vec<Packet>) {
+        self.buffer.extend_from_slice(bytes);
+        let mut start = 0;
+        loop {
+            let rest = &self.buffer[start..];
+            let Some(offset) = rest.windows(2).position(|w| w == SYNC) else {
+                // keep a trailing first sync byte, it may start the next frame
+                start = self.buffer.len() - usize::from(rest.last() == Some(&SYNC[0]));
+                break;
+            };
+            start += offset;
+            let frame = &self.buffer[start..];
+            if frame.len() < 4 {
+                break;
+            }
+            let (kind, len) = (frame[2], frame[3] as usize);
+            if len > MAX_PAYLOAD {
+                self.dropped += 1;
+                start += 1;
+                continue;
+            }
+            if frame.len() < 5 + len {
+                break;
+            }
+            let payload = &frame[4..4 + len];
+            match (frame[4 + len] == checksum(kind, payload)).then(|| parse(kind, payload)) {
+                Some(Some(packet)) => {
+                    out.push(packet);
+                    start += 5 + len;
+                }
+                // a valid frame of an unknown kind is skipped whole
+                Some(None) if !matches!(kind, KIND_ACCEL | KIND_TIME) => start += 5 + len,
+                _ => {
+                    self.dropped += 1;
+                    start += 1;
+                }
+            }
+        }
+        self.buffer.drain(..start);
+    }
+}
+
+fn parse(kind: u8, payload: &[u8]) -> Option<Packet> {
+    match kind {
+        KIND_ACCEL if payload.len() == 12 => {
+            let axis = |i: usize| f32::from_le_bytes(payload[i * 4..i * 4 + 4].try_into().expect("4 bytes"));
+            Some(Packet::Accel([axis(0), axis(1), axis(2)]))
+        }
+        KIND_TIME => payload.try_into().ok().map(|bytes| Packet::Time(u64::from_le_bytes(bytes))),
+        _ => None,
+    }
+}
+
+fn checksum(kind: u8, payload: &[u8]) -> u8 {
+    payload.iter().fold(kind.wrapping_add(payload.len() as u8), |sum, &b| sum.wrapping_add(b))
+}
+
+/// Packets received from a `sensor_bridge` over TCP, polled without blocking.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct TcpSensor {
+    stream: std::net::TcpStream,
+    decoder: PacketDecoder,
+    buffer: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TcpSensor {
+    pub fn connect(addr: &str) -> std::io::Result<Self> {
+        let stream = std::net::TcpStream::connect(addr)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self { stream, decoder: PacketDecoder::new(), buffer: vec![0; 4096] })
+    }
+
+    /// Close the connection, telling the bridge we are gone.
+    pub fn disconnect(self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+
+    /// Append the packets received since the last poll to `out`.
+    /// Returns false once the connection is closed or failed.
+    pub fn poll(&mut self, out: &mut Vec<Packet>) -> bool {
+        use std::io::Read;
+        loop {
+            match self.stream.read(&mut self.buffer) {
+                Ok(0) => return false,
+                Ok(n) => self.decoder.push(&self.buffer[..n], out),
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => return true,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    log::warn!("sensor connection failed: {err}");
+                    return false;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::config::Config;
+use crate::scene::Scene;
+use crate::world::GustShape;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use glam::Vec2;
+
+/// Largest payload `decode_payload` inflates, so a crafted link cannot exhaust memory.
+const MAX_PAYLOAD_BYTES: usize = 1 << 20;
+
+/// Compress `json` with deflate and encode it as unpadded base64url, which needs no
+/// escaping in query strings and fragments.
+pub fn encode_payload(json: &str) -> String {
+    URL_SAFE_NO_PAD.encode(miniz_oxide::deflate::compress_to_vec(json.as_bytes(), 9))
+}
+
+/// Reverse `encode_payload`; `None` for malformed or oversized payloads.
+pub fn decode_payload(text: &str) -> Option<String> {
+    let compressed = URL_SAFE_NO_PAD.decode(text).ok()?;
+    let json = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_PAYLOAD_BYTES).ok()?;
+    String::from_utf8(json).ok()
+}
+
+/// Query parameter (without `&`) carrying `config`, the preset the sharing instance runs,
+/// compressed with `encode_payload`. `None` for the default config, which needs no parameter.
+pub fn preset_query(config: &Config) -> Option<String> {
+    let json = serde_json::to_string(config).ok()?;
+    let default = serde_json::to_string(&Config::default()).ok()?;
+    (json != default).then(|| format!("preset={}", encode_payload(&json)))
+}
+
+/// The preset stored by `preset_query` in `query`, if it has a valid one.
+pub fn preset_from_query(query: &str) -> Option<Config> {
+    let payload = query.trim_start_matches('?').split('&').find_map(|pair| pair.strip_prefix("preset="))?;
+    let Some(json) = decode_payload(payload) else {
+        log::warn!("malformed shared preset");
+        return None;
+    };
+    Config::from_json(&json).map_err(|err| log::warn!("invalid shared preset: {err}")).ok()
+}
+
+/// Encode the seed and key parameters of `scene` as a URL query string (without `?`).
+pub fn to_query(scene: &Scene) -> String {
+    let sim = &scene.simulation;
+    let mut parts = Vec::new();
+    if let Some(seed) = scene.seed {
+        parts.push(format!("seed={seed}"));
+    }
+    parts.push(format!("gravity={},{}", sim.gravity.x, sim.gravity.y));
+    parts.push(format!("wind={},{}", sim.wind.base.x, sim.wind.base.y));
+    parts.push(format!("gust={}", sim.wind.gust));
+    parts.push(format!("gust_frequency={}", sim.wind.frequency));
+    parts.push(format!("gust_shape={:?}", sim.wind.shape));
+    parts.push(format!("drag={},{}", sim.global_drag.x, sim.global_drag.y));
+    parts.push(format!("restitution={}", sim.restitution));
+    parts.push(format!("friction={}", sim.wall_friction));
+    parts.push(format!("surface_friction={}", sim.surface_friction));
+    parts.push(format!("dt={}", sim.dt));
+    parts.join("&")
+}
+
+/// Override parameters of `scene` from a query string produced by `to_query`.
+///
+/// A leading `?` is ignored, as are unknown keys and malformed values.
+pub fn apply_query(scene: &mut Scene, query: &str) {
+    let sim = &mut scene.simulation;
+    for pair in query.trim_start_matches('?').split('&') {
+        let Some((key, value)) = pair.split_once('=') else {
+            continue;
+        };
+        match key {
+            "seed" => scene.seed = value.parse().ok().or(scene.seed),
+            "gravity" => sim.gravity = parse_vec2(value).unwrap_or(sim.gravity),
+            "wind" => sim.wind.base = parse_vec2(value).unwrap_or(sim.wind.base),
+            "gust" => sim.wind.gust = value.parse().unwrap_or(sim.wind.gust),
+            "gust_frequency" => sim.wind.frequency = value.parse().unwrap_or(sim.wind.frequency),
+            "gust_shape" => sim.wind.shape = parse_gust_shape(value).unwrap_or(sim.wind.shape),
+            "drag" => sim.global_drag = parse_vec2(value).unwrap_or(sim.global_drag),
+            "restitution" => sim.restitution = value.parse().unwrap_or(sim.restitution),
+            "friction" => sim.wall_friction = value.parse().unwrap_or(sim.wall_friction),
+            "surface_friction" => sim.surface_friction = value.parse().unwrap_or(sim.surface_friction),
+            "dt" => sim.dt = value.parse().unwrap_or(sim.dt),
+            _ => (),
+        }
+    }
+}
+
+fn parse_gust_shape(value: &str) -> Option<GustShape> {
+    match value {
+        "Sine" => Some(GustShape::Sine),
+        "Noise" => Some(GustShape::Noise),
+        _ => None,
+    }
+}
+
+fn parse_vec2(value: &str) -> Option<Vec2> {
+    let (x, y) = value.split_once(',')?;
+    Some(Vec2::new(x.parse().ok()?, y.parse().ok()?))
+}
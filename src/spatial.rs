@@ -0,0 +1,129 @@
+use crate::arena::FrameArena;
+use crate::memory::vec_bytes;
+use glam::Vec2;
+
+/// Cell size in pixels of the grid `ParticleSystem` maintains for neighbor queries.
+pub const GRID_CELL: f32 = 8.0;
+
+/// Uniform grid over the arena bucketing particle indices by position, rebuilt each
+/// step with a counting sort. Positions outside the arena go to the border cells.
+///
+/// Along wrapped axes opposite edges are adjacent, so queries near one edge also
+/// return the particles just across the other.
+#[derive(Clone, Debug, Default)]
+pub struct SpatialGrid {
+    cell: f32,
+    cols: usize,
+    rows: usize,
+    extent: Vec2,
+    /// Whether the x and y axes wrap around.
+    wrap: [bool; 2],
+    /// Offset of each cell's first entry in `indices`, plus one past the last cell.
+    starts: Vec<u32>,
+    indices: Vec<u32>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bucket `positions` into square cells of `cell` pixels covering `width` x `height`,
+    /// with the fill cursors taken from `scratch`. `wrap` makes the x and y axes periodic.
+    pub fn build(
+        &mut self,
+        positions: &[Vec2],
+        cell: f32,
+        (width, height): (usize, usize),
+        wrap: [bool; 2],
+        scratch: &mut FrameArena<u32>,
+    ) {
+        self.cell = cell.max(1.0);
+        self.cols = (width as f32 / self.cell).ceil().max(1.0) as usize;
+        self.rows = (height as f32 / self.cell).ceil().max(1.0) as usize;
+        self.extent = Vec2::new(width as f32, height as f32);
+        self.wrap = wrap;
+        self.starts.clear();
+        self.starts.resize(self.cols * self.rows + 1, 0);
+        for &p in positions {
+            let c = self.cell_index(p);
+            self.starts[c + 1] += 1;
+        }
+        for c in 1..self.starts.len() {
+            self.starts[c] += self.starts[c - 1];
+        }
+        // fill each cell from its end, leaving `starts` intact afterwards
+        let next = scratch.alloc_slice(&self.starts[1..]);
+        let next = scratch.get_mut(next);
+        self.indices.clear();
+        self.indices.resize(positions.len(), 0);
+        for (i, &p) in positions.iter().enumerate().rev() {
+            let c = self.cell_index(p);
+            next[c] -= 1;
+            self.indices[next[c] as usize] = i as u32;
+        }
+    }
+
+    /// Grid of `cols` x `rows` cells of `cell` pixels over `extent`, with neither axis
+    /// wrapping, from buckets sorted elsewhere (e.g. on the GPU) in the layout `build` makes.
+    pub(crate) fn from_sorted(
+        cell: f32,
+        (cols, rows): (usize, usize),
+        extent: Vec2,
+        starts: Vec<u32>,
+        indices: Vec<u32>,
+    ) -> Self {
+        debug_assert_eq!(starts.len(), cols * rows + 1);
+        Self { cell, cols, rows, extent, wrap: [false; 2], starts, indices }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.starts) + vec_bytes(&self.indices)
+    }
+
+    /// Every index in the cells overlapping the square of half size `radius` around `pos`,
+    /// including across wrapped edges; callers filter by exact distance. A negative or NaN
+    /// `radius` is treated as 0.
+    pub fn near(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let radius = radius.max(0.0);
+        let (xs, ys) = if self.indices.is_empty() {
+            ([None; 2], [None; 2])
+        } else {
+            (self.spans(pos.x, radius, 0, self.cols), self.spans(pos.y, radius, 1, self.rows))
+        };
+        ys.into_iter().flatten().flat_map(|(y0, y1)| y0..=y1).flat_map(move |y| {
+            xs.into_iter().flatten().flat_map(move |(x0, x1)| {
+                let row = y * self.cols;
+                let (start, end) = (self.starts[row + x0] as usize, self.starts[row + x1 + 1] as usize);
+                self.indices[start..end].iter().map(|&i| i as usize)
+            })
+        })
+    }
+
+    /// Cell ranges covering `center ± radius` along `axis`: one range, or two when the
+    /// interval sticks out over a wrapped edge and continues at the opposite one.
+    fn spans(&self, center: f32, radius: f32, axis: usize, cells: usize) -> [Option<(usize, usize)>; 2] {
+        let cell_of = |p: f32| (p / self.cell).clamp(0.0, (cells - 1) as f32) as usize;
+        let extent = self.extent[axis];
+        if !self.wrap[axis] {
+            return [Some((cell_of(center - radius), cell_of(center + radius))), None];
+        }
+        // spanning (almost) the whole axis: visit each cell once
+        if 2.0 * radius >= extent - self.cell {
+            return [Some((0, cells - 1)), None];
+        }
+        let lo = (center - radius).rem_euclid(extent);
+        let hi = lo + 2.0 * radius;
+        if hi < extent {
+            [Some((cell_of(lo), cell_of(hi))), None]
+        } else {
+            [Some((cell_of(lo), cells - 1)), Some((0, cell_of(hi - extent)))]
+        }
+    }
+
+    fn cell_index(&self, p: Vec2) -> usize {
+        let x = (p.x / self.cell).clamp(0.0, (self.cols - 1) as f32) as usize;
+        let y = (p.y / self.cell).clamp(0.0, (self.rows - 1) as f32) as usize;
+        y * self.cols + x
+    }
+}
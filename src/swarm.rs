@@ -0,0 +1,197 @@
+use crate::force::{Force, ParticleView};
+use crate::group::GroupFilter;
+use crate::memory::vec_bytes;
+use crate::obstacle::Shape;
+use crate::sdf::SdfGrid;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Path cost of a straight and a diagonal step between cells, a chamfer approximation
+/// of the Euclidean distance.
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+/// Directions towards a goal around the obstacles, on a coarse grid: a distance transform
+/// from the goal cell over the free cells, each pointing to its closest neighbour.
+#[derive(Clone, Debug)]
+pub struct NavGrid {
+    cols: usize,
+    rows: usize,
+    cell: f32,
+    goal: Vec2,
+    /// Cells overlapping an obstacle or a wall.
+    blocked: Vec<bool>,
+    /// Unit direction to walk in from each cell; zero where blocked or unreachable.
+    direction: Vec<Vec2>,
+}
+
+impl NavGrid {
+    /// Grid of `cell` pixel cells over a `width` x `height` arena, without a path yet.
+    pub fn new(width: usize, height: usize, cell: f32) -> Self {
+        let cell = cell.max(1.0);
+        let cols = (width as f32 / cell).ceil().max(1.0) as usize;
+        let rows = (height as f32 / cell).ceil().max(1.0) as usize;
+        Self {
+            cols,
+            rows,
+            cell,
+            goal: Vec2::NAN,
+            blocked: vec![false; cols * rows],
+            direction: vec![Vec2::ZERO; cols * rows],
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.blocked) + vec_bytes(&self.direction)
+    }
+
+    fn center(&self, index: usize) -> Vec2 {
+        Vec2::new((index % self.cols) as f32 + 0.5, (index / self.cols) as f32 + 0.5) * self.cell
+    }
+
+    fn index_of(&self, pos: Vec2) -> usize {
+        let x = ((pos.x / self.cell).max(0.0) as usize).min(self.cols - 1);
+        let y = ((pos.y / self.cell).max(0.0) as usize).min(self.rows - 1);
+        y * self.cols + x
+    }
+
+    /// Re-mark the cells `blocked_at` reports as blocked and recompute the directions to
+    /// `goal`, unless neither changed since the last call.
+    pub fn update(&mut self, goal: Vec2, blocked_at: impl Fn(Vec2) -> bool) {
+        let mut changed = goal != self.goal;
+        for i in 0..self.blocked.len() {
+            let blocked = blocked_at(self.center(i));
+            changed |= blocked != self.blocked[i];
+            self.blocked[i] = blocked;
+        }
+        if changed {
+            self.goal = goal;
+            self.solve();
+        }
+    }
+
+    /// Dijkstra from the goal cell over the free cells, then point each cell at its
+    /// nearest neighbour.
+    fn solve(&mut self) {
+        let mut distance = vec![u32::MAX; self.blocked.len()];
+        let goal = self.index_of(self.goal);
+        let mut queue = BinaryHeap::new();
+        distance[goal] = 0;
+        queue.push(Reverse((0, goal)));
+        while let Some(Reverse((d, index))) = queue.pop() {
+            if d > distance[index] {
+                continue;
+            }
+            for (next, cost) in self.neighbours(index) {
+                if d + cost < distance[next] {
+                    distance[next] = d + cost;
+                    queue.push(Reverse((d + cost, next)));
+                }
+            }
+        }
+        for i in 0..self.direction.len() {
+            self.direction[i] = if i == goal {
+                (self.goal - self.center(i)).normalize_or_zero()
+            } else if self.blocked[i] || distance[i] == u32::MAX {
+                Vec2::ZERO
+            } else {
+                self.neighbours(i)
+                    .min_by_key(|&(next, _)| distance[next])
+                    .map_or(Vec2::ZERO, |(next, _)| (self.center(next) - self.center(i)).normalize_or_zero())
+            };
+        }
+    }
+
+    /// Free cells one step from `index` with the step cost; diagonal steps may not cut
+    /// past a blocked corner.
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let (cols, rows) = (self.cols as isize, self.rows as isize);
+        let (x, y) = (index as isize % cols, index as isize / cols);
+        let free = move |x: isize, y: isize| x >= 0 && y >= 0 && x < cols && y < rows && !self.blocked[(y * cols + x) as usize];
+        [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                let straight = dx == 0 || dy == 0;
+                let open = free(nx, ny) && (straight || (free(nx, y) && free(x, ny)));
+                open.then_some(((ny * cols + nx) as usize, if straight { STRAIGHT } else { DIAGONAL }))
+            })
+    }
+
+    /// Direction to walk in at `pos`, blended between the surrounding cells.
+    pub fn direction_at(&self, pos: Vec2) -> Vec2 {
+        let max = Vec2::new((self.cols - 1) as f32, (self.rows - 1) as f32);
+        let cell = (pos / self.cell - 0.5).clamp(Vec2::ZERO, max);
+        let x0 = cell.x as usize;
+        let y0 = cell.y as usize;
+        let (x1, y1) = ((x0 + 1).min(self.cols - 1), (y0 + 1).min(self.rows - 1));
+        let t = cell - Vec2::new(x0 as f32, y0 as f32);
+        let at = |x: usize, y: usize| self.direction[y * self.cols + x];
+        let top = at(x0, y0).lerp(at(x1, y0), t.x);
+        let bottom = at(x0, y1).lerp(at(x1, y1), t.x);
+        top.lerp(bottom, t.y).normalize_or_zero()
+    }
+}
+
+/// Goal-seeking behavior for ants and swarms: particles steer towards `speed` along the
+/// shortest path to `goal` around obstacles and painted walls, recomputed when those move.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Swarm {
+    pub goal: Vec2,
+    /// Speed particles try to reach along the path.
+    pub speed: f32,
+    /// How fast particles adopt the path velocity, per unit of simulation time.
+    pub steering: f32,
+    /// Width of a path grid cell in pixels; gaps narrower than a cell count as blocked.
+    #[serde(default = "default_cell")]
+    pub cell: f32,
+    /// Groups of particles that follow the path; the others ignore it.
+    #[serde(default, skip_serializing_if = "GroupFilter::is_all")]
+    pub filter: GroupFilter,
+    #[serde(skip)]
+    nav: Option<NavGrid>,
+}
+
+fn default_cell() -> f32 {
+    8.0
+}
+
+impl Swarm {
+    pub fn new(goal: Vec2, speed: f32, steering: f32) -> Self {
+        Self { goal, speed, steering, cell: default_cell(), filter: GroupFilter::All, nav: None }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.nav.as_ref().map_or(0, NavGrid::memory_bytes)
+    }
+
+    /// Bring the path up to date with the goal and the obstacles of a `width` x `height` arena.
+    pub fn update_path(&mut self, width: usize, height: usize, obstacles: &[Shape], walls: &SdfGrid) {
+        let half = self.cell.max(1.0) * 0.5;
+        if self.nav.as_ref().is_some_and(|nav| nav.cell != 2.0 * half) {
+            self.nav = None;
+        }
+        let nav = self.nav.get_or_insert_with(|| NavGrid::new(width, height, self.cell));
+        nav.update(self.goal, |center| {
+            obstacles.iter().any(|shape| shape.contact(center, half).is_some())
+                || (!walls.is_empty() && walls.distance(center) < half)
+        });
+    }
+
+    /// Direction along the path at `pos`; zero before the first `update_path`.
+    pub fn direction_at(&self, pos: Vec2) -> Vec2 {
+        self.nav.as_ref().map_or(Vec2::ZERO, |nav| nav.direction_at(pos))
+    }
+}
+
+impl Force for Swarm {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        if !self.filter.applies(particle.group) {
+            return Vec2::ZERO;
+        }
+        let target = self.direction_at(particle.position) * self.speed;
+        (target - particle.velocity) * self.steering * particle.mass
+    }
+}
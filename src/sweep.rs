@@ -0,0 +1,173 @@
+use crate::batch::{Batch, BatchStats};
+use crate::format::NumberFormat;
+use crate::world::SimParams;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Evenly spaced values from `start` to `end`, both included.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SweepRange {
+    pub start: f32,
+    pub end: f32,
+    pub steps: usize,
+}
+
+impl SweepRange {
+    pub fn values(&self) -> Vec<f32> {
+        match self.steps {
+            0 => Vec::new(),
+            1 => vec![self.start],
+            n => (0..n).map(|i| self.start + (self.end - self.start) * i as f32 / (n - 1) as f32).collect(),
+        }
+    }
+}
+
+/// Runs a batch for every combination of simulation parameter values.
+///
+/// Parameters are paths into `SimParams` as written in JSON, e.g. `"restitution"`,
+/// `"gravity.1"` or `"turbulence.strength"`. A path to a vector sets all its components,
+/// so `"global_drag"` sweeps both axes together.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sweep {
+    /// Base run; its `simulation` (or the default parameters) is varied.
+    pub batch: Batch,
+    pub params: BTreeMap<String, SweepRange>,
+    /// Decimal and field separators of the CSV.
+    pub format: NumberFormat,
+}
+
+/// One combination of parameter values and the outcome of its batch.
+#[derive(Clone, Debug)]
+pub struct SweepRun {
+    pub values: Vec<f32>,
+    pub stats: BatchStats,
+}
+
+impl Sweep {
+    /// Every combination of parameter values, in the order of `params`.
+    pub fn combinations(&self) -> Vec<Vec<f32>> {
+        let mut combinations = vec![Vec::new()];
+        for range in self.params.values() {
+            let values = range.values();
+            combinations = combinations
+                .iter()
+                .flat_map(|prefix| {
+                    values.iter().map(move |&value| {
+                        let mut combination = prefix.clone();
+                        combination.push(value);
+                        combination
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+
+    /// The batch to run for one combination from `combinations`.
+    pub fn batch_for(&self, values: &[f32]) -> Result<Batch, String> {
+        with_params(&self.batch, self.params.keys(), values)
+    }
+
+    /// Run all combinations on up to `threads` threads. All runs share one seed so
+    /// they differ only in the swept parameters.
+    pub fn run(&self, threads: usize) -> Result<Vec<SweepRun>, String> {
+        let mut sweep = self.clone();
+        sweep.batch.seed.get_or_insert_with(rand::random);
+        let combinations = sweep.combinations();
+        let batches = combinations
+            .iter()
+            .map(|values| sweep.batch_for(values))
+            .collect::<Result<Vec<_>, String>>()?;
+        let runs = combinations
+            .into_iter()
+            .zip(run_parallel(&batches, threads))
+            .map(|(values, stats)| SweepRun { values, stats })
+            .collect();
+        Ok(runs)
+    }
+
+    /// One CSV row per run: the parameter values followed by the outcome metrics.
+    pub fn to_csv(&self, runs: &[SweepRun]) -> String {
+        let sep = self.format.csv_separator();
+        let mut csv = String::new();
+        for name in self.params.keys() {
+            let _ = write!(csv, "{name}{sep}");
+        }
+        let columns = ["live", "mean_speed", "max_speed", "kinetic_energy", "centroid_x", "centroid_y", "spread"];
+        let _ = writeln!(csv, "{}", columns.join(&sep.to_string()));
+        for run in runs {
+            for &value in &run.values {
+                let _ = write!(csv, "{}{sep}", self.format.export(value));
+            }
+            let s = &run.stats;
+            let metrics = [s.mean_speed, s.max_speed, s.kinetic_energy, s.centroid.x, s.centroid.y, s.spread];
+            let _ = write!(csv, "{}", s.live);
+            for metric in metrics {
+                let _ = write!(csv, "{sep}{}", self.format.export(metric));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// `batch` with the simulation parameters at `paths` (see `Sweep`) set to `values`.
+pub(crate) fn with_params<'a>(
+    batch: &Batch,
+    paths: impl IntoIterator<Item = &'a String>,
+    values: &[f32],
+) -> Result<Batch, String> {
+    let base = batch.simulation.clone().unwrap_or_default();
+    let mut json = serde_json::to_value(base).map_err(|err| err.to_string())?;
+    for (path, &value) in paths.into_iter().zip(values) {
+        set_path(&mut json, path, value)?;
+    }
+    let simulation: SimParams = serde_json::from_value(json).map_err(|err| err.to_string())?;
+    Ok(Batch { simulation: Some(simulation), ..batch.clone() })
+}
+
+/// Run `batches` split over up to `threads` threads, returning their stats in order.
+pub(crate) fn run_parallel(batches: &[Batch], threads: usize) -> Vec<BatchStats> {
+    let chunk = batches.len().div_ceil(threads.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = batches
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|batch| batch.run_stats()).collect::<Vec<_>>()))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().expect("batch worker panicked")).collect()
+    })
+}
+
+/// Set the number at a dotted `path` in `json`, creating missing objects on the way
+/// (so optional parameter groups fall back to their defaults for the other fields).
+fn set_path(json: &mut Value, path: &str, value: f32) -> Result<(), String> {
+    let mut target = json;
+    for key in path.split('.') {
+        // unset groups have no fields yet; elsewhere unknown names are typos
+        let created = target.is_null();
+        if created {
+            *target = Value::Object(Default::default());
+        }
+        target = match target {
+            Value::Object(map) => {
+                if !created && !map.contains_key(key) {
+                    return Err(format!("{path}: unknown parameter {key}"));
+                }
+                map.entry(key).or_insert(Value::Null)
+            }
+            Value::Array(items) => {
+                let index: usize = key.parse().map_err(|_| format!("{path}: {key} is not an index"))?;
+                items.get_mut(index).ok_or_else(|| format!("{path}: index {index} out of range"))?
+            }
+            _ => return Err(format!("{path}: {key} is not a parameter group")),
+        };
+    }
+    match target {
+        Value::Array(items) => items.iter_mut().for_each(|item| *item = value.into()),
+        other => *other = value.into(),
+    }
+    Ok(())
+}
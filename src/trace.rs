@@ -0,0 +1,120 @@
+use crate::colormap::Palette;
+use crate::format::NumberFormat;
+use crate::world::ParticleSystem;
+use glam::Vec2;
+use std::fmt::Write as _;
+
+/// Most particles traced at once, one per categorical palette color.
+pub const MAX_TRACES: usize = 8;
+
+/// Points recorded per trace before it stops growing.
+pub const MAX_TRACE_POINTS: usize = 10_000;
+
+/// Full trajectories of a few selected particles.
+#[derive(Default)]
+pub struct PathTracer {
+    traces: Vec<Trace>,
+    step: u64,
+    /// Path colors are its categories, one per trace slot.
+    palette: Palette,
+}
+
+struct Trace {
+    /// Stable id of the traced particle, see `ParticleSystem::ids`.
+    particle: u64,
+    /// Index the particle had last step; it moves when others die.
+    index: usize,
+    first_step: u64,
+    points: Vec<Vec2>,
+    /// Cleared once the particle dies.
+    active: bool,
+}
+
+impl PathTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracing the live particle nearest to `pos` within `max_distance`.
+    /// Returns false if there is none or all trace slots are taken.
+    pub fn select_near(&mut self, particles: &ParticleSystem, pos: Vec2, max_distance: f32) -> bool {
+        if self.traces.len() >= MAX_TRACES {
+            return false;
+        }
+        let nearest = particles
+            .positions()
+            .iter()
+            .enumerate()
+            .filter(|&(_, p)| p.distance(pos) <= max_distance)
+            .filter(|&(i, _)| !self.traces.iter().any(|t| t.active && t.particle == particles.ids()[i]))
+            .min_by(|a, b| a.1.distance_squared(pos).total_cmp(&b.1.distance_squared(pos)));
+        let Some((index, &start)) = nearest else {
+            return false;
+        };
+        let particle = particles.ids()[index];
+        self.traces.push(Trace { particle, index, first_step: self.step, points: vec![start], active: true });
+        true
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn clear(&mut self) {
+        self.traces.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    /// Append the current position of every traced particle; call once per simulation step.
+    pub fn record(&mut self, particles: &ParticleSystem) {
+        self.step += 1;
+        for trace in self.traces.iter_mut().filter(|t| t.active) {
+            match particles.index_of(trace.particle, trace.index) {
+                None => trace.active = false,
+                Some(index) => {
+                    trace.index = index;
+                    if trace.points.len() < MAX_TRACE_POINTS {
+                        trace.points.push(particles.positions()[index]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw the paths as colored polylines into an RGBA frame `frame_width` pixels wide.
+    pub fn draw(&self, frame: &mut [u8], frame_width: usize) {
+        let frame_height = frame.len() / 4 / frame_width;
+        for (trace, color) in self.traces.iter().zip(self.palette.categories()) {
+            for segment in trace.points.windows(2) {
+                let (from, to) = (segment[0], segment[1]);
+                let steps = (to - from).abs().max_element().ceil().max(1.0) as usize;
+                for step in 0..=steps {
+                    let p = from.lerp(to, step as f32 / steps as f32);
+                    let (x, y) = (p.x as usize, p.y as usize);
+                    if p.x >= 0.0 && p.y >= 0.0 && x < frame_width && y < frame_height {
+                        let idx = (y * frame_width + x) * 4;
+                        frame[idx..idx + 3].copy_from_slice(color);
+                        frame[idx + 3] = 0xFF;
+                    }
+                }
+            }
+        }
+    }
+
+    /// All recorded points as CSV: `trace,particle,step,x,y`, written in `format`.
+    pub fn to_csv(&self, format: &NumberFormat) -> String {
+        let sep = format.csv_separator();
+        let mut csv = format!("trace{sep}particle{sep}step{sep}x{sep}y\n");
+        for (n, trace) in self.traces.iter().enumerate() {
+            for (k, p) in trace.points.iter().enumerate() {
+                let step = trace.first_step + k as u64;
+                let (x, y) = (format.export(p.x), format.export(p.y));
+                let _ = writeln!(csv, "{n}{sep}{}{sep}{step}{sep}{x}{sep}{y}", trace.particle);
+            }
+        }
+        csv
+    }
+}
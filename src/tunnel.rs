@@ -0,0 +1,68 @@
+use crate::flow::{Sink, Source};
+use crate::obstacle::Shape;
+use crate::world::ParticleSystem;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Flow visualization preset: particles stream in along the left edge, leave through
+/// the right edge and flow around an obstacle in the middle and any shapes drawn in
+/// edit mode.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindTunnel {
+    /// Speed of the inflow, to the right.
+    pub speed: f32,
+    /// Particles entering per unit of simulation time.
+    pub rate: f32,
+    /// Radius of the circle in the middle of the arena; 0 leaves it out.
+    pub obstacle_radius: f32,
+    /// Lifetime of the entering particles; `None` keeps them until they leave.
+    pub lifetime: Option<f32>,
+}
+
+impl Default for WindTunnel {
+    fn default() -> Self {
+        Self {
+            speed: 2.0,
+            rate: 8.0,
+            obstacle_radius: 30.0,
+            lifetime: None,
+        }
+    }
+}
+
+impl WindTunnel {
+    /// Turn gravity and drag off (so the stream reaches the far side) and add the inflow
+    /// source, the outflow sink and the obstacle, unless already there (e.g. from a scene
+    /// saved in the tunnel).
+    pub fn apply(&self, particles: &mut ParticleSystem) {
+        let (width, height) = particles.size();
+        let (width, height) = (width as f32, height as f32);
+        // deep enough that no particle crosses the strips within one step
+        let depth = (2.0 * self.speed).max(4.0);
+        particles.simulation.gravity = Vec2::ZERO;
+        particles.simulation.global_drag = Vec2::ZERO;
+        let source = Source {
+            region: Shape::rect(Vec2::ZERO, Vec2::new(depth, height)),
+            velocity: Vec2::new(self.speed, 0.0),
+            rate: Some(self.rate),
+            lifetime: self.lifetime,
+        };
+        if !particles.sources.contains(&source) {
+            particles.sources.push(source);
+        }
+        let sink = Sink {
+            region: Shape::rect(Vec2::new(width - depth, 0.0), Vec2::new(width, height)),
+        };
+        if !particles.sinks.contains(&sink) {
+            particles.sinks.push(sink);
+        }
+        let obstacle = Shape::Circle {
+            center: Vec2::new(width, height) / 2.0,
+            radius: self.obstacle_radius,
+        };
+        if self.obstacle_radius > 0.0 && !particles.obstacles.contains(&obstacle) {
+            particles.obstacles.push(obstacle);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::integrator::IntegratorKind;
+use crate::world::{GravityWell, ParticleSystem, SimParams};
+use glam::Vec2;
+
+/// Scenarios with a known analytic solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scenario {
+    /// Constant gravity from an initial velocity, no drag: a parabola.
+    Projectile,
+    /// Circular orbit around an unsoftened `GravityWell`.
+    Orbit,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 2] = [Scenario::Projectile, Scenario::Orbit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::Projectile => "projectile",
+            Scenario::Orbit => "orbit",
+        }
+    }
+}
+
+/// Integration error of one scenario run.
+#[derive(Clone, Copy, Debug)]
+pub struct ValidationResult {
+    pub scenario: Scenario,
+    pub integrator: IntegratorKind,
+    pub dt: f32,
+    pub steps: usize,
+    /// Largest distance from the analytic position along the run.
+    pub max_error: f32,
+    /// Distance from the analytic position at the end.
+    pub final_error: f32,
+}
+
+// Large enough that the walls never interfere
+const ARENA: usize = 1000;
+const PROJECTILE_GRAVITY: Vec2 = Vec2::new(0.0, 0.5);
+const PROJECTILE_VELOCITY: Vec2 = Vec2::new(3.0, -8.0);
+const PROJECTILE_DURATION: f32 = 30.0;
+// G·M giving one revolution every 20π of simulation time
+const ORBIT_MASS: f32 = 1.0e4;
+const ORBIT_RADIUS: f32 = 100.0;
+
+/// Run `scenario` with `integrator` for a fixed span of simulation time in steps of `dt`.
+///
+/// The gravity well pulls inside the integrator, so each integrator sees the force at its
+/// own probe states. Fails unless `dt` is positive and finite.
+pub fn run(scenario: Scenario, integrator: IntegratorKind, dt: f32) -> Result<ValidationResult, String> {
+    if !(dt.is_finite() && dt > 0.0) {
+        return Err(format!("time step {dt} is not positive"));
+    }
+    let center = Vec2::splat(ARENA as f32 / 2.0);
+    let mut particles = ParticleSystem::new(1, ARENA, ARENA);
+    particles.simulation = SimParams { global_drag: Vec2::ZERO, gravity: Vec2::ZERO, integrator, ..SimParams::default() };
+
+    let well = GravityWell::new(center, ORBIT_MASS, 0.0);
+    let omega = well.orbital_speed(ORBIT_RADIUS) / ORBIT_RADIUS;
+    let (start, velocity, duration) = match scenario {
+        Scenario::Projectile => {
+            particles.simulation.gravity = PROJECTILE_GRAVITY;
+            (center, PROJECTILE_VELOCITY, PROJECTILE_DURATION)
+        }
+        // one full revolution
+        Scenario::Orbit => {
+            particles.gravity_wells.add(well);
+            let velocity = Vec2::new(0.0, omega * ORBIT_RADIUS);
+            (center + Vec2::new(ORBIT_RADIUS, 0.0), velocity, std::f32::consts::TAU / omega)
+        }
+    };
+    let analytic = |t: f32| match scenario {
+        Scenario::Projectile => start + velocity * t + 0.5 * PROJECTILE_GRAVITY * t * t,
+        Scenario::Orbit => center + Vec2::from_angle(omega * t) * ORBIT_RADIUS,
+    };
+    particles.spawn(start.into(), velocity.into(), 1.0, f32::INFINITY);
+
+    let steps = (duration / dt).round().max(1.0) as usize;
+    let mut max_error = 0.0f32;
+    let mut error = 0.0;
+    for step in 1..=steps {
+        particles.update_with_dt(dt);
+        error = particles.positions()[0].distance(analytic(step as f32 * dt));
+        max_error = max_error.max(error);
+    }
+    Ok(ValidationResult { scenario, integrator, dt, steps, max_error, final_error: error })
+}
+
+/// Run every scenario with every integrator at each of `dts`, skipping invalid time steps
+/// with a warning.
+pub fn run_all(dts: &[f32]) -> Vec<ValidationResult> {
+    let (dts, invalid): (Vec<f32>, Vec<f32>) = dts.iter().partition(|dt| dt.is_finite() && **dt > 0.0);
+    for dt in invalid {
+        log::warn!("skipping validation at time step {dt}, which is not positive");
+    }
+    let mut results = Vec::new();
+    for scenario in Scenario::ALL {
+        for integrator in IntegratorKind::ALL {
+            results.extend(dts.iter().filter_map(|&dt| run(scenario, integrator, dt).ok()));
+        }
+    }
+    results
+}
+
+pub fn log_report(results: &[ValidationResult]) {
+    log::info!("integration error vs. analytic solution:");
+    for result in results {
+        log::info!(
+            "  {:<10} {:<20} dt {:<6} {:>6} steps: {:.4} max, {:.4} final",
+            result.scenario.name(),
+            result.integrator.name(),
+            result.dt,
+            result.steps,
+            result.max_error,
+            result.final_error
+        );
+    }
+}
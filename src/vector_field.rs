@@ -0,0 +1,217 @@
+use crate::force::{Force, ParticleView};
+use crate::memory::vec_bytes;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// First bytes of the binary field format written by `VectorField::to_bytes`.
+pub const FIELD_MAGIC: &[u8; 4] = b"RWVF";
+
+/// Velocities on a regular grid, e.g. exported from a CFD solver or a weather model,
+/// sampled bilinearly in between the grid points.
+#[derive(Clone, Debug)]
+pub struct VectorField {
+    cols: usize,
+    rows: usize,
+    /// Position of the first grid point.
+    origin: Vec2,
+    /// Distance between neighbouring grid points.
+    spacing: Vec2,
+    /// Row-major, `cols * rows` values.
+    values: Vec<Vec2>,
+}
+
+impl VectorField {
+    /// Field of `cols` x `rows` points starting at `origin`; `None` unless there
+    /// are at least 2 x 2 points, a positive spacing and one value per point, all finite.
+    pub fn new(cols: usize, rows: usize, origin: Vec2, spacing: Vec2, values: Vec<Vec2>) -> Option<Self> {
+        let valid = cols >= 2
+            && rows >= 2
+            && origin.is_finite()
+            && spacing.is_finite()
+            && spacing.cmpgt(Vec2::ZERO).all()
+            && values.len() == cols * rows
+            && values.iter().all(|v| v.is_finite());
+        valid.then_some(Self { cols, rows, origin, spacing, values })
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.values)
+    }
+
+    /// Grid points per row and column.
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// Corners of the area covered by the grid.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let extent = self.spacing * Vec2::new((self.cols - 1) as f32, (self.rows - 1) as f32);
+        (self.origin, self.origin + extent)
+    }
+
+    /// Stretch the grid over a `width` x `height` arena, whatever its own units.
+    pub fn fit(&mut self, width: usize, height: usize) {
+        self.origin = Vec2::ZERO;
+        self.spacing = Vec2::new(
+            width as f32 / (self.cols - 1) as f32,
+            height as f32 / (self.rows - 1) as f32,
+        );
+    }
+
+    /// Interpolated velocity at `pos`; positions outside the grid take the nearest edge value.
+    pub fn sample(&self, pos: Vec2) -> Vec2 {
+        let max = Vec2::new((self.cols - 1) as f32, (self.rows - 1) as f32);
+        let cell = ((pos - self.origin) / self.spacing).clamp(Vec2::ZERO, max);
+        let x0 = (cell.x as usize).min(self.cols - 2);
+        let y0 = (cell.y as usize).min(self.rows - 2);
+        let t = cell - Vec2::new(x0 as f32, y0 as f32);
+        let at = |x: usize, y: usize| self.values[y * self.cols + x];
+        let top = at(x0, y0).lerp(at(x0 + 1, y0), t.x);
+        let bottom = at(x0, y0 + 1).lerp(at(x0 + 1, y0 + 1), t.x);
+        top.lerp(bottom, t.y)
+    }
+
+    /// Parse `x,y,u,v` lines, one per grid point in any order. Blank lines, `#` comments
+    /// and a leading header line are skipped; the points must form a complete, evenly
+    /// spaced grid of finite numbers, at least 2 x 2.
+    pub fn from_csv(text: &str) -> Result<Self, String> {
+        let mut points = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Result<Vec<f32>, _> = line.split([',', ';', '\t']).map(|f| f.trim().parse::<f32>()).collect();
+            match fields {
+                Ok(fields) if fields.len() == 4 && fields.iter().any(|f| !f.is_finite()) => {
+                    return Err(format!("line {}: x,y,u,v must be finite", number + 1));
+                }
+                Ok(fields) if fields.len() == 4 => {
+                    points.push((Vec2::new(fields[0], fields[1]), Vec2::new(fields[2], fields[3])));
+                }
+                Err(_) if points.is_empty() => continue, // header
+                _ => return Err(format!("line {}: expected four numbers x,y,u,v", number + 1)),
+            }
+        }
+        let axis = |coord: fn(&Vec2) -> f32| {
+            let mut values: Vec<f32> = points.iter().map(|(p, _)| coord(p)).collect();
+            values.sort_by(f32::total_cmp);
+            values.dedup();
+            values
+        };
+        let (xs, ys) = (axis(|p| p.x), axis(|p| p.y));
+        if xs.len() < 2 || ys.len() < 2 {
+            return Err("grid needs at least 2 x 2 points".into());
+        }
+        if points.len() != xs.len() * ys.len() {
+            return Err(format!(
+                "{} points do not form a complete {} x {} grid",
+                points.len(),
+                xs.len(),
+                ys.len()
+            ));
+        }
+        let mut values = vec![Vec2::ZERO; points.len()];
+        let mut seen = vec![false; points.len()];
+        for (p, v) in points {
+            let x = xs.binary_search_by(|x| x.total_cmp(&p.x)).expect("x from the same points");
+            let y = ys.binary_search_by(|y| y.total_cmp(&p.y)).expect("y from the same points");
+            let index = y * xs.len() + x;
+            if std::mem::replace(&mut seen[index], true) {
+                return Err(format!("duplicate grid point ({}, {})", p.x, p.y));
+            }
+            values[index] = v;
+        }
+        let (first, last) = (Vec2::new(xs[0], ys[0]), Vec2::new(xs[xs.len() - 1], ys[ys.len() - 1]));
+        let spacing = (last - first) / Vec2::new(xs.len().max(2) as f32 - 1.0, ys.len().max(2) as f32 - 1.0);
+        for (name, coords, step) in [("x", &xs, spacing.x), ("y", &ys, spacing.y)] {
+            // allow for the rounding of coordinates written with a few decimals
+            if let Some(gap) = coords.windows(2).map(|pair| pair[1] - pair[0]).find(|gap| (gap - step).abs() > 1e-3 * step) {
+                return Err(format!("{name} coordinates are not evenly spaced: a gap of {gap} where {step} was expected"));
+            }
+        }
+        Self::new(xs.len(), ys.len(), first, spacing, values).ok_or_else(|| "grid needs at least 2 x 2 points".to_string())
+    }
+
+    /// Serialize as `FIELD_MAGIC`, little-endian `u32` cols and rows, `f32` origin and
+    /// spacing x/y, then the `f32` u/v pairs row by row.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = FIELD_MAGIC.to_vec();
+        bytes.extend_from_slice(&(self.cols as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.rows as u32).to_le_bytes());
+        let header = [self.origin, self.spacing];
+        for v in header.iter().chain(&self.values) {
+            bytes.extend_from_slice(&v.x.to_le_bytes());
+            bytes.extend_from_slice(&v.y.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Parse the format written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let body = bytes.strip_prefix(FIELD_MAGIC).ok_or("not a binary vector field")?;
+        let mut words = body.chunks_exact(4).map(|w| [w[0], w[1], w[2], w[3]]);
+        let mut next = || words.next().ok_or("truncated vector field");
+        let cols = u32::from_le_bytes(next()?) as usize;
+        let rows = u32::from_le_bytes(next()?) as usize;
+        let mut vec2 = || -> Result<Vec2, &str> { Ok(Vec2::new(f32::from_le_bytes(next()?), f32::from_le_bytes(next()?))) };
+        let origin = vec2()?;
+        let spacing = vec2()?;
+        let values = (0..cols.saturating_mul(rows).min(body.len() / 8)).map(|_| vec2()).collect::<Result<Vec<_>, _>>()?;
+        Self::new(cols, rows, origin, spacing, values).ok_or_else(|| format!("invalid {cols} x {rows} vector field"))
+    }
+
+    /// Parse either format, telling them apart by the binary magic.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.starts_with(FIELD_MAGIC) {
+            Self::from_bytes(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes).map_err(|err| format!("not a CSV vector field: {err}"))?;
+            Self::from_csv(text)
+        }
+    }
+}
+
+/// Imported vector field driving particles: they are steered towards the local
+/// field velocity instead of being pushed by it, so they trace its streamlines.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    pub field: VectorField,
+    /// Multiplier from field units to pixels per unit of simulation time.
+    pub speed: f32,
+    /// How fast particles adopt the field velocity, per unit of simulation time.
+    pub coupling: f32,
+}
+
+impl FlowField {
+    /// Acceleration of a particle at `pos` moving with `vel`.
+    pub fn acceleration(&self, pos: Vec2, vel: Vec2) -> Vec2 {
+        (self.field.sample(pos) * self.speed - vel) * self.coupling
+    }
+}
+
+impl Force for FlowField {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        self.acceleration(particle.position, particle.velocity) * particle.mass
+    }
+}
+
+/// Vector field file to load at startup, see `FlowField`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlowFieldConfig {
+    /// CSV `x,y,u,v` grid or binary `RWVF` file.
+    pub path: String,
+    /// Multiplier from field units to pixels per unit of simulation time.
+    pub speed: f32,
+    /// How fast particles adopt the field velocity, per unit of simulation time.
+    pub coupling: f32,
+    /// Stretch the grid over the arena; when false, field coordinates are pixels.
+    pub fit: bool,
+}
+
+impl Default for FlowFieldConfig {
+    fn default() -> Self {
+        Self { path: String::new(), speed: 1.0, coupling: 0.1, fit: true }
+    }
+}
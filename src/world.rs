@@ -1,150 +1,2171 @@
-use glam::Vec2;
+use crate::aggregation::Aggregate;
+use crate::arena::FrameArena;
+use crate::colormap::{self, ColorOverLifetime, Theme};
+use crate::constraint::{Pin, Spring, SpringLink, FABRIC_DAMPING, FABRIC_STIFFNESS};
+use crate::decal::DecalLayer;
+use crate::distribution::VectorDistribution;
+use crate::emitter::{Emitter, RandomEmitter, MIN_MASS};
+use crate::flow::{FlowStats, Sink, Source};
+use crate::fluid::FluidGrid;
+use crate::group::{GroupFilter, ParticleGroup, NO_GROUP};
+use crate::integrator::IntegratorKind;
+use crate::life::LifeLayer;
+use crate::impacts::{ImpactMap, GLOW_ENERGY, IMPACT_SPEED};
+use crate::memory::vec_bytes;
+use crate::footprint::{Footprint, FootprintCache};
+use crate::force::{Force, ForceSources, GlobalForces, ParticleView};
+use crate::noise;
+use crate::obstacle::{Gate, Portal, Shape};
+use crate::rules::{DeathCause, EventListener, ParticleEvent, SpawnRule, MAX_EVENTS, MAX_LIFECYCLE_EVENTS};
+use crate::sdf::{SdfGrid, SDF_CELL};
+use crate::spatial::{SpatialGrid, GRID_CELL};
+use crate::swarm::Swarm;
+use crate::vector_field::FlowField;
+use crate::wetness::Wetness;
+use glam::{Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub struct ParticleSystem {
     width: usize,
     height: usize,
     position: Vec<Vec2>,
+    /// Position before the last step, for render interpolation.
+    previous_position: Vec<Vec2>,
     velocity: Vec<Vec2>,
     forces: Vec<Vec2>,
+    external_forces: Vec<Vec2>,
     mass: Vec<f32>,
+    radius: Vec<f32>,
     lifetime: Vec<f32>,
+    decay: Vec<f32>,
+    /// Multiplier of the global (or group) drag, see `set_drag`.
+    drag: Vec<f32>,
+    fade: Vec<f32>,
+    emitter: Vec<EmitterId>,
+    /// Index in `groups` of each particle's group, `NO_GROUP` for none.
+    group: Vec<u8>,
+    /// Base RGBA color, white unless the emitter sets one.
+    color: Vec<[u8; 4]>,
+    /// Lifetime used up so far, for `age_fraction`.
+    age: Vec<f32>,
+    id: Vec<u64>,
+    next_id: u64,
+    emitter_stats: Vec<EmitterStats>,
     pub count: usize,
     capacity: usize,
-    radius: i16,
+    /// Fraction of the way from `previous_position` to `position` that is drawn.
+    interpolation: f32,
     pub simulation: SimParams,
-    pub attractor: Option<Attractor>,
+    /// Initial velocities of `spawn_random`.
+    pub spawn_velocity: VectorDistribution,
+    /// Fixed attractors of the scene.
+    pub attractors: ForceSources<Attractor>,
+    /// Interactive attractor following the cursor (or the dead-reckoned sensor).
+    pub pointer_attractor: Option<Attractor>,
+    /// Regions pushing particles away, e.g. from UI elements.
+    pub repellers: ForceSources<Repeller>,
+    /// Swirls around fixed centers.
+    pub vortices: ForceSources<Vortex>,
+    /// Inverse-square point masses, for orbits.
+    pub gravity_wells: ForceSources<GravityWell>,
+    /// Goal-seeking path followers, see `Swarm`.
+    swarms: Vec<Swarm>,
+    /// User forces, see `add_force`.
+    custom_forces: Vec<Box<dyn Force>>,
+    /// User callbacks for `events`, see `add_event_listener`.
+    event_listeners: Vec<EventListener>,
+    /// Temporary attractors, one per finger touching the screen.
+    pub touch_attractors: Vec<Attractor>,
+    /// Static shapes particles bounce off, see `add_obstacle`.
+    pub obstacles: Vec<Shape>,
+    /// Freehand walls rasterized into a distance field, or a sampled SDF, see `set_collision_sdf`.
+    pub wall_sdf: SdfGrid,
+    /// Hits and expiries of the last `update`, see `events`.
+    events: Vec<ParticleEvent>,
+    /// Length of `lifecycle` when each of `events` was recorded, to pass both to the
+    /// listeners in recording order.
+    event_marks: Vec<usize>,
+    /// Spawns and deaths of the last `update` and since, see `lifecycle_events`; capped
+    /// far above `events` at `MAX_LIFECYCLE_EVENTS`, so counters kept from them stay exact.
+    lifecycle: Vec<ParticleEvent>,
+    /// Lifecycle events already passed to the listeners, dropped by the next `update`.
+    dispatched: usize,
+    /// Record `lifecycle`, see `record_lifecycle`.
+    record_lifecycle: bool,
+    /// Secondary particles spawned in reaction to `events`.
+    pub spawn_rules: Vec<SpawnRule>,
+    /// Imported vector field steering particles, see `FlowField`.
+    pub flow_field: Option<FlowField>,
+    /// Coarse fluid the particles stir up and are carried by, see `FluidGrid`.
+    pub fluid: Option<FluidGrid>,
+    pub portals: Vec<Portal>,
+    pub gates: Vec<Gate>,
+    pub sinks: Vec<Sink>,
+    pub sources: Vec<Source>,
+    /// Named particle subsets with their own gravity, drag, restitution and color. Particles
+    /// join the group of their emitter when spawned; call `regroup` after changing which
+    /// emitters a group lists.
+    pub groups: Vec<ParticleGroup>,
+    sink_stats: Vec<FlowStats>,
+    source_stats: Vec<FlowStats>,
+    /// Absorbed particles not yet re-emitted by matching sources.
+    absorbed: usize,
+    /// Springs between particle pairs, see `add_spring`.
+    springs: Vec<Spring>,
+    /// Spring ends of all particles this step, grouped by particle.
+    spring_links: Vec<SpringLink>,
+    /// Start and length of each particle's run in `spring_links`.
+    spring_ranges: Vec<(u32, u32)>,
+    /// Particles held in place, see `pin`.
+    pins: Vec<Pin>,
+    impacts: ImpactMap,
+    /// Fading marks strong impacts leave on the background; `None` leaves none.
+    pub decals: Option<DecalLayer>,
+    /// Water rain collects into along the bottom edge; `None` lets it bounce off.
+    pub wetness: Option<Wetness>,
+    /// Cluster particles freeze onto when they touch it; `None` leaves them moving.
+    pub aggregate: Option<Aggregate>,
+    /// Cellular automaton emitting and pulling in particles, see `LifeLayer`.
+    pub life: Option<LifeLayer>,
+    emitters: Vec<Box<dyn Emitter>>,
+    emitters_paused: bool,
+    /// Particles bucketed by position, rebuilt every step for collisions and neighbor queries.
+    grid: SpatialGrid,
+    /// Temporaries of the current step, reset at its start.
+    scratch: FrameArena<u32>,
+    /// Simulation time since creation, drives time-varying forces.
+    time: f32,
+    seed: u64,
+    rng: StdRng,
 }
 
+/// Dynamic particle state of a `ParticleSystem` at one instant, see `snapshot` and `restore`.
+#[derive(Clone)]
+pub struct Snapshot {
+    count: usize,
+    position: Vec<Vec2>,
+    velocity: Vec<Vec2>,
+    mass: Vec<f32>,
+    radius: Vec<f32>,
+    lifetime: Vec<f32>,
+    decay: Vec<f32>,
+    drag: Vec<f32>,
+    fade: Vec<f32>,
+    emitter: Vec<EmitterId>,
+    group: Vec<u8>,
+    color: Vec<[u8; 4]>,
+    age: Vec<f32>,
+    id: Vec<u64>,
+    next_id: u64,
+    time: f32,
+    rng: StdRng,
+}
+
+impl Snapshot {
+    /// Heap bytes of a snapshot of `count` particles.
+    pub fn bytes_for(count: usize) -> usize {
+        count
+            * (2 * std::mem::size_of::<Vec2>()
+                + 7 * std::mem::size_of::<f32>()
+                + std::mem::size_of::<EmitterId>()
+                + std::mem::size_of::<u8>()
+                + std::mem::size_of::<[u8; 4]>()
+                + std::mem::size_of::<u64>())
+    }
+    pub fn memory_bytes(&self) -> usize {
+        Self::bytes_for(self.position.capacity())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SimParams {
     pub gravity: Vec2,
-    pub wind: Vec2,              // constant wind acceleration
+    pub wind: Wind,              // wind force, optionally gusting
     pub acceleration: Vec2,      // from acceleration sensor
     pub global_drag: Vec2,        // simple velocity damping
     pub restitution: f32,        // wall collision bounce factor
+    pub wall_friction: f32,      // tangential velocity lost per wall impact
+    pub surface_friction: f32,   // Coulomb coefficient: tangential speed lost per unit of normal speed change on contact
+    #[serde(deserialize_with = "crate::config::positive")]
+    pub density: f32,            // mass per unit area, sets particle radius; must be positive
+    pub walls: WallOverrides,    // per-edge bounce, wrap, kill or stick
     pub dt: f32,
+    pub fade_duration: f32,      // time for expired particles to shrink out
+    pub turbulence: Option<Turbulence>, // evolving noise force
+    pub curl_noise: Option<CurlNoise>, // divergence-free flow blended into velocities
+    pub collisions: Option<Collisions>, // particle-particle collisions
+    pub integrator: IntegratorKind, // how positions and velocities advance
+    #[serde(deserialize_with = "substeps")]
+    pub substeps: u32,           // integration steps per update, each advancing dt / substeps; at most MAX_SUBSTEPS
+}
+
+impl SimParams {
+    /// Radius of a disc of `mass` at the configured `density`.
+    pub fn radius_for_mass(&self, mass: f32) -> f32 {
+        (mass / (self.density * std::f32::consts::PI)).sqrt()
+    }
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, 0.5),
+            global_drag: Vec2::new(0.01, 0.01),
+            wind: Wind::default(),
+            acceleration: Vec2::new(0.0, 0.0),
+            restitution: 0.9,
+            wall_friction: 0.0,
+            surface_friction: 0.0,
+            // a unit mass particle has radius 4
+            density: 1.0 / (std::f32::consts::PI * 16.0),
+            walls: WallOverrides::default(),
+            dt: 1.0,
+            fade_duration: 20.0,
+            turbulence: None,
+            curl_noise: None,
+            collisions: None,
+            integrator: IntegratorKind::default(),
+            substeps: 1,
+        }
+    }
+}
+
+/// Particles bouncing off each other instead of passing through.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Collisions {
+    /// Fraction of the approach speed kept after two particles collide.
+    pub restitution: f32,
+}
+
+impl Default for Collisions {
+    fn default() -> Self {
+        Self { restitution: 0.5 }
+    }
+}
+
+/// Wavy motion from a smooth noise field sampled at each particle.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Turbulence {
+    /// Spatial frequency: noise features are about `1 / scale` pixels wide.
+    pub scale: f32,
+    /// Peak acceleration.
+    pub strength: f32,
+    /// How fast the field evolves per unit of simulation time.
+    pub time_speed: f32,
+}
+
+impl Default for Turbulence {
+    fn default() -> Self {
+        Self { scale: 0.01, strength: 0.5, time_speed: 0.01 }
+    }
+}
+
+impl Turbulence {
+    /// Acceleration at `pos` at simulation time `time`.
+    pub fn sample(&self, pos: Vec2, time: f32) -> Vec2 {
+        let p = pos * self.scale;
+        let t = time * self.time_speed;
+        // two decorrelated channels of the same field
+        let x = noise::perlin3(p.x, p.y, t);
+        let y = noise::perlin3(p.x + 31.7, p.y - 47.3, t + 13.1);
+        Vec2::new(x, y) * self.strength
+    }
+}
+
+impl Force for Turbulence {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        self.sample(particle.position, particle.time) * particle.mass
+    }
+}
+
+/// Swirling, divergence-free flow from the curl of a noise field that carries particles
+/// along directly rather than pushing them, for smoke and ink looks.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurlNoise {
+    /// Spatial frequency: eddies are about `1 / scale` pixels wide.
+    pub scale: f32,
+    /// Typical flow speed in pixels per unit of simulation time.
+    pub speed: f32,
+    /// How fast the field evolves per unit of simulation time.
+    pub time_speed: f32,
+    /// Fraction of a particle's velocity replaced by the flow per unit of simulation time:
+    /// `0` leaves the dynamics alone, `1` moves particles with the flow plus the forces of a
+    /// single step.
+    pub weight: f32,
+}
+
+impl Default for CurlNoise {
+    fn default() -> Self {
+        Self { scale: 0.005, speed: 1.0, time_speed: 0.005, weight: 0.5 }
+    }
+}
+
+impl CurlNoise {
+    /// Flow velocity at `pos` at simulation time `time`.
+    pub fn velocity(&self, pos: Vec2, time: f32) -> Vec2 {
+        const EPS: f32 = 0.01;
+        let p = pos * self.scale;
+        let t = time * self.time_speed;
+        let potential = |x: f32, y: f32| noise::perlin3(x, y, t);
+        // rotated gradient of the potential, which has no divergence
+        let dx = (potential(p.x + EPS, p.y) - potential(p.x - EPS, p.y)) / (2.0 * EPS);
+        let dy = (potential(p.x, p.y + EPS) - potential(p.x, p.y - EPS)) / (2.0 * EPS);
+        Vec2::new(dy, -dx) * self.speed
+    }
+
+    /// Blend `vel` of a particle at `pos` towards the flow over a step of `dt`.
+    pub fn advect(&self, pos: Vec2, vel: Vec2, time: f32, dt: f32) -> Vec2 {
+        let blend = 1.0 - (1.0 - self.weight.clamp(0.0, 1.0)).powf(dt);
+        vel.lerp(self.velocity(pos, time), blend)
+    }
+}
+
+/// Wind force, `base` plus gusts along its direction that swell and ease over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "WindRepr")]
+pub struct Wind {
+    pub base: Vec2,
+    /// Peak gust force added to `base`; gusts blow along `base`, or towards +x without it.
+    pub gust: f32,
+    /// Gusts per unit of simulation time.
+    pub frequency: f32,
+    pub shape: GustShape,
+}
+
+/// How gust strength varies over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GustShape {
+    /// Regular swell and lull, from `-gust` to `gust`.
+    #[default]
+    Sine,
+    /// Irregular gusts from smooth noise.
+    Noise,
+}
+
+impl Wind {
+    /// Constant wind without gusts.
+    pub fn steady(base: Vec2) -> Self {
+        Self { base, ..Self::default() }
+    }
+
+    /// Wind force at simulation time `time`.
+    pub fn at(&self, time: f32) -> Vec2 {
+        if self.gust == 0.0 {
+            return self.base;
+        }
+        let phase = time * self.frequency;
+        let strength = match self.shape {
+            GustShape::Sine => (phase * std::f32::consts::TAU).sin(),
+            GustShape::Noise => noise::perlin3(phase, 7.3, -2.9),
+        };
+        self.base + self.base.try_normalize().unwrap_or(Vec2::X) * strength * self.gust
+    }
+}
+
+/// Accepts a bare vector, as wind was configured before it had gusts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WindRepr {
+    Steady(Vec2),
+    Gusting(#[serde(with = "WindDef")] Wind),
+}
+
+#[derive(Deserialize)]
+#[serde(remote = "Wind")]
+struct WindDef {
+    #[serde(default)]
+    base: Vec2,
+    #[serde(default)]
+    gust: f32,
+    #[serde(default)]
+    frequency: f32,
+    #[serde(default)]
+    shape: GustShape,
+}
+
+impl From<WindRepr> for Wind {
+    fn from(repr: WindRepr) -> Self {
+        match repr {
+            WindRepr::Steady(base) => Wind::steady(base),
+            WindRepr::Gusting(wind) => wind,
+        }
+    }
+}
+
+/// What happens to particles reaching an edge of the arena.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "EdgeRepr")]
+pub enum BoundaryBehavior {
+    /// Reflect with this restitution: `1.0` is perfectly elastic, `0.0` fully absorbing.
+    Bounce(f32),
+    /// Leave through this edge and come back in through the opposite one.
+    Wrap,
+    /// Remove the particle as soon as it touches the edge.
+    Kill,
+    /// Stop the particle where it touched the edge.
+    Stick,
+    /// Push particles back with an acceleration growing smoothly from 0 at `margin` pixels
+    /// from the edge to `strength` at the edge, instead of flipping their velocity. Gentler
+    /// on springs and dense crowds; particles that still reach the edge stop there.
+    Soft { margin: f32, strength: f32 },
+}
+
+/// Accepts a bare restitution, as walls were configured before they had behaviors.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EdgeRepr {
+    Restitution(f32),
+    Behavior(#[serde(with = "BoundaryBehaviorDef")] BoundaryBehavior),
+}
+
+#[derive(Deserialize)]
+#[serde(remote = "BoundaryBehavior")]
+enum BoundaryBehaviorDef {
+    Bounce(f32),
+    Wrap,
+    Kill,
+    Stick,
+    Soft { margin: f32, strength: f32 },
+}
+
+impl From<EdgeRepr> for BoundaryBehavior {
+    fn from(repr: EdgeRepr) -> Self {
+        match repr {
+            EdgeRepr::Restitution(restitution) => BoundaryBehavior::Bounce(restitution),
+            EdgeRepr::Behavior(behavior) => behavior,
+        }
+    }
+}
+
+/// Effect of an edge on a particle, along the axis perpendicular to it.
+enum EdgeOutcome {
+    Inside,
+    /// Bounced off with this normal speed.
+    Bounced(f32),
+    /// Moved by this offset to the opposite edge.
+    Wrapped(f32),
+    Killed,
+    /// Stuck after hitting with this normal speed.
+    Stuck(f32),
+}
+
+impl BoundaryBehavior {
+    /// Apply this behavior of the low (`high == false`) or high edge of an axis of length
+    /// `extent` to a particle of `radius` at `p` moving at `v` along that axis.
+    fn apply(self, p: &mut f32, v: &mut f32, radius: f32, extent: f32, high: bool) -> EdgeOutcome {
+        // normal speed towards the edge
+        let speed = if high { *v } else { -*v };
+        match self {
+            BoundaryBehavior::Bounce(restitution) => {
+                *p = if high { extent - radius } else { radius };
+                *v = if high { -v.abs() } else { v.abs() } * restitution;
+                EdgeOutcome::Bounced(speed)
+            }
+            // wrap once the center crosses, so particles slide out and in smoothly
+            BoundaryBehavior::Wrap if high && *p >= extent => {
+                *p -= extent;
+                EdgeOutcome::Wrapped(-extent)
+            }
+            BoundaryBehavior::Wrap if !high && *p < 0.0 => {
+                *p += extent;
+                EdgeOutcome::Wrapped(extent)
+            }
+            BoundaryBehavior::Wrap => EdgeOutcome::Inside,
+            BoundaryBehavior::Kill => EdgeOutcome::Killed,
+            BoundaryBehavior::Stick => {
+                *p = if high { extent - radius } else { radius };
+                EdgeOutcome::Stuck(speed)
+            }
+            BoundaryBehavior::Soft { .. } => {
+                *p = if high { extent - radius } else { radius };
+                *v = if high { v.min(0.0) } else { v.max(0.0) };
+                EdgeOutcome::Bounced(speed)
+            }
+        }
+    }
+
+    /// Acceleration away from the edge for a particle whose surface is `gap` pixels from it.
+    fn push(self, gap: f32) -> f32 {
+        match self {
+            BoundaryBehavior::Soft { margin, strength } if gap < margin => {
+                let depth = 1.0 - gap.max(0.0) / margin;
+                strength * depth * depth
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Per-edge behavior overriding a bounce with `SimParams::restitution` where set.
+/// A bare number is read as `Bounce` with that restitution.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WallOverrides {
+    pub left: Option<BoundaryBehavior>,
+    pub right: Option<BoundaryBehavior>,
+    pub top: Option<BoundaryBehavior>,
+    pub bottom: Option<BoundaryBehavior>,
+}
+
+/// Most integration steps a single update runs, however many `SimParams::substeps` asks for.
+pub const MAX_SUBSTEPS: u32 = 64;
+
+/// Deserialize `SimParams::substeps`, clamped to `1..=MAX_SUBSTEPS`.
+fn substeps<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    Ok(u32::deserialize(deserializer)?.clamp(1, MAX_SUBSTEPS))
+}
+
+/// Lifetime of particles that never expire.
+pub const INFINITE_LIFETIME: f32 = f32::INFINITY;
+
+/// Identifies the emitter that spawned a particle. Plain `spawn` calls use `DEFAULT_EMITTER`.
+pub type EmitterId = u16;
+pub const DEFAULT_EMITTER: EmitterId = 0;
+const WHITE: [u8; 4] = [0xFF; 4];
+
+/// Per-emitter population statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmitterStats {
+    /// Particles currently alive.
+    pub live: usize,
+    /// Total particles spawned.
+    pub spawned: u64,
+    /// Smoothed spawn throughput in particles per unit of simulation time.
+    pub spawn_rate: f32,
+    spawned_this_step: u32,
+}
+
+impl EmitterStats {
+    /// Fold the spawns since the last update into `spawn_rate`. Spawns of an update that
+    /// does not advance time are carried over to the next one.
+    fn end_update(&mut self, dt: f32) {
+        if dt > 0.0 {
+            let rate = self.spawned_this_step as f32 / dt;
+            self.spawn_rate = 0.9 * self.spawn_rate + 0.1 * rate;
+            self.spawned_this_step = 0;
+        }
+    }
 }
 
+/// Pulls particles towards `position`, hardest at the center and fading out linearly at `radius`.
+///
+/// Like `Repeller` and `Vortex` it is a `Force` whose `apply` gives the push of a whole
+/// update, which `ParticleSystem` spreads over the update's substeps.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attractor {
     pub position: Vec2,
+    /// Velocity change per update of a unit mass right at the center.
+    pub strength: f32,
+    pub radius: f32,
+    /// Groups of particles that are pulled in; the others ignore it.
+    #[serde(default, skip_serializing_if = "GroupFilter::is_all")]
+    pub filter: GroupFilter,
+}
+
+/// Strength of the attractors that follow the user, e.g. under a finger.
+pub const INTERACTIVE_ATTRACTOR_STRENGTH: f32 = 6.0;
+/// Radius of the attractors that follow the user.
+pub const INTERACTIVE_ATTRACTOR_RADIUS: f32 = 50.0;
+
+impl Attractor {
+    pub fn new(position: Vec2, strength: f32, radius: f32) -> Self {
+        Self { position, strength, radius, filter: GroupFilter::All }
+    }
+
+    /// Attractor following the user at `position`, with the interactive strength and radius.
+    pub fn interactive(position: Vec2) -> Self {
+        Self::new(position, INTERACTIVE_ATTRACTOR_STRENGTH, INTERACTIVE_ATTRACTOR_RADIUS)
+    }
+
+    /// Pull per update on a unit mass at `pos`, pointing towards the center.
+    fn pull(&self, pos: Vec2) -> Vec2 {
+        let towards = self.position - pos;
+        let distance = towards.length();
+        if distance >= self.radius || distance == 0.0 {
+            return Vec2::ZERO;
+        }
+        let falloff = 1.0 - distance / self.radius;
+        towards / distance * falloff * self.strength
+    }
+}
+
+impl Force for Attractor {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        if !self.filter.applies(particle.group) {
+            return Vec2::ZERO;
+        }
+        self.pull(particle.position)
+    }
+}
+
+/// Pushes particles away from `position`, e.g. to keep them out of UI areas.
+///
+/// The push falls off quadratically towards `radius`, so particles are eased out
+/// at the edge and pushed hardest near the center.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Repeller {
+    pub position: Vec2,
+    /// Velocity change per update of a unit mass next to the center.
+    pub strength: f32,
+    pub radius: f32,
+    /// Groups of particles kept out of the area; the others may enter it.
+    #[serde(default, skip_serializing_if = "GroupFilter::is_all")]
+    pub filter: GroupFilter,
+}
+
+impl Repeller {
+    pub fn new(position: Vec2, strength: f32, radius: f32) -> Self {
+        Self { position, strength, radius, filter: GroupFilter::All }
+    }
+
+    /// Push per update on a unit mass at `pos`, pointing away from the center.
+    fn push(&self, pos: Vec2) -> Vec2 {
+        let away = pos - self.position;
+        let distance = away.length();
+        if distance >= self.radius || distance == 0.0 {
+            return Vec2::ZERO;
+        }
+        let falloff = 1.0 - distance / self.radius;
+        away / distance * falloff * falloff * self.strength
+    }
+}
+
+impl Force for Repeller {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        if !self.filter.applies(particle.group) {
+            return Vec2::ZERO;
+        }
+        self.push(particle.position)
+    }
+}
+
+/// Swirls particles around `position` with a tangential push that falls off
+/// linearly towards `radius`. Positive strength turns clockwise on screen.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Vortex {
+    pub position: Vec2,
+    /// Tangential velocity change per update of a unit mass next to the center.
     pub strength: f32,
-    pub radius: u8,
+    pub radius: f32,
+    /// Groups of particles that are swirled; the others are not turned by it.
+    #[serde(default, skip_serializing_if = "GroupFilter::is_all")]
+    pub filter: GroupFilter,
+}
+
+impl Vortex {
+    pub fn new(position: Vec2, strength: f32, radius: f32) -> Self {
+        Self { position, strength, radius, filter: GroupFilter::All }
+    }
+
+    /// Tangential push per update on a unit mass at `pos`.
+    fn swirl(&self, pos: Vec2) -> Vec2 {
+        let offset = pos - self.position;
+        let distance = offset.length();
+        if distance >= self.radius || distance == 0.0 {
+            return Vec2::ZERO;
+        }
+        let falloff = 1.0 - distance / self.radius;
+        (offset / distance).perp() * falloff * self.strength
+    }
+}
+
+impl Force for Vortex {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        if !self.filter.applies(particle.group) {
+            return Vec2::ZERO;
+        }
+        self.swirl(particle.position)
+    }
+}
+
+/// Point mass pulling particles with Newtonian 1/r² gravity, for orbits. Unlike
+/// `Attractor` it reaches across the whole arena and is integrated with the other
+/// forces, so the chosen integrator keeps orbits stable.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GravityWell {
+    pub position: Vec2,
+    /// Gravitational parameter G·M: the acceleration at a distance of 1 pixel.
+    pub mass: f32,
+    /// Distance below which the pull stops growing, so close passes do not explode.
+    pub softening: f32,
+    /// Groups of particles that orbit the well; the others feel no gravity from it.
+    #[serde(default, skip_serializing_if = "GroupFilter::is_all")]
+    pub filter: GroupFilter,
+}
+
+impl GravityWell {
+    pub fn new(position: Vec2, mass: f32, softening: f32) -> Self {
+        Self { position, mass, softening, filter: GroupFilter::All }
+    }
+
+    /// Acceleration of a particle at `pos`.
+    pub fn acceleration(&self, pos: Vec2) -> Vec2 {
+        let to_well = self.position - pos;
+        let r2 = to_well.length_squared() + self.softening * self.softening;
+        if r2 == 0.0 {
+            return Vec2::ZERO;
+        }
+        to_well * (self.mass / (r2 * r2.sqrt()))
+    }
+
+    /// Speed of a circular orbit at `radius` pixels, ignoring softening.
+    pub fn orbital_speed(&self, radius: f32) -> f32 {
+        (self.mass / radius).sqrt()
+    }
+}
+
+impl Force for GravityWell {
+    fn apply(&self, _index: usize, particle: &ParticleView) -> Vec2 {
+        if !self.filter.applies(particle.group) {
+            return Vec2::ZERO;
+        }
+        self.acceleration(particle.position) * particle.mass
+    }
 }
 
 impl ParticleSystem {
     /// Create a new `World` instance that can draw a moving box.
     pub fn new(max_particles: usize, width: usize, height: usize) -> Self {
+        let seed = rand::random();
         Self {
             width,
             height,
             position: vec![Vec2::new(0.0, 0.0); max_particles],
+            previous_position: vec![Vec2::ZERO; max_particles],
             velocity: vec![Vec2::new(0.0, 0.0); max_particles],
             forces: vec![Vec2::new(0.0, 0.0); max_particles],
+            external_forces: vec![Vec2::ZERO; max_particles],
             mass: vec![1.0; max_particles],
+            radius: vec![0.0; max_particles],
             lifetime: vec![1.0; max_particles],
+            decay: vec![1.0; max_particles],
+            drag: vec![1.0; max_particles],
+            fade: vec![1.0; max_particles],
+            emitter: vec![DEFAULT_EMITTER; max_particles],
+            group: vec![NO_GROUP; max_particles],
+            color: vec![WHITE; max_particles],
+            age: vec![0.0; max_particles],
+            id: vec![0; max_particles],
+            next_id: 0,
+            emitter_stats: Vec::new(),
             count: 0,
             capacity: max_particles,
-            radius: 4,
-            simulation: SimParams {
-                gravity: Vec2::new(0.0, 0.5),
-                global_drag: Vec2::new(0.01, 0.01),
-                wind: Vec2::new(0.0, 0.0),
-                acceleration: Vec2::new(0.0, 0.0),
-                restitution: 0.9,
-                dt: 1.0,
+            interpolation: 1.0,
+            simulation: SimParams::default(),
+            spawn_velocity: VectorDistribution::default(),
+            attractors: ForceSources::default(),
+            pointer_attractor: None,
+            repellers: ForceSources::default(),
+            vortices: ForceSources::default(),
+            gravity_wells: ForceSources::default(),
+            swarms: Vec::new(),
+            custom_forces: Vec::new(),
+            event_listeners: Vec::new(),
+            touch_attractors: Vec::new(),
+            obstacles: Vec::new(),
+            wall_sdf: SdfGrid::new(width, height, SDF_CELL),
+            events: Vec::new(),
+            event_marks: Vec::new(),
+            lifecycle: Vec::new(),
+            dispatched: 0,
+            record_lifecycle: false,
+            spawn_rules: Vec::new(),
+            flow_field: None,
+            fluid: None,
+            portals: Vec::new(),
+            gates: Vec::new(),
+            sinks: Vec::new(),
+            sources: Vec::new(),
+            groups: Vec::new(),
+            sink_stats: Vec::new(),
+            source_stats: Vec::new(),
+            absorbed: 0,
+            springs: Vec::new(),
+            spring_links: Vec::new(),
+            spring_ranges: Vec::new(),
+            pins: Vec::new(),
+            impacts: ImpactMap::new(width, height),
+            decals: None,
+            wetness: None,
+            aggregate: None,
+            life: None,
+            emitters: Vec::new(),
+            scratch: FrameArena::new(),
+            emitters_paused: false,
+            grid: SpatialGrid::new(),
+            time: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    /// Simulation time advanced so far.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    /// Seed used for the random spawn functions.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Restart the random sequence from `seed`, making subsequent random spawns reproducible.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+    /// Arena width and height in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    /// Random generator behind the random spawns, for emitters to stay reproducible.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+    /// Add an emitter run at the start of every `update` and return its index.
+    pub fn add_emitter(&mut self, emitter: impl Emitter + 'static) -> usize {
+        self.emitters.push(Box::new(emitter));
+        self.emitters.len() - 1
+    }
+    /// Spawn `n` particles right away, placed and drawn like those of `params`.
+    pub fn emit_burst(&mut self, n: usize, params: &RandomEmitter) {
+        params.emit_burst(n, self);
+    }
+    /// Remove the emitter at `index`; later emitters move down by one.
+    pub fn remove_emitter(&mut self, index: usize) -> bool {
+        let exists = index < self.emitters.len();
+        if exists {
+            self.emitters.remove(index);
+        }
+        exists
+    }
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+    /// Stop (or resume) running the emitters, e.g. while the population is set directly.
+    pub fn pause_emitters(&mut self, paused: bool) {
+        self.emitters_paused = paused;
+    }
+    /// Remove all particles.
+    pub fn clear(&mut self) {
+        for i in 0..self.count {
+            self.record_death(i, DeathCause::Removed);
+        }
+        self.count = 0;
+        self.springs.clear();
+        self.pins.clear();
+        self.emitter_stats.clear();
+        self.absorbed = 0;
+        self.rebuild_grid();
+    }
+    pub fn spawn(&mut self, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32) {
+        self.spawn_owned(DEFAULT_EMITTER, pos, vel, mass, lifetime);
+    }
+    /// Spawn a particle owned by `emitter`, counted in its `EmitterStats`.
+    /// Returns its index, or `None` when the system is full, see `free_slots`.
+    pub fn spawn_owned(&mut self, emitter: EmitterId, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32) -> Option<usize> {
+        if self.count == self.capacity {
+            return None;
+        }
+        let index = self.count;
+        self.count += 1;
+        self.id[index] = self.next_id;
+        self.next_id += 1;
+        self.position[index] = Vec2::new(pos[0], pos[1]);
+        self.previous_position[index] = self.position[index];
+        self.velocity[index] = Vec2::new(vel[0], vel[1]);
+        self.mass[index] = mass;
+        self.radius[index] = self.simulation.radius_for_mass(mass);
+        self.lifetime[index] = lifetime;
+        self.decay[index] = 1.0;
+        self.drag[index] = 1.0;
+        self.fade[index] = 1.0;
+        self.emitter[index] = emitter;
+        self.group[index] = self.group_of_emitter(emitter);
+        self.color[index] = WHITE;
+        self.age[index] = 0.0;
+        self.external_forces[index] = Vec2::ZERO;
+        self.record_spawn(index);
+
+        let stats = self.stats_mut(emitter);
+        stats.live += 1;
+        stats.spawned += 1;
+        stats.spawned_this_step += 1;
+        Some(index)
+    }
+    /// Spawn a particle anywhere in the arena with a velocity from `spawn_velocity`.
+    pub fn spawn_random(&mut self, mass: f32, lifetime: f32) {
+        if self.count < self.capacity {
+            let position = [
+                self.rng.random::<f32>() * self.width as f32,
+                self.rng.random::<f32>() * self.height as f32,
+            ];
+            // the default spread keeps its original sampling, so existing seeds, share links
+            // and digests reproduce
+            let velocity = if self.spawn_velocity == VectorDistribution::default() {
+                Vec2::new((self.rng.random::<f32>() - 0.5) * 4.0, (self.rng.random::<f32>() - 0.5) * 4.0)
+            } else {
+                self.spawn_velocity.sample(&mut self.rng)
+            };
+            self.spawn(position, velocity.into(), mass, lifetime);
+        }
+    }
+
+    /// Heap bytes per unit of capacity: the particle columns plus its neighbor grid entry.
+    pub const BYTES_PER_PARTICLE: usize = 5 * std::mem::size_of::<Vec2>()
+        + 7 * std::mem::size_of::<f32>()
+        + std::mem::size_of::<EmitterId>()
+        + std::mem::size_of::<u8>()
+        + std::mem::size_of::<[u8; 4]>()
+        + std::mem::size_of::<u64>()
+        + std::mem::size_of::<u32>();
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Draw particles `alpha` of the way from their position before the last step to
+    /// their current one, to smooth out a fixed timestep. 1 draws the current positions.
+    pub fn set_interpolation(&mut self, alpha: f32) {
+        self.interpolation = alpha.clamp(0.0, 1.0);
+    }
+    /// Where particle `index` is drawn, see `set_interpolation`.
+    pub fn render_position(&self, index: usize) -> Vec2 {
+        if self.interpolation >= 1.0 {
+            return self.position[index];
+        }
+        self.previous_position[index].lerp(self.position[index], self.interpolation)
+    }
+    /// Heap bytes of the particle columns.
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.position)
+            + vec_bytes(&self.previous_position)
+            + vec_bytes(&self.velocity)
+            + vec_bytes(&self.forces)
+            + vec_bytes(&self.external_forces)
+            + vec_bytes(&self.mass)
+            + vec_bytes(&self.radius)
+            + vec_bytes(&self.lifetime)
+            + vec_bytes(&self.decay)
+            + vec_bytes(&self.drag)
+            + vec_bytes(&self.fade)
+            + vec_bytes(&self.emitter)
+            + vec_bytes(&self.group)
+            + vec_bytes(&self.color)
+            + vec_bytes(&self.age)
+            + vec_bytes(&self.id)
+    }
+    /// Heap bytes of the neighbor grid, step scratch, wall distance field, impact map,
+    /// imported vector field, fluid grid, swarm paths, aggregate, life cells, decals and floor water.
+    pub fn spatial_memory_bytes(&self) -> usize {
+        self.grid.memory_bytes()
+            + self.scratch.memory_bytes()
+            + self.wall_sdf.memory_bytes()
+            + self.impacts.memory_bytes()
+            + self.flow_field.as_ref().map_or(0, |flow| flow.field.memory_bytes())
+            + self.fluid.as_ref().map_or(0, FluidGrid::memory_bytes)
+            + self.swarms.iter().map(Swarm::memory_bytes).sum::<usize>()
+            + self.decals.as_ref().map_or(0, DecalLayer::memory_bytes)
+            + self.wetness.as_ref().map_or(0, Wetness::memory_bytes)
+            + self.aggregate.as_ref().map_or(0, Aggregate::memory_bytes)
+            + self.life.as_ref().map_or(0, LifeLayer::memory_bytes)
+    }
+
+    /// Position of each live particle. Indices change as particles die, see `ids`.
+    pub fn positions(&self) -> &[Vec2] {
+        &self.position[..self.count]
+    }
+    /// Velocity of each live particle, indexed like `positions`.
+    pub fn velocities(&self) -> &[Vec2] {
+        &self.velocity[..self.count]
+    }
+    /// Mass of each live particle, indexed like `positions`.
+    pub fn masses(&self) -> &[f32] {
+        &self.mass[..self.count]
+    }
+    /// Indices of the particles within `radius` of `point`, across wrapped edges too, found
+    /// through a grid rebuilt at the end of every `update` and after removals such as
+    /// `kill_emitter` and `limit_live`. Particles spawned since are not included. A negative
+    /// or NaN `radius` is treated as 0.
+    pub fn neighbors_within(&self, point: Vec2, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let radius = radius.max(0.0);
+        self.grid
+            .near(point, radius)
+            .filter(move |&i| i < self.count && self.offset_between(point, self.position[i]).length_squared() <= radius * radius)
+    }
+    /// Whether the x and y axes wrap around, i.e. both of their edges are `BoundaryBehavior::Wrap`.
+    pub fn wrapped_axes(&self) -> [bool; 2] {
+        let walls = &self.simulation.walls;
+        let wraps = |a: Option<BoundaryBehavior>, b: Option<BoundaryBehavior>| {
+            a == Some(BoundaryBehavior::Wrap) && b == Some(BoundaryBehavior::Wrap)
+        };
+        [wraps(walls.left, walls.right), wraps(walls.top, walls.bottom)]
+    }
+    /// Shortest offset from `from` to `to`, crossing wrapped edges when that is closer.
+    pub fn offset_between(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let mut offset = to - from;
+        let extents = [self.width as f32, self.height as f32];
+        for (axis, wraps) in self.wrapped_axes().into_iter().enumerate() {
+            if wraps {
+                let extent = extents[axis];
+                offset[axis] -= extent * (offset[axis] / extent).round();
+            }
+        }
+        offset
+    }
+    /// Replace the walls with the surface of a signed distance function (negative inside,
+    /// in pixels), sampled on the wall grid. Particles inside are pushed out along its gradient.
+    /// An arena without area gets no walls.
+    pub fn set_collision_sdf(&mut self, sdf: impl Fn(Vec2) -> f32) {
+        self.wall_sdf = SdfGrid::from_fn(self.width, self.height, SDF_CELL, sdf)
+            .unwrap_or_else(|| SdfGrid::new(self.width, self.height, SDF_CELL));
+    }
+    /// Add a static collider and return its index.
+    pub fn add_obstacle(&mut self, shape: Shape) -> usize {
+        self.obstacles.push(shape);
+        self.obstacles.len() - 1
+    }
+    /// Remove the obstacle at `index`; later obstacles move down by one.
+    pub fn remove_obstacle(&mut self, index: usize) -> Option<Shape> {
+        (index < self.obstacles.len()).then(|| self.obstacles.remove(index))
+    }
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles.clear();
+    }
+    /// Add a swarm steering particles to its goal around obstacles and return its index.
+    pub fn add_swarm(&mut self, swarm: Swarm) -> usize {
+        self.swarms.push(swarm);
+        self.swarms.len() - 1
+    }
+    pub fn remove_swarm(&mut self, index: usize) -> Option<Swarm> {
+        (index < self.swarms.len()).then(|| self.swarms.remove(index))
+    }
+    pub fn clear_swarms(&mut self) {
+        self.swarms.clear();
+    }
+    pub fn swarms(&self) -> &[Swarm] {
+        &self.swarms
+    }
+    /// Mutable access to move a swarm's goal; its path follows on the next step.
+    pub fn swarm_mut(&mut self, index: usize) -> Option<&mut Swarm> {
+        self.swarms.get_mut(index)
+    }
+    /// Register a custom force acting on every particle and return its index.
+    pub fn add_force(&mut self, force: impl Force + 'static) -> usize {
+        self.custom_forces.push(Box::new(force));
+        self.custom_forces.len() - 1
+    }
+    /// Remove the custom force at `index`; later forces move down by one.
+    pub fn remove_force(&mut self, index: usize) -> Option<Box<dyn Force>> {
+        (index < self.custom_forces.len()).then(|| self.custom_forces.remove(index))
+    }
+    pub fn clear_forces(&mut self) {
+        self.custom_forces.clear();
+    }
+    /// Stable identifier of each particle, indexed like `positions`.
+    pub fn ids(&self) -> &[u64] {
+        &self.id[..self.count]
+    }
+    /// Current index of the particle with `id`, if it is alive.
+    /// `hint` is checked first, usually the index it had before.
+    pub fn index_of(&self, id: u64, hint: usize) -> Option<usize> {
+        if self.ids().get(hint) == Some(&id) {
+            return Some(hint);
+        }
+        self.ids().iter().position(|&other| other == id)
+    }
+    /// Radius of each particle, used for collisions and rendering.
+    /// Set from the mass at spawn time unless the emitter gives a radius range.
+    pub fn radii(&self) -> &[f32] {
+        &self.radius[..self.count]
+    }
+    /// Base RGBA color of each particle, tinted over its life by the renderer.
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.color[..self.count]
+    }
+    pub fn set_color(&mut self, index: usize, color: [u8; 4]) {
+        if index < self.count {
+            self.color[index] = color;
+        }
+    }
+    /// How far the particle at `index` is through its lifetime, from 0 at spawn to 1
+    /// once expired; always 0 for particles living forever.
+    pub fn age_fraction(&self, index: usize) -> f32 {
+        let remaining = self.lifetime[index].max(0.0);
+        let total = self.age[index] + remaining;
+        if total.is_finite() && total > 0.0 {
+            self.age[index] / total
+        } else {
+            0.0
+        }
+    }
+    pub fn set_radius(&mut self, index: usize, radius: f32) {
+        if index < self.count {
+            self.radius[index] = radius;
+        }
+    }
+    /// Total force applied to each particle in the last `update()`, indexed like the particles.
+    /// Attractor, repeller and vortex pushes are included as the force that spreads each
+    /// push over the update's substeps, i.e. the push divided by the update's `dt`.
+    pub fn forces(&self) -> &[Vec2] {
+        &self.forces[..self.count]
+    }
+    /// Add a force to the particle at `index` for the next `update()` only.
+    /// Forces accumulate until the step consumes them.
+    pub fn add_external_force(&mut self, index: usize, force: Vec2) {
+        if index < self.count {
+            self.external_forces[index] += force;
+        }
+    }
+    /// Connect the live particles at `i` and `j` with a damped spring, solved every step
+    /// until either dies. Returns false if either index is not alive or they are equal.
+    ///
+    /// Stiff springs need a small `dt` or `SimParams::substeps` to stay stable.
+    pub fn add_spring(&mut self, i: usize, j: usize, rest_length: f32, stiffness: f32, damping: f32) -> bool {
+        if i == j || !self.is_alive(i) || !self.is_alive(j) {
+            return false;
+        }
+        self.springs.push(Spring {
+            a: self.id[i],
+            b: self.id[j],
+            rest_length,
+            stiffness,
+            damping,
+            hints: (i, j),
+        });
+        true
+    }
+    pub fn springs(&self) -> &[Spring] {
+        &self.springs
+    }
+    pub fn clear_springs(&mut self) {
+        self.springs.clear();
+    }
+    /// Hold the live particle at `index` at its current position until it dies.
+    pub fn pin(&mut self, index: usize) -> bool {
+        if !self.is_alive(index) {
+            return false;
+        }
+        let particle = self.id[index];
+        self.pins.retain(|pin| pin.particle != particle);
+        self.pins.push(Pin { particle, anchor: self.position[index], hint: index });
+        true
+    }
+    /// Release the particle at `index` if it is pinned.
+    pub fn unpin(&mut self, index: usize) {
+        if let Some(&particle) = self.ids().get(index) {
+            self.pins.retain(|pin| pin.particle != particle);
+        }
+    }
+    pub fn pins(&self) -> &[Pin] {
+        &self.pins
+    }
+    /// Spawn a rope of `segments` springs from `start` (pinned) to `end`, each at rest at
+    /// its initial length. Returns the indices of its particles from `start` to `end`,
+    /// or `None` if there is no room for all of them.
+    pub fn spawn_rope(&mut self, start: Vec2, end: Vec2, segments: usize) -> Option<Vec<usize>> {
+        let segments = segments.max(1);
+        if self.free_slots() < segments + 1 {
+            return None;
+        }
+        let rest_length = start.distance(end) / segments as f32;
+        let indices: Vec<usize> = (0..=segments)
+            .filter_map(|k| self.spawn_at_rest(start.lerp(end, k as f32 / segments as f32)))
+            .collect();
+        for pair in indices.windows(2) {
+            self.add_spring(pair[0], pair[1], rest_length, FABRIC_STIFFNESS, FABRIC_DAMPING);
+        }
+        self.pin(indices[0]);
+        Some(indices)
+    }
+    /// Spawn a cloth of `nx` by `ny` particles spanning the bounding box of `rect`, hung
+    /// from its pinned top row. Neighbors are linked along the grid and its diagonals so
+    /// the cloth resists shearing. Returns the indices row by row from the top, or `None`
+    /// if there is no room for all of them.
+    pub fn spawn_cloth(&mut self, rect: Shape, nx: usize, ny: usize) -> Option<Vec<usize>> {
+        let (nx, ny) = (nx.max(2), ny.max(2));
+        if self.free_slots() < nx * ny {
+            return None;
+        }
+        let (min, max) = rect.bounds();
+        let spacing = (max - min) / Vec2::new((nx - 1) as f32, (ny - 1) as f32);
+        let indices: Vec<usize> = (0..ny)
+            .flat_map(|row| (0..nx).map(move |col| Vec2::new(col as f32, row as f32)))
+            .filter_map(|cell| self.spawn_at_rest(min + cell * spacing))
+            .collect();
+        let at = |col: usize, row: usize| indices[row * nx + col];
+        for row in 0..ny {
+            for col in 0..nx {
+                let mut links = Vec::with_capacity(4);
+                if col + 1 < nx {
+                    links.push(at(col + 1, row));
+                }
+                if row + 1 < ny {
+                    links.push(at(col, row + 1));
+                    if col + 1 < nx {
+                        links.push(at(col + 1, row + 1));
+                    }
+                    if col > 0 {
+                        links.push(at(col - 1, row + 1));
+                    }
+                }
+                for other in links {
+                    let rest_length = self.position[at(col, row)].distance(self.position[other]);
+                    self.add_spring(at(col, row), other, rest_length, FABRIC_STIFFNESS, FABRIC_DAMPING);
+                }
+            }
+        }
+        for col in 0..nx {
+            self.pin(at(col, 0));
+        }
+        Some(indices)
+    }
+    /// A unit mass particle at rest that never expires, for ropes and cloth.
+    fn spawn_at_rest(&mut self, position: Vec2) -> Option<usize> {
+        self.spawn_owned(DEFAULT_EMITTER, position.into(), [0.0, 0.0], 1.0, INFINITE_LIFETIME)
+    }
+    /// The group named `name`, e.g. to animate its overrides.
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ParticleGroup> {
+        self.groups.iter_mut().find(|group| group.name == name)
+    }
+    /// Enable or disable the overrides of the group named `name`; false if there is none.
+    pub fn toggle_group(&mut self, name: &str) -> bool {
+        let Some(group) = self.group_mut(name) else {
+            return false;
+        };
+        group.enabled = !group.enabled;
+        true
+    }
+    /// Group new particles of `emitter` join: the first listing it, among the first
+    /// `NO_GROUP` groups.
+    fn group_of_emitter(&self, emitter: EmitterId) -> u8 {
+        self.groups
+            .iter()
+            .take(NO_GROUP as usize)
+            .position(|group| group.emitters.contains(&emitter))
+            .map_or(NO_GROUP, |k| k as u8)
+    }
+    /// Index in `groups` of the group of the particle at `index`, if it is in one.
+    pub fn group_index(&self, index: usize) -> Option<usize> {
+        let group = *self.group[..self.count].get(index)? as usize;
+        (group < self.groups.len()).then_some(group)
+    }
+    /// Move the particle at `index` into the group named `name`, or out of its group with
+    /// `None`; false if there is no such particle or group.
+    pub fn set_group(&mut self, index: usize, name: Option<&str>) -> bool {
+        let group = match name {
+            Some(name) => match self.groups.iter().take(NO_GROUP as usize).position(|group| group.name == name) {
+                Some(k) => k as u8,
+                None => return false,
             },
-            attractor: None,
+            None => NO_GROUP,
+        };
+        if index >= self.count {
+            return false;
+        }
+        self.group[index] = group;
+        true
+    }
+    /// Put every live particle back into the group of its emitter.
+    pub fn regroup(&mut self) {
+        for i in 0..self.count {
+            self.group[i] = self.group_of_emitter(self.emitter[i]);
+        }
+    }
+    /// Color of each group's tint at the current time, indexed like `groups`; empty
+    /// without tints.
+    pub fn group_tints(&self) -> Vec<Option<[u8; 4]>> {
+        if self.groups.iter().all(|group| group.color.is_none()) {
+            return Vec::new();
+        }
+        self.groups.iter().map(|group| group.tint(group.weight(self.time))).collect()
+    }
+    /// Current indices of both ends of `spring`, if they are alive.
+    pub fn spring_ends(&self, spring: &Spring) -> Option<(usize, usize)> {
+        Some((self.index_of(spring.a, spring.hints.0)?, self.index_of(spring.b, spring.hints.1)?))
+    }
+    /// Statistics of `emitter`; all zero if it never spawned anything.
+    pub fn emitter_stats(&self, emitter: EmitterId) -> EmitterStats {
+        self.emitter_stats.get(emitter as usize).copied().unwrap_or_default()
+    }
+    /// Throughput of the sink at `index` in `sinks`.
+    pub fn sink_stats(&self, index: usize) -> FlowStats {
+        self.sink_stats.get(index).copied().unwrap_or_default()
+    }
+    /// Throughput of the source at `index` in `sources`.
+    pub fn source_stats(&self, index: usize) -> FlowStats {
+        self.source_stats.get(index).copied().unwrap_or_default()
+    }
+    /// Hits and expiries during the last `update`, at most `MAX_EVENTS`.
+    pub fn events(&self) -> &[ParticleEvent] {
+        &self.events
+    }
+    /// Take the events of the last `update` out, e.g. to queue sounds elsewhere.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = ParticleEvent> + '_ {
+        self.events.drain(..)
+    }
+    /// Spawns and deaths during the last `update` and since, in order, when enabled with
+    /// `record_lifecycle`. Every particle is accounted for as long as the system is
+    /// updated or drained before `MAX_LIFECYCLE_EVENTS` pile up; later ones are dropped.
+    pub fn lifecycle_events(&self) -> &[ParticleEvent] {
+        &self.lifecycle
+    }
+    /// Take the lifecycle events out, see `lifecycle_events`.
+    pub fn drain_lifecycle_events(&mut self) -> impl Iterator<Item = ParticleEvent> + '_ {
+        self.dispatched = 0;
+        self.lifecycle.drain(..)
+    }
+    /// Also record a `Spawned` event for every new particle and a `Died` event with the
+    /// cause for every removed one, e.g. to keep scores or counters in sync, see
+    /// `lifecycle_events`. Spawns and removals between updates, including those of
+    /// `clear` and `restore`, are passed to the listeners of the next `update`; a system
+    /// that is paused for long should drain them with `drain_lifecycle_events`.
+    pub fn record_lifecycle(&mut self, enabled: bool) {
+        self.record_lifecycle = enabled;
+    }
+    /// Register a callback run for every event at the end of each `update`, e.g. to play
+    /// a sound on impact, and return its index.
+    pub fn add_event_listener(&mut self, listener: impl FnMut(&ParticleEvent) + Send + Sync + 'static) -> usize {
+        self.event_listeners.push(Box::new(listener));
+        self.event_listeners.len() - 1
+    }
+    /// Remove the event listener at `index`; later listeners move down by one.
+    pub fn remove_event_listener(&mut self, index: usize) -> Option<EventListener> {
+        (index < self.event_listeners.len()).then(|| self.event_listeners.remove(index))
+    }
+    pub fn clear_event_listeners(&mut self) {
+        self.event_listeners.clear();
+    }
+    /// Wall and obstacle impacts accumulated since the last `clear_impacts`.
+    pub fn impacts(&self) -> &ImpactMap {
+        &self.impacts
+    }
+    pub fn clear_impacts(&mut self) {
+        self.impacts.clear();
+    }
+    /// Immediately kill all live particles spawned by `emitter`.
+    pub fn kill_emitter(&mut self, emitter: EmitterId) {
+        let mut i = 0;
+        while i < self.count {
+            if self.emitter[i] == emitter {
+                // the last particle moved into `i`, check it next
+                self.kill(i, DeathCause::Removed);
+            } else {
+                i += 1;
+            }
+        }
+        self.rebuild_grid();
+    }
+    /// Fade out all live particles spawned by `emitter` so they expire within `duration`
+    /// units of simulation time.
+    pub fn fade_out_emitter(&mut self, emitter: EmitterId, duration: f32) {
+        for i in 0..self.count {
+            if self.emitter[i] == emitter && self.lifetime[i] > 0.0 {
+                self.lifetime[i] = self.lifetime[i].min(duration);
+                self.decay[i] = 1.0;
+            }
+        }
+    }
+    /// Set how fast the particle at `index` ages, in lifetime per unit of simulation time.
+    /// Spawned particles age at `1.0`; `0.0` freezes their lifetime.
+    pub fn set_decay(&mut self, index: usize, rate: f32) {
+        if index < self.count {
+            self.decay[index] = rate;
+        }
+    }
+    /// Scale the drag on the particle at `index`, and with it the push of the wind: above
+    /// `1.0` for fluffy particles that the air slows and carries along quickly, below for
+    /// dense ones that plough on. Spawned particles start at `1.0`.
+    pub fn set_drag(&mut self, index: usize, drag: f32) {
+        if index < self.count {
+            self.drag[index] = drag;
+        }
+    }
+    /// Number of particles currently simulated, including dying ones.
+    pub fn live_count(&self) -> usize {
+        self.count
+    }
+    /// How many more particles can be spawned before the system is full.
+    pub fn free_slots(&self) -> usize {
+        self.capacity - self.count
+    }
+    /// Copy the particle state (not the parameters or interactive elements).
+    pub fn snapshot(&self) -> Snapshot {
+        let n = self.count;
+        Snapshot {
+            count: n,
+            position: self.position[..n].to_vec(),
+            velocity: self.velocity[..n].to_vec(),
+            mass: self.mass[..n].to_vec(),
+            radius: self.radius[..n].to_vec(),
+            lifetime: self.lifetime[..n].to_vec(),
+            decay: self.decay[..n].to_vec(),
+            drag: self.drag[..n].to_vec(),
+            fade: self.fade[..n].to_vec(),
+            emitter: self.emitter[..n].to_vec(),
+            group: self.group[..n].to_vec(),
+            color: self.color[..n].to_vec(),
+            age: self.age[..n].to_vec(),
+            id: self.id[..n].to_vec(),
+            next_id: self.next_id,
+            time: self.time,
+            rng: self.rng.clone(),
+        }
+    }
+    /// Return the particles to a state taken with `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let n = snapshot.count.min(self.capacity);
+        // particles missing from the snapshot die, those only in it spawn again
+        let mut before = Vec::new();
+        if self.record_lifecycle {
+            let mut restored = snapshot.id[..n].to_vec();
+            restored.sort_unstable();
+            for i in 0..self.count {
+                if restored.binary_search(&self.id[i]).is_err() {
+                    self.record_death(i, DeathCause::Rewound);
+                }
+            }
+            before = self.id[..self.count].to_vec();
+            before.sort_unstable();
+        }
+        self.count = n;
+        self.position[..n].copy_from_slice(&snapshot.position[..n]);
+        self.previous_position[..n].copy_from_slice(&snapshot.position[..n]);
+        self.velocity[..n].copy_from_slice(&snapshot.velocity[..n]);
+        self.mass[..n].copy_from_slice(&snapshot.mass[..n]);
+        self.radius[..n].copy_from_slice(&snapshot.radius[..n]);
+        self.lifetime[..n].copy_from_slice(&snapshot.lifetime[..n]);
+        self.decay[..n].copy_from_slice(&snapshot.decay[..n]);
+        self.drag[..n].copy_from_slice(&snapshot.drag[..n]);
+        self.fade[..n].copy_from_slice(&snapshot.fade[..n]);
+        self.emitter[..n].copy_from_slice(&snapshot.emitter[..n]);
+        self.group[..n].copy_from_slice(&snapshot.group[..n]);
+        self.color[..n].copy_from_slice(&snapshot.color[..n]);
+        self.age[..n].copy_from_slice(&snapshot.age[..n]);
+        self.id[..n].copy_from_slice(&snapshot.id[..n]);
+        self.next_id = snapshot.next_id;
+        self.time = snapshot.time;
+        self.external_forces[..n].fill(Vec2::ZERO);
+        self.rng = snapshot.rng.clone();
+
+        // live counts follow the restored particles
+        for stats in &mut self.emitter_stats {
+            stats.live = 0;
+        }
+        for i in 0..n {
+            self.stats_mut(self.emitter[i]).live += 1;
+            if self.record_lifecycle && before.binary_search(&self.id[i]).is_err() {
+                self.record_spawn(i);
+            }
+        }
+        self.rebuild_grid();
+    }
+    /// Kill the most recently spawned particles until at most `max` are live.
+    pub fn limit_live(&mut self, max: usize) {
+        while self.count > max {
+            self.kill(self.count - 1, DeathCause::Removed);
+        }
+        self.rebuild_grid();
+    }
+    /// Whether `index` holds a particle that is simulated and drawn, including dying ones.
+    pub fn is_alive(&self, index: usize) -> bool {
+        index < self.count
+    }
+    /// Whether the particle's lifetime expired and it is fading out.
+    pub fn is_dying(&self, index: usize) -> bool {
+        self.is_alive(index) && self.lifetime[index] <= 0.0
+    }
+    /// Remove the particle at `i` by moving the last particle into its slot.
+    fn kill(&mut self, i: usize, cause: DeathCause) {
+        self.record_death(i, cause);
+        let stats = self.stats_mut(self.emitter[i]);
+        stats.live = stats.live.saturating_sub(1);
+
+        let last = self.count - 1;
+        self.position.swap(i, last);
+        self.previous_position.swap(i, last);
+        self.velocity.swap(i, last);
+        self.forces.swap(i, last);
+        self.external_forces.swap(i, last);
+        self.mass.swap(i, last);
+        self.radius.swap(i, last);
+        self.lifetime.swap(i, last);
+        self.decay.swap(i, last);
+        self.drag.swap(i, last);
+        self.fade.swap(i, last);
+        self.emitter.swap(i, last);
+        self.group.swap(i, last);
+        self.color.swap(i, last);
+        self.age.swap(i, last);
+        self.id.swap(i, last);
+        // spring forces of the step in progress follow their particle
+        if last < self.spring_ranges.len() {
+            self.spring_ranges.swap(i, last);
+        }
+        self.count = last;
+    }
+    fn record_spawn(&mut self, i: usize) {
+        self.record_lifecycle_event(ParticleEvent::Spawned {
+            particle: self.id[i],
+            emitter: self.emitter[i],
+            position: self.position[i],
+            velocity: self.velocity[i],
+        });
+    }
+    fn record_death(&mut self, i: usize, cause: DeathCause) {
+        self.record_lifecycle_event(ParticleEvent::Died {
+            particle: self.id[i],
+            emitter: self.emitter[i],
+            position: self.position[i],
+            velocity: self.velocity[i],
+            cause,
+        });
+    }
+    fn record_lifecycle_event(&mut self, event: ParticleEvent) {
+        if self.record_lifecycle && self.lifecycle.len() < MAX_LIFECYCLE_EVENTS {
+            self.lifecycle.push(event);
+        }
+    }
+    fn stats_mut(&mut self, emitter: EmitterId) -> &mut EmitterStats {
+        let index = emitter as usize;
+        if index >= self.emitter_stats.len() {
+            self.emitter_stats.resize(index + 1, EmitterStats::default());
+        }
+        &mut self.emitter_stats[index]
+    }
+
+    /// Update the `ParticleSystem` internal state; bounce the particles around the screen.
+    pub fn update(&mut self) {
+        self.update_with_dt(self.simulation.dt);
+    }
+
+    /// Like `update`, advancing by `dt` instead of `SimParams::dt`.
+    ///
+    /// Runs `SimParams::substeps` smaller steps so strong forces neither tunnel through
+    /// walls nor blow up at large `dt`. Forces added with `add_external_force` act on all of them.
+    pub fn update_with_dt(&mut self, dt: f32) {
+        self.previous_position[..self.count].copy_from_slice(&self.position[..self.count]);
+        self.events.clear();
+        self.event_marks.clear();
+        // keep the spawns and deaths since the last update for the listeners
+        self.lifecycle.drain(..self.dispatched.min(self.lifecycle.len()));
+        let substeps = self.simulation.substeps.clamp(1, MAX_SUBSTEPS);
+        for _ in 0..substeps {
+            self.substep(dt / substeps as f32);
+        }
+        self.external_forces[..self.count].fill(Vec2::ZERO);
+        // fading walks every pixel, so do it once for the whole update
+        if let Some(decals) = &mut self.decals {
+            decals.fade(dt);
+        }
+        for stats in &mut self.emitter_stats {
+            stats.end_update(dt);
+        }
+        for stats in self.sink_stats.iter_mut().chain(&mut self.source_stats) {
+            stats.end_update(dt);
+        }
+        for listener in &mut self.event_listeners {
+            recording_order(&self.events, &self.event_marks, &self.lifecycle).for_each(&mut *listener);
+        }
+        self.dispatched = self.lifecycle.len();
+    }
+
+    fn substep(&mut self, dt: f32) {
+        self.scratch.reset();
+        let first_event = self.events.len();
+        if !self.emitters_paused {
+            let mut emitters = std::mem::take(&mut self.emitters);
+            for emitter in &mut emitters {
+                emitter.emit(dt, self);
+            }
+            // keep emitters added while emitting
+            emitters.append(&mut self.emitters);
+            self.emitters = emitters;
+        }
+        self.solve_springs();
+        let g = self.simulation.gravity;
+        let wind = self.simulation.wind.at(self.time);
+        let acc = self.simulation.acceleration;
+        let drag = self.simulation.global_drag;
+        let restitution = self.simulation.restitution;
+        // attractors, repellers and vortices push per update: spread each push evenly over the
+        // update's substeps, as a force over this substep's `dt`
+        let substeps = self.simulation.substeps.clamp(1, MAX_SUBSTEPS) as f32;
+        let push_scale = if dt > 0.0 { 1.0 / (substeps * dt) } else { 0.0 };
+        let group_physics: Vec<(Vec2, Vec2, f32)> = self
+            .groups
+            .iter()
+            .map(|group| group.physics(group.weight(self.time), g, drag, restitution))
+            .collect();
+        let turbulence = self.simulation.turbulence;
+        let curl_noise = self.simulation.curl_noise;
+        let jitter = self.aggregate.as_ref().map_or(0.0, |aggregate| aggregate.config.jitter);
+        // taken so the loop below can still borrow `self` mutably
+        let flow_field = self.flow_field.take();
+        let mut fluid = self.fluid.take();
+        let life = self.life.take();
+        for swarm in &mut self.swarms {
+            swarm.update_path(self.width, self.height, &self.obstacles, &self.wall_sdf);
+        }
+        if let Some(fluid) = &mut fluid {
+            let n = self.count;
+            fluid.couple(&self.position[..n], &self.velocity[..n], &self.mass[..n], dt);
+            fluid.step(dt);
+        }
+        let integrator = self.simulation.integrator.integrator();
+        let time = self.time;
+        let walls = self.simulation.walls;
+        // low and high edge of each axis, bouncing with `restitution` unless overridden
+        let edges_for = |restitution: f32| {
+            let edge = |behavior: Option<BoundaryBehavior>| behavior.unwrap_or(BoundaryBehavior::Bounce(restitution));
+            [(edge(walls.left), edge(walls.right)), (edge(walls.top), edge(walls.bottom))]
+        };
+        let edges = edges_for(restitution);
+        let extents = [self.width as f32, self.height as f32];
+        let soft_edges = edges.iter().any(|&(low, high)| {
+            matches!(low, BoundaryBehavior::Soft { .. }) || matches!(high, BoundaryBehavior::Soft { .. })
+        });
+        let keep_tangential = 1.0 - self.simulation.wall_friction;
+        let surface_friction = self.simulation.surface_friction;
+        // Coulomb friction: slow the tangential speed by up to `surface_friction` times the
+        // normal speed change, so particles resting on a surface come to a stop
+        let rub = |tangential: Vec2, normal_change: f32| {
+            let speed = tangential.length();
+            if speed == 0.0 {
+                return tangential;
+            }
+            tangential * (1.0 - surface_friction * normal_change / speed).max(0.0)
+        };
+        let fade_step = if self.simulation.fade_duration > 0.0 {
+            dt / self.simulation.fade_duration
+        } else {
+            1.0
+        };
+
+        // push out along the contact normal and reflect like the walls; returns the impact speed
+        let bounce = |pos: &mut Vec2, vel: &mut Vec2, (normal, depth): (Vec2, f32), restitution: f32| {
+            *pos += normal * depth;
+            let vn = vel.dot(normal);
+            if vn < 0.0 {
+                let tangential = rub(*vel - vn * normal, -vn * (1.0 + restitution));
+                *vel = tangential * keep_tangential - vn * restitution * normal;
+            }
+            -vn
+        };
+
+        self.impacts.decay(dt);
+        if let Some(wetness) = &mut self.wetness {
+            wetness.step(dt);
+        }
+        self.sink_stats.resize(self.sinks.len(), FlowStats::default());
+        self.source_stats.resize(self.sources.len(), FlowStats::default());
+
+        // killed particles are swapped with the last one, which is then updated at `i`
+        let mut i = 0;
+        while i < self.count {
+            let m = self.mass[i];
+            let radius = self.radius[i];
+            let mut pos = self.position[i];
+            let mut start = pos;
+            let mut vel = self.velocity[i];
+            let mut lt = self.lifetime[i];
+            if let Some(curl_noise) = &curl_noise {
+                vel = curl_noise.advect(pos, vel, time, dt);
+            }
+            if jitter > 0.0 {
+                // random walk of the aggregating particles
+                vel += Vec2::from_angle(self.rng.random::<f32>() * std::f32::consts::TAU) * jitter * dt.sqrt();
+            }
+
+            let external = self.external_forces[i]; // `add_external_force`
+            let (first_link, links) = self.spring_ranges.get(i).copied().unwrap_or_default();
+            let springs = &self.spring_links[first_link as usize..(first_link + links) as usize];
+            let group = Some(self.group[i] as usize).filter(|&group| group < group_physics.len());
+            let (g, drag, restitution) = group.map_or((g, drag, restitution), |group| group_physics[group]);
+            let edges = if restitution == self.simulation.restitution { edges } else { edges_for(restitution) };
+            let group_name = group.map(|group| self.groups[group].name.as_str());
+            // wind acts through the drag, pulling velocity towards `wind / drag`: fluffy
+            // particles catch it quickly, dense ones hardly notice it
+            let coupling = self.drag[i];
+            let global = GlobalForces { gravity: g, wind: wind * coupling, acceleration: acc, drag: drag * coupling };
+            let (emitter, positions) = (self.emitter[i], &self.position[..self.count]);
+            let (wells, swarms, custom_forces) = (&self.gravity_wells, &self.swarms, &self.custom_forces);
+            let (attractors, pointer_attractor, touch_attractors) =
+                (&self.attractors, &self.pointer_attractor, &self.touch_attractors);
+            let (repellers, vortices) = (&self.repellers, &self.vortices);
+            let force = |pos: Vec2, vel: Vec2| {
+                let particle = ParticleView {
+                    position: pos,
+                    velocity: vel,
+                    mass: m,
+                    radius,
+                    emitter,
+                    group: group_name,
+                    time,
+                    positions,
+                };
+                let mut f = Vec2::new(0.0, 0.0);
+                f += global.apply(i, &particle);
+                f += external;
+                // springs are evaluated at each probe state, which Verlet and RK4 rely on
+                for link in springs {
+                    f += link.force(pos, vel);
+                }
+                if let Some(turbulence) = &turbulence {
+                    f += turbulence.apply(i, &particle);
+                }
+                if let Some(flow) = &flow_field {
+                    f += flow.apply(i, &particle);
+                }
+                if let Some(fluid) = &fluid {
+                    f += fluid.apply(i, &particle);
+                }
+                if let Some(life) = &life {
+                    f += life.apply(i, &particle);
+                }
+                for well in wells {
+                    f += well.apply(i, &particle);
+                }
+                for swarm in swarms {
+                    f += swarm.apply(i, &particle);
+                }
+                for custom in custom_forces {
+                    f += custom.apply(i, &particle);
+                }
+                let mut push = Vec2::ZERO;
+                for attractor in attractors.iter().chain(pointer_attractor).chain(touch_attractors) {
+                    push += attractor.apply(i, &particle);
+                }
+                for repeller in repellers {
+                    push += repeller.apply(i, &particle);
+                }
+                for vortex in vortices {
+                    push += vortex.apply(i, &particle);
+                }
+                f += push * push_scale;
+                if soft_edges {
+                    for axis in 0..2 {
+                        let (low, high) = edges[axis];
+                        f[axis] += (low.push(pos[axis] - radius) - high.push(extents[axis] - pos[axis] - radius)) * m;
+                    }
+                }
+                f
+            };
+            let f = force(pos, vel);
+
+            (pos, vel) = integrator.step(pos, vel, dt, &|pos, vel| force(pos, vel) / m);
+
+            // rain reaching the floor water is absorbed into it
+            if let Some(wetness) = self.wetness.as_mut().filter(|wetness| wetness.collects(emitter)) {
+                if pos.y + radius >= self.height as f32 - wetness.level_at(pos.x) {
+                    wetness.add(pos.x, m);
+                    self.position[i] = pos;
+                    self.velocity[i] = vel;
+                    self.kill(i, DeathCause::Absorbed);
+                    continue;
+                }
+            }
+
+            // fastest contact of the particle with anything this step
+            let mut impact = Impact::default();
+            // edges: bounces reflect the normal velocity scaled by restitution and damp the
+            // tangential velocity by friction
+            let mut killed = false;
+            for axis in 0..2 {
+                let (low, high) = edges[axis];
+                let normal_before = vel[axis];
+                let mut normal = Vec2::ZERO;
+                let outcome = if pos[axis] - radius <= 0.0 {
+                    normal[axis] = 1.0;
+                    low.apply(&mut pos[axis], &mut vel[axis], radius, extents[axis], false)
+                } else if pos[axis] + radius >= extents[axis] {
+                    normal[axis] = -1.0;
+                    high.apply(&mut pos[axis], &mut vel[axis], radius, extents[axis], true)
+                } else {
+                    EdgeOutcome::Inside
+                };
+                match outcome {
+                    EdgeOutcome::Inside => {}
+                    EdgeOutcome::Bounced(speed) => {
+                        let tangent = 1 - axis;
+                        let normal_change = (vel[axis] - normal_before).abs();
+                        impact.note(speed, normal, m * normal_change);
+                        vel[tangent] = rub(Vec2::new(vel[tangent], 0.0), normal_change).x * keep_tangential;
+                    }
+                    EdgeOutcome::Wrapped(offset) => {
+                        // keep the step and the interpolated position continuous
+                        start[axis] += offset;
+                        self.previous_position[i][axis] += offset;
+                    }
+                    EdgeOutcome::Killed => killed = true,
+                    EdgeOutcome::Stuck(speed) => {
+                        impact.note(speed, normal, m * speed);
+                        pos[1 - axis] = start[1 - axis];
+                        vel = Vec2::ZERO;
+                    }
+                }
+            }
+            if killed {
+                self.position[i] = pos;
+                self.velocity[i] = vel;
+                self.kill(i, DeathCause::Boundary);
+                continue;
+            }
+            // obstacles and drawn walls
+            for shape in &self.obstacles {
+                if let Some(contact) = shape.contact(pos, radius) {
+                    let speed = bounce(&mut pos, &mut vel, contact, restitution);
+                    impact.note(speed, contact.0, m * speed.max(0.0) * (1.0 + restitution));
+                }
+            }
+            if let Some(contact) = self.wall_sdf.contact(pos, radius) {
+                let speed = bounce(&mut pos, &mut vel, contact, restitution);
+                impact.note(speed, contact.0, m * speed.max(0.0) * (1.0 + restitution));
+            }
+            // one-way gates turn particles back, portals move them keeping their velocity
+            for gate in &self.gates {
+                if gate.blocks(start, pos) {
+                    let n = gate.normal();
+                    let speed = -vel.dot(n);
+                    impact.note(speed, n, m * speed.max(0.0) * (1.0 + restitution));
+                    pos = start;
+                    vel -= (1.0 + restitution) * vel.dot(n).min(0.0) * n;
+                }
+            }
+            if impact.speed > IMPACT_SPEED {
+                self.impacts.record(pos, 0.5 * m * impact.speed * impact.speed);
+                if let Some(decals) = &mut self.decals {
+                    decals.stamp(pos, impact.speed);
+                }
+                record_event(&mut self.events, &mut self.event_marks, self.lifecycle.len(), ParticleEvent::Hit {
+                    particle: self.id[i],
+                    index: i,
+                    emitter: self.emitter[i],
+                    position: pos,
+                    velocity: vel,
+                    contact: pos - impact.normal * radius,
+                    normal: impact.normal,
+                    speed: impact.speed,
+                    impulse: impact.impulse,
+                });
+            }
+            if let Some(exit) = self.portals.iter().find_map(|portal| portal.teleport(start, pos)) {
+                pos = exit;
+            }
+            if let Some(sink) = self.sinks.iter().position(|sink| sink.region.contains(pos)) {
+                self.sink_stats[sink].count(1);
+                self.absorbed += 1;
+                self.position[i] = pos;
+                self.velocity[i] = vel;
+                self.kill(i, DeathCause::Absorbed);
+                continue;
+            }
+            
+            // age in simulation time; `INFINITE_LIFETIME` never expires
+            lt -= self.decay[i] * dt;
+            self.age[i] += self.decay[i] * dt;
+
+            if self.lifetime[i] > 0.0 && lt <= 0.0 {
+                record_event(&mut self.events, &mut self.event_marks, self.lifecycle.len(), ParticleEvent::Expired {
+                    particle: self.id[i],
+                    emitter: self.emitter[i],
+                    position: pos,
+                    velocity: vel,
+                });
+            }
+
+            // write back mutated values
+            self.forces[i] = f;
+            self.velocity[i] = vel;
+            self.position[i] = pos;
+            self.lifetime[i] = lt;
+            if lt <= 0.0 {
+                // dying: shrink out before being removed
+                self.fade[i] -= fade_step;
+                if self.fade[i] <= 0.0 {
+                    self.kill(i, DeathCause::Expired);
+                    continue;
+                }
+            }
+            i += 1;
         }
-    }
-    pub fn spawn(&mut self, pos: [f32; 2], vel: [f32; 2], mass: f32, lifetime: f32) {
-        if self.count < self.capacity {
-            self.position[self.count] = Vec2::new(pos[0], pos[1]);
-            self.velocity[self.count] = Vec2::new(vel[0], vel[1]);
-            self.mass[self.count] = mass;
-            self.lifetime[self.count] = lifetime;
-            self.count += 1;
+        self.flow_field = flow_field;
+        self.fluid = fluid;
+        self.life = life;
+        if let Some(collisions) = self.simulation.collisions {
+            self.collide(collisions.restitution);
         }
+        self.apply_pins();
+        self.aggregate_particles();
+        self.apply_spawn_rules(first_event);
+        self.step_life(dt, first_event);
+        self.emit_sources(dt);
+        self.rebuild_grid();
+        self.time += dt;
     }
-    pub fn spawn_random(&mut self, mass: f32, lifetime: f32) {
-        if self.count < self.capacity {
-            let position = [
-                rand::random::<f32>() * self.width as f32,
-                rand::random::<f32>() * self.height as f32,
-            ];
-            let velocity = [
-                (rand::random::<f32>() - 0.5) * 4.0,
-                (rand::random::<f32>() - 0.5) * 4.0,
-            ];
-            self.spawn(position, velocity, mass, lifetime);
-        }
+
+    /// Bucket the live particles for `neighbors_within` again, after particles moved or
+    /// removals reordered them.
+    fn rebuild_grid(&mut self) {
+        // no other scratch allocation outlives the build, here or between updates
+        self.scratch.reset();
+        let wrap = self.wrapped_axes();
+        self.grid.build(&self.position[..self.count], GRID_CELL, (self.width, self.height), wrap, &mut self.scratch);
     }
 
-    /// Update the `ParticleSystem` internal state; bounce the particles around the screen.
-    pub fn update(&mut self) {
-        let g = self.simulation.gravity;
-        let wind = self.simulation.wind;
-        let acc = self.simulation.acceleration;
-        let drag = self.simulation.global_drag;
-        let dt = self.simulation.dt;
-        let radius = self.radius as f32;
+    /// Gather the springs at every particle into `spring_links`, with the other ends as
+    /// they are now, dropping springs whose ends died.
+    fn solve_springs(&mut self) {
+        self.spring_ranges.clear();
+        self.spring_ranges.resize(self.count, (0, 0));
+        self.spring_links.clear();
+        if self.springs.is_empty() {
+            return;
+        }
+        let mut springs = std::mem::take(&mut self.springs);
+        springs.retain_mut(|spring| {
+            let Some((a, b)) = self.spring_ends(spring) else {
+                return false;
+            };
+            spring.hints = (a, b);
+            self.spring_ranges[a].1 += 1;
+            self.spring_ranges[b].1 += 1;
+            true
+        });
+        let mut start = 0;
+        for range in &mut self.spring_ranges {
+            let len = range.1;
+            *range = (start, 0);
+            start += len;
+        }
+        let Some(&first) = springs.first() else {
+            self.springs = springs;
+            return;
+        };
+        // placeholder links, each overwritten below
+        let placeholder = SpringLink { spring: first, at_a: true, other_position: Vec2::ZERO, other_velocity: Vec2::ZERO };
+        self.spring_links.resize(start as usize, placeholder);
+        for &spring in &springs {
+            let (a, b) = spring.hints;
+            for (at, other, at_a) in [(a, b, true), (b, a, false)] {
+                let range = &mut self.spring_ranges[at];
+                self.spring_links[(range.0 + range.1) as usize] = SpringLink {
+                    spring,
+                    at_a,
+                    other_position: self.position[other],
+                    other_velocity: self.velocity[other],
+                };
+                range.1 += 1;
+            }
+        }
+        self.springs = springs;
+    }
 
-        for i in 0..self.count {
-            let m = self.mass[i];
-            let mut pos = self.position[i];
-            let mut vel = self.velocity[i];
-            let mut lt = self.lifetime[i];
+    /// Return pinned particles to their anchors, dropping pins of dead particles.
+    fn apply_pins(&mut self) {
+        let mut pins = std::mem::take(&mut self.pins);
+        pins.retain_mut(|pin| {
+            let Some(index) = self.index_of(pin.particle, pin.hint) else {
+                return false;
+            };
+            pin.hint = index;
+            self.position[index] = pin.anchor;
+            self.velocity[index] = Vec2::ZERO;
+            true
+        });
+        self.pins = pins;
+    }
 
-            let mut f = Vec2::new(0.0, 0.0);
-            f += g * m;         // gravity
-            f += wind;          // wind
-            f += acc * m;       // external acceleration
-            f += - drag * vel;  // simple drag: F = -k v
+    /// Separate overlapping particles and exchange momentum along their contact normal.
+    fn collide(&mut self, restitution: f32) {
+        let n = self.count;
+        let max_radius = self.radius[..n].iter().fold(0.0f32, |max, &r| max.max(r));
+        if max_radius <= 0.0 {
+            return;
+        }
+        let mut grid = std::mem::take(&mut self.grid);
+        let wrap = self.wrapped_axes();
+        grid.build(&self.position[..n], GRID_CELL.max(2.0 * max_radius), (self.width, self.height), wrap, &mut self.scratch);
+        for i in 0..n {
+            let reach = self.radius[i] + max_radius;
+            // each pair once
+            for j in grid.near(self.position[i], reach).filter(|&j| j > i) {
+                // across the wrap seam when that is closer
+                let offset = self.offset_between(self.position[i], self.position[j]);
+                let distance = offset.length();
+                let overlap = self.radius[i] + self.radius[j] - distance;
+                if overlap <= 0.0 || distance == 0.0 {
+                    continue;
+                }
+                let normal = offset / distance;
+                let (inv_i, inv_j) = (1.0 / self.mass[i], 1.0 / self.mass[j]);
+                let share = overlap / (inv_i + inv_j);
+                self.position[i] -= normal * share * inv_i;
+                self.position[j] += normal * share * inv_j;
 
-            // semi-implicit Euler integration  
-            let acceleration = f / m;
+                let approach = (self.velocity[j] - self.velocity[i]).dot(normal);
+                if approach < 0.0 {
+                    let impulse = -(1.0 + restitution) * approach / (inv_i + inv_j);
+                    self.velocity[i] -= normal * impulse * inv_i;
+                    self.velocity[j] += normal * impulse * inv_j;
+                }
+            }
+        }
+        self.grid = grid;
+    }
 
-            vel += acceleration * dt;
-            
-            pos += vel * dt;       
-            // simple wall collisions
-            if pos[0] - radius <= 0.0 || pos[0] + radius >= self.width as f32 {
-                vel[0] *= -1.0;
-                pos[0] = pos[0].clamp(0.0, (self.width - radius as usize) as f32);
+    /// Freeze the particles touching the aggregate into it.
+    fn aggregate_particles(&mut self) {
+        let Some(mut aggregate) = self.aggregate.take() else {
+            return;
+        };
+        aggregate.refresh();
+        let mut i = 0;
+        while i < self.count {
+            let (pos, radius) = (self.position[i], self.radius[i]);
+            if aggregate.touches(pos, radius) && self.rng.random::<f32>() < aggregate.config.stickiness {
+                aggregate.freeze(pos, radius);
+                self.kill(i, DeathCause::Absorbed);
+            } else {
+                i += 1;
             }
-            if pos[1] - radius <= 0.0 || pos[1] + radius >= self.height as f32 {
-                vel[1] *= -1.0;
-                pos[1] = pos[1].clamp(0.0, (self.height - radius as usize) as f32);
+        }
+        self.aggregate = Some(aggregate);
+    }
+
+    /// Seed life cells where the events from `first` on hit hard enough, then advance the
+    /// automaton and emit particles from the newborn cells.
+    fn step_life(&mut self, dt: f32, first: usize) {
+        let Some(mut life) = self.life.take() else {
+            return;
+        };
+        for event in &self.events[first..] {
+            if let ParticleEvent::Hit { position, speed, .. } = *event {
+                if speed >= life.config.seed_speed {
+                    life.seed(position);
+                }
             }
-            
-            //  repell at bottom left corner
-            if pos[0] < 10.0 && pos[1] >= 0.95 * self.height as f32 {
-                vel += Vec2::new(2.0,-8.0) / m;
+        }
+        let config = life.config.clone();
+        'births: for center in life.advance(dt) {
+            for _ in 0..config.emit {
+                let vel = Vec2::from_angle(self.rng.random::<f32>() * std::f32::consts::TAU) * config.emit_speed;
+                if self.spawn_owned(config.emitter, center.into(), vel.into(), config.mass, config.lifetime).is_none() {
+                    break 'births;
+                }
             }
-            if self.attractor.is_some() {
-                let attractor = self.attractor.as_ref().unwrap();
-                let to_particle = pos - attractor.position;
-                let distance = to_particle.length();
-                if distance < attractor.radius as f32 {
-                    let n = to_particle * (1.0 / distance);
-                    let falloff = 1.0 - (distance / attractor.radius as f32);
-                    vel += -n * falloff * attractor.strength / m;
+        }
+        self.life = Some(life);
+    }
+
+    /// Spawn the particles of `spawn_rules` for the events from `first` on.
+    fn apply_spawn_rules(&mut self, first: usize) {
+        if self.spawn_rules.is_empty() {
+            return;
+        }
+        let rules = std::mem::take(&mut self.spawn_rules);
+        for e in first..self.events.len() {
+            let event = self.events[e];
+            let (position, velocity) = event.motion();
+            for rule in rules.iter().filter(|rule| rule.matches(&event)) {
+                for _ in 0..rule.count {
+                    let vel = velocity * rule.inherit + rule.velocity.sample(&mut self.rng);
+                    let mass = rule.mass.sample(&mut self.rng).max(MIN_MASS);
+                    let lifetime = rule.lifetime.sample(&mut self.rng);
+                    if self.spawn_owned(rule.emitter, position.into(), vel.into(), mass, lifetime).is_none() {
+                        break;
+                    }
                 }
             }
-            // lt -= 0.001;
-            if lt < 0.0 {
-                lt = 0.0;
-                vel = Vec2::ZERO;
-                pos = Vec2::new(-100.0, -100.0); // move off-screen
+        }
+        self.spawn_rules = rules;
+    }
+
+    /// Spawn the particles owed by fixed-rate sources and re-emit absorbed ones.
+    fn emit_sources(&mut self, dt: f32) {
+        let matching = self.sources.iter().filter(|source| source.rate.is_none()).count();
+        let absorbed = std::mem::take(&mut self.absorbed);
+        let mut nth_matching = 0;
+        for k in 0..self.sources.len() {
+            let source = self.sources[k];
+            let due = match source.rate {
+                Some(rate) => self.source_stats[k].due(rate, dt),
+                None => {
+                    // spread the remainder over the first matching sources
+                    let due = absorbed / matching + usize::from(nth_matching < absorbed % matching);
+                    nth_matching += 1;
+                    due
+                }
+            };
+            let live = self.live_count();
+            for _ in 0..due {
+                let pos = source.region.random_point(&mut self.rng);
+                let lifetime = source.lifetime.unwrap_or(INFINITE_LIFETIME);
+                self.spawn(pos.into(), source.velocity.into(), 1.0, lifetime);
             }
+            let emitted = self.live_count() - live;
+            self.source_stats[k].count(emitted);
+        }
+    }
+}
 
-            // write back mutated values
-            self.forces[i] = f;
-            self.velocity[i] = vel;
-            self.position[i] = pos;
-            self.lifetime[i] = lt;
+/// Fastest contact of a particle during a step.
+#[derive(Default)]
+struct Impact {
+    speed: f32,
+    normal: Vec2,
+    impulse: f32,
+}
+
+impl Impact {
+    /// Keep this contact if it is faster than the ones before.
+    fn note(&mut self, speed: f32, normal: Vec2, impulse: f32) {
+        if speed > self.speed {
+            *self = Impact { speed, normal, impulse };
         }
     }
 }
 
+/// Queue `event` unless `MAX_EVENTS` were already recorded this update, marking it with
+/// the number of lifecycle events recorded before it.
+fn record_event(events: &mut Vec<ParticleEvent>, marks: &mut Vec<usize>, lifecycle_len: usize, event: ParticleEvent) {
+    if events.len() < MAX_EVENTS {
+        events.push(event);
+        marks.push(lifecycle_len);
+    }
+}
+
+/// `events` and `lifecycle` merged in the order they were recorded, where `marks` holds
+/// the length of `lifecycle` when each of `events` was recorded.
+fn recording_order<'a>(
+    events: &'a [ParticleEvent],
+    marks: &'a [usize],
+    lifecycle: &'a [ParticleEvent],
+) -> impl Iterator<Item = &'a ParticleEvent> {
+    let mut start = 0;
+    let rest = marks.last().map_or(0, |&mark| mark.min(lifecycle.len()));
+    events
+        .iter()
+        .zip(marks)
+        .flat_map(move |(event, &mark)| {
+            let end = mark.clamp(start, lifecycle.len());
+            let before = &lifecycle[start..end];
+            start = end;
+            before.iter().chain(std::iter::once(event))
+        })
+        .chain(&lifecycle[rest..])
+}
+
+/// Distance between the seeds of flow field streamlines, in pixels.
+const STREAMLINE_SPACING: f32 = 40.0;
+/// Length of one streamline segment, in pixels.
+const STREAMLINE_STEP: f32 = 3.0;
+/// Segments per streamline.
+const STREAMLINE_STEPS: usize = 8;
+
 
 pub struct Renderer{
     width: usize,
@@ -153,19 +2174,129 @@ pub struct Renderer{
     post_process: Option<PostProcess>,
     temp_buffer: Vec<u8>,
     blur_buffer: Vec<u8>,
+    view_buffer: Vec<u8>,
+    ghost: Vec<Vec2>,
+    preview: Option<Shape>,
+    show_impacts: bool,
+    show_streamlines: bool,
+    heat_glow: bool,
+    routes: BTreeMap<EmitterId, Vec<PostProcess>>,
+    fade_out: FadeOut,
+    /// Tint over the particles' lifetime per emitter.
+    gradients: BTreeMap<EmitterId, ColorOverLifetime>,
+    theme: Theme,
+    layer_buffer: Vec<u8>,
+    bloom_buffer: Vec<Vec3>,
+    bloom_temp: Vec<Vec3>,
+    density: Vec<u32>,
+    density_cdf: Vec<f32>,
+    gpu_heatmap: bool,
+    heat_values: Vec<f32>,
+    /// Frame owned by the renderer, drawn by `render`.
+    frame: Vec<u8>,
+    footprints: FootprintCache,
     dirty_rect: Option<(usize, usize, usize, usize)>
 }
 
-#[allow(dead_code)]
-enum DrawMode {
-    Circle {radius: i16},
-    Point
-} 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawMode {
+    /// Disc sized by the particle's radius.
+    Circle,
+    Point,
+    /// Soft glowing particles: a Gaussian kernel of `radius` added per particle.
+    Splat { radius: usize },
+    /// Particle density per `cell`-sized square, colormapped.
+    Heatmap { cell: usize, scaling: DensityScaling },
+}
+
+/// Box blur the `len` values `stride` apart from `start` in `src` into the same places
+/// of `dst`, repeating the edge values. A running sum keeps it linear in `len` for any `radius`.
+fn box_blur_line(src: &[Vec3], dst: &mut [Vec3], start: usize, stride: usize, len: usize, radius: usize) {
+    let at = |i: isize| src[start + i.clamp(0, len as isize - 1) as usize * stride];
+    let r = radius as isize;
+    let norm = 1.0 / (2 * r + 1) as f32;
+    let mut sum: Vec3 = (-r..=r).map(at).sum();
+    for i in 0..len as isize {
+        dst[start + i as usize * stride] = sum * norm;
+        sum += at(i + r + 1) - at(i - r);
+    }
+}
+
+/// How each particle is drawn in the `DrawMode`s that draw particles individually.
+#[derive(Clone, Copy)]
+enum Sprite {
+    Circle,
+    Point,
+    Splat { radius: usize },
+}
+
+/// Normalized heatmap densities left for the GPU to colormap, see `Renderer::heat_grid`.
+pub struct HeatGrid<'a> {
+    /// Scaled density in `[0, 1]` per cell, row-major.
+    pub values: &'a [f32],
+    pub cols: usize,
+    pub rows: usize,
+    pub cell: usize,
+    /// View size in pixels; the last row and column of cells may be partial.
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Borrowed RGBA frame, see `Renderer::frame_view`.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameView<'a> {
+    pub pixels: &'a [u8],
+    pub width: usize,
+    pub height: usize,
+    /// Bytes from the start of one row to the next.
+    pub stride: usize,
+}
+
+impl FrameView<'_> {
+    /// The RGBA bytes of row `y`.
+    pub fn row(&self, y: usize) -> &[u8] {
+        &self.pixels[y * self.stride..][..self.width * 4]
+    }
+}
+
+/// How cell densities are normalized before colormapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DensityScaling {
+    Linear,
+    /// `ln(1 + d)`, compresses very dense cells.
+    Log,
+    /// Histogram equalization: equal color spread across occupied cells.
+    Equalized,
+}
+
+/// How expired particles disappear during `SimParams::fade_duration`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FadeOut {
+    /// Fade the color to transparent.
+    pub alpha: bool,
+    /// Shrink circles to nothing.
+    pub size: bool,
+}
+
+impl Default for FadeOut {
+    fn default() -> Self {
+        Self { alpha: true, size: true }
+    }
+}
 
-#[allow(dead_code)]
-enum PostProcess {
+/// Full-frame pass applied after the particles are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PostProcess {
     BoxBlur,
     Dilate,
+    /// Glow around pixels brighter than `threshold` (linear luminance in `[0, 1]`),
+    /// blurred over `radius` pixels. Computed in linear color space.
+    Bloom {
+        #[serde(deserialize_with = "crate::config::unit_interval")]
+        threshold: f32,
+        radius: usize,
+    },
 }
 
 impl Renderer {
@@ -177,6 +2308,25 @@ impl Renderer {
             post_process: Some(PostProcess::Dilate),
             temp_buffer: vec![0u8; width * height * 4],
             blur_buffer: vec![0u8; width * height * 4],
+            view_buffer: Vec::new(),
+            ghost: Vec::new(),
+            preview: None,
+            show_impacts: false,
+            show_streamlines: false,
+            heat_glow: false,
+            routes: BTreeMap::new(),
+            fade_out: FadeOut::default(),
+            gradients: BTreeMap::new(),
+            theme: Theme::default(),
+            layer_buffer: Vec::new(),
+            bloom_buffer: Vec::new(),
+            bloom_temp: Vec::new(),
+            density: Vec::new(),
+            density_cdf: Vec::new(),
+            gpu_heatmap: false,
+            heat_values: Vec::new(),
+            frame: Vec::new(),
+            footprints: FootprintCache::new(),
             dirty_rect: None,
             }
         }
@@ -184,24 +2334,141 @@ impl Renderer {
     ///
     /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
     pub fn draw(&mut self, frame: &mut [u8], particles: &ParticleSystem) {
+        self.draw_scene(frame, particles);
+        if self.show_impacts {
+            self.draw_impacts(frame, particles.impacts());
+        }
+    }
+    fn draw_scene(&mut self, frame: &mut [u8], particles: &ParticleSystem) {
         // Clear the frame to black
         frame.fill(0x00);
 
+        let overlays = self.theme.overlays();
+        if let Some(decals) = particles.decals.as_ref().filter(|decals| !decals.is_empty()) {
+            self.draw_decals(frame, decals);
+        }
+        // Faint reference particles behind the live ones
+        for p in &self.ghost {
+            let (x, y) = (p.x as usize, p.y as usize);
+            if x < self.width && y < self.height {
+                let idx = (y * self.width + x) * 4;
+                // zero alpha keeps ghosts out of dilation
+                frame[idx..idx + 4].copy_from_slice(&overlays.ghost);
+            }
+        }
+
+        for shape in &particles.obstacles {
+            self.fill_shape(frame, shape, overlays.obstacle);
+        }
+        for portal in &particles.portals {
+            self.fill_shape(frame, &portal.a, overlays.portal_entry);
+            self.fill_shape(frame, &portal.b, overlays.portal_exit);
+        }
+        for gate in &particles.gates {
+            self.draw_line(frame, gate.start, gate.end, overlays.gate);
+        }
+        for sink in &particles.sinks {
+            self.fill_shape(frame, &sink.region, overlays.sink);
+        }
+        for source in &particles.sources {
+            self.fill_shape(frame, &source.region, overlays.source);
+        }
+        if !particles.wall_sdf.is_empty() {
+            self.fill_walls(frame, &particles.wall_sdf, overlays.obstacle);
+        }
+        if let Some(shape) = &self.preview {
+            self.fill_shape(frame, shape, overlays.preview);
+        }
+        for spring in particles.springs() {
+            if let Some((a, b)) = particles.spring_ends(spring) {
+                self.draw_line(frame, particles.render_position(a), particles.render_position(b), overlays.spring);
+            }
+        }
+        if self.show_streamlines {
+            if let Some(flow) = &particles.flow_field {
+                self.draw_streamlines(frame, |p| flow.field.sample(p), overlays.flow);
+            }
+            if let Some(fluid) = &particles.fluid {
+                self.draw_streamlines(frame, |p| fluid.sample(p), overlays.flow);
+            }
+        }
+        if let Some(life) = &particles.life {
+            self.draw_life(frame, life);
+        }
+        if let Some(aggregate) = &particles.aggregate {
+            self.draw_aggregate(frame, aggregate);
+        }
+        if let Some(wetness) = particles.wetness.as_ref().filter(|wetness| !wetness.is_empty()) {
+            self.draw_water(frame, wetness);
+        }
+
+        let sprite = match self.mode {
+            DrawMode::Circle => Sprite::Circle,
+            DrawMode::Point => Sprite::Point,
+            DrawMode::Splat { radius } => Sprite::Splat { radius },
+            DrawMode::Heatmap { cell, scaling } => {
+                self.draw_heatmap(frame, particles, cell, scaling);
+                return;
+            }
+        };
+
+        // Particles of routed emitters get their own layer and post chain
+        let routes = std::mem::take(&mut self.routes);
+        self.draw_particles(frame, particles, sprite, |emitter| !routes.contains_key(&emitter));
+
+        // Splats are already soft, skip the full-frame passes
+        let energy = self.heat_glow.then(|| particles.impacts());
+        if !matches!(sprite, Sprite::Splat { .. }) {
+            let chain = self.post_process;
+            self.apply_post_chain(frame, chain.as_slice(), energy);
+        }
+
+        let mut layer = std::mem::take(&mut self.layer_buffer);
+        layer.resize(frame.len(), 0);
+        for (&emitter, chain) in &routes {
+            layer.fill(0);
+            self.draw_particles(&mut layer, particles, sprite, |e| e == emitter);
+            self.apply_post_chain(&mut layer, chain, energy);
+            composite_max(frame, &layer);
+        }
+        self.layer_buffer = layer;
+        self.routes = routes;
+    }
+    /// Draw the live particles whose emitter passes `include` as `sprite` and track their dirty region.
+    fn draw_particles(&mut self, frame: &mut [u8], particles: &ParticleSystem, sprite: Sprite, include: impl Fn(EmitterId) -> bool) {
         // track region of interest
         let mut min_x = self.width ;
         let mut max_x = 0;
         let mut min_y = self.height;
         let mut max_y = 0;
-                
+        let tints = particles.group_tints();
 
         for particle_index in 0..particles.count {
-            let x  = particles.position[particle_index].x as usize;
-            let y  = particles.position[particle_index].y as usize;
-            let lifetime = particles.lifetime[particle_index];
+            if !include(particles.emitter[particle_index]) {
+                continue;
+            }
+            let position = particles.render_position(particle_index);
+            let x  = position.x as usize;
+            let y  = position.y as usize;
+            // dying particles shrink and fade out
+            let fade = particles.fade[particle_index];
+            let alpha = if self.fade_out.alpha { fade } else { 1.0 };
+            let size = if self.fade_out.size { fade } else { 1.0 };
+            let mut color = particles.color[particle_index];
+            if let Some(gradient) = self.gradients.get(&particles.emitter[particle_index]) {
+                color = colormap::multiply(color, gradient.sample(particles.age_fraction(particle_index)));
+            }
+            if let Some(&Some(tint)) = tints.get(particles.group[particle_index] as usize) {
+                color = colormap::multiply(color, tint);
+            }
 
-            match self.mode {
-                DrawMode::Circle {radius} => self.draw_circle(frame, x as i16, y as i16, radius, lifetime),
-                DrawMode::Point =>  self.draw_point_fast(frame, x, y),
+            match sprite {
+                Sprite::Circle => {
+                    let radius = (particles.radius[particle_index] * size).round() as i16;
+                    self.draw_circle(frame, x as i16, y as i16, radius, colormap::shade(color, alpha))
+                }
+                Sprite::Point =>  self.draw_point_fast(frame, x, y, colormap::shade(color, alpha)),
+                Sprite::Splat { radius } => self.draw_splat(frame, x, y, radius, colormap::shade(color, alpha)),
             }
 
             // Update bounds for dirty region
@@ -213,48 +2480,421 @@ impl Renderer {
         
         // Store dirty region
         self.dirty_rect = Some((min_x, min_y, max_x, max_y));
+    }
+    /// Apply the shared post-processing to a frame of this renderer's size drawn elsewhere,
+    /// e.g. read back by `GpuSimulation::read_frame`. Without the particles at hand the
+    /// heat glow is left out.
+    pub fn post_process_frame(&mut self, frame: &mut [u8]) {
+        self.dirty_rect = Some((0, 0, self.width, self.height));
+        let chain = self.post_process;
+        self.apply_post_chain(frame, chain.as_slice(), None);
+    }
+    fn apply_post_chain(&mut self, frame: &mut [u8], chain: &[PostProcess], energy: Option<&ImpactMap>) {
+        for &pass in chain {
+            match pass {
+                PostProcess::BoxBlur => self.fast_blur_alpha_only(frame),
+                PostProcess::Dilate => self.dilate(frame, energy),
+                PostProcess::Bloom { threshold, radius } => self.bloom(frame, threshold, radius),
+            }
+        }
+    }
+    /// Render particles of `emitter` into a separate layer with its own post-processing
+    /// `chain`, composited over the others, instead of the shared post-processing.
+    pub fn route_emitter(&mut self, emitter: EmitterId, chain: Vec<PostProcess>) {
+        self.routes.insert(emitter, chain);
+    }
+    pub fn clear_routes(&mut self) {
+        self.routes.clear();
+    }
+    /// Multiply the color of `emitter`'s particles by `gradient` over their lifetime.
+    pub fn set_color_gradient(&mut self, emitter: EmitterId, gradient: ColorOverLifetime) {
+        self.gradients.insert(emitter, gradient);
+    }
+    pub fn clear_color_gradients(&mut self) {
+        self.gradients.clear();
+    }
+    pub fn set_mode(&mut self, mode: DrawMode) {
+        self.mode = mode;
+    }
+    /// Switch to the next draw mode: point, circle, splat, then heatmap with each density scaling.
+    pub fn cycle_mode(&mut self) {
+        let heatmap = |scaling| DrawMode::Heatmap { cell: 8, scaling };
+        self.mode = match self.mode {
+            DrawMode::Point => DrawMode::Circle,
+            DrawMode::Circle => DrawMode::Splat { radius: 4 },
+            DrawMode::Splat { .. } => heatmap(DensityScaling::Linear),
+            DrawMode::Heatmap { scaling: DensityScaling::Linear, .. } => heatmap(DensityScaling::Log),
+            DrawMode::Heatmap { scaling: DensityScaling::Log, .. } => heatmap(DensityScaling::Equalized),
+            DrawMode::Heatmap { .. } => DrawMode::Point,
+        };
+    }
+    /// Positions drawn as faint ghost particles behind the simulation; empty to disable.
+    pub fn set_ghost(&mut self, positions: &[Vec2]) {
+        self.ghost.clear();
+        self.ghost.extend_from_slice(positions);
+    }
+    pub fn set_fade_out(&mut self, fade_out: FadeOut) {
+        self.fade_out = fade_out;
+    }
+    /// Colormap for heatmaps, impacts and glow, and the contrast of the overlays.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+    /// Tint the dilation glow by recent impact energy.
+    pub fn set_heat_glow(&mut self, enabled: bool) {
+        self.heat_glow = enabled;
+    }
+    /// Leave heatmap colormapping to the GPU: the heatmap mode only fills the grid
+    /// returned by `heat_grid`.
+    pub fn set_gpu_heatmap(&mut self, enabled: bool) {
+        self.gpu_heatmap = enabled;
+    }
+    /// Heap bytes of the frame-sized and density buffers.
+    pub fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.temp_buffer)
+            + vec_bytes(&self.blur_buffer)
+            + vec_bytes(&self.view_buffer)
+            + vec_bytes(&self.ghost)
+            + vec_bytes(&self.layer_buffer)
+            + vec_bytes(&self.bloom_buffer)
+            + vec_bytes(&self.bloom_temp)
+            + vec_bytes(&self.density)
+            + vec_bytes(&self.density_cdf)
+            + vec_bytes(&self.heat_values)
+            + vec_bytes(&self.frame)
+    }
+    /// The density grid of the last heatmap frame, if drawn in GPU heatmap mode.
+    pub fn heat_grid(&self) -> Option<HeatGrid<'_>> {
+        let DrawMode::Heatmap { cell, .. } = self.mode else {
+            return None;
+        };
+        let cell = cell.max(1);
+        let (cols, rows) = (self.width.div_ceil(cell), self.height.div_ceil(cell));
+        (self.gpu_heatmap && self.heat_values.len() == cols * rows).then_some(HeatGrid {
+            values: &self.heat_values,
+            cols,
+            rows,
+            cell,
+            width: self.width,
+            height: self.height,
+        })
+    }
+    /// Show or hide the accumulated impact counts on top of the particles.
+    pub fn toggle_impacts(&mut self) {
+        self.show_impacts = !self.show_impacts;
+    }
+    /// Blend impact marks into the background; zero alpha keeps them out of dilation.
+    fn draw_decals(&self, frame: &mut [u8], decals: &DecalLayer) {
+        let color = decals.config.color;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let coverage = decals.coverage_at(x, y);
+                if coverage > 0.0 {
+                    let idx = (y * self.width + x) * 4;
+                    for c in 0..3 {
+                        frame[idx + c] = (color[c] as f32 * coverage) as u8;
+                    }
+                }
+            }
+        }
+    }
+    /// Live cells of the automaton as dim squares behind the particles.
+    fn draw_life(&self, frame: &mut [u8], life: &LifeLayer) {
+        let (cols, rows) = life.size();
+        let cell = life.config.cell;
+        let color = life.config.color;
+        for row in 0..rows {
+            for col in 0..cols {
+                if !life.is_alive(col, row) {
+                    continue;
+                }
+                let y0 = (row as f32 * cell) as usize;
+                let x0 = (col as f32 * cell) as usize;
+                let y1 = (((row + 1) as f32 * cell) as usize).min(self.height);
+                let x1 = (((col + 1) as f32 * cell) as usize).min(self.width);
+                for y in y0..y1 {
+                    for px in frame[(y * self.width + x0) * 4..(y * self.width + x1) * 4].chunks_exact_mut(4) {
+                        px[..3].copy_from_slice(&color);
+                    }
+                }
+            }
+        }
+    }
+    /// Frozen points of the aggregate, colored by the order they joined in so growth
+    /// fronts show as bands.
+    fn draw_aggregate(&mut self, frame: &mut [u8], aggregate: &Aggregate) {
+        let count = aggregate.points().len().max(2) - 1;
+        for (k, (p, &radius)) in aggregate.points().iter().zip(aggregate.radii()).enumerate() {
+            let color = self.theme.palette.sample(0.25 + 0.75 * k as f32 / count as f32);
+            self.draw_circle(frame, p.x as i16, p.y as i16, radius.round() as i16, color);
+        }
+    }
+    /// Water columns along the bottom edge, with a brighter line at the surface.
+    fn draw_water(&self, frame: &mut [u8], wetness: &Wetness) {
+        let color = wetness.config.color;
+        let surface = color.map(|c| c.saturating_mul(2));
+        for (x, &level) in wetness.levels().iter().enumerate().take(self.width) {
+            if level <= 0.0 {
+                continue;
+            }
+            let top = self.height as f32 - level.min(self.height as f32);
+            for y in top as usize..self.height {
+                // partly covered top pixel
+                let coverage = (y as f32 + 1.0 - top).min(1.0);
+                let shade = if y == top as usize { surface } else { color };
+                let idx = (y * self.width + x) * 4;
+                for c in 0..3 {
+                    let blended = frame[idx + c] as f32 * (1.0 - coverage) + shade[c] as f32 * coverage;
+                    frame[idx + c] = blended as u8;
+                }
+            }
+        }
+    }
+    /// Show or hide the streamlines of the imported flow field and the fluid.
+    pub fn toggle_streamlines(&mut self) {
+        self.show_streamlines = !self.show_streamlines;
+    }
+    /// Short streamlines of the velocity field `sample` from seeds on a regular grid, each
+    /// ending in an arrowhead pointing downstream.
+    fn draw_streamlines(&self, frame: &mut [u8], sample: impl Fn(Vec2) -> Vec2, color: [u8; 4]) {
+        let direction = |p: Vec2| sample(p).normalize_or_zero();
+        let mut seed = Vec2::splat(STREAMLINE_SPACING * 0.5);
+        while seed.y < self.height as f32 {
+            while seed.x < self.width as f32 {
+                let mut p = seed;
+                let mut heading = Vec2::ZERO;
+                for _ in 0..STREAMLINE_STEPS {
+                    // midpoint step along the normalized field
+                    let mid = p + direction(p) * STREAMLINE_STEP * 0.5;
+                    let step = direction(mid) * STREAMLINE_STEP;
+                    if step == Vec2::ZERO {
+                        break;
+                    }
+                    self.draw_line(frame, p, p + step, color);
+                    p += step;
+                    heading = step / STREAMLINE_STEP;
+                }
+                if heading != Vec2::ZERO {
+                    let back = -heading * STREAMLINE_STEP * 2.0;
+                    self.draw_line(frame, p, p + back + heading.perp() * STREAMLINE_STEP, color);
+                    self.draw_line(frame, p, p + back - heading.perp() * STREAMLINE_STEP, color);
+                }
+                seed.x += STREAMLINE_SPACING;
+            }
+            seed = Vec2::new(STREAMLINE_SPACING * 0.5, seed.y + STREAMLINE_SPACING);
+        }
+    }
+    /// Blend the impact counts over the frame, log-scaled and colormapped.
+    fn draw_impacts(&self, frame: &mut [u8], impacts: &ImpactMap) {
+        if impacts.max() == 0 {
+            return;
+        }
+        let norm = 1.0 / (impacts.max() as f32).ln_1p();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let count = impacts.count_at(x, y);
+                if count == 0 {
+                    continue;
+                }
+                let color = self.theme.palette.sample((count as f32).ln_1p() * norm);
+                let idx = (y * self.width + x) * 4;
+                for c in 0..3 {
+                    frame[idx + c] = ((frame[idx + c] as u16 + color[c] as u16) / 2) as u8;
+                }
+                frame[idx + 3] = 0xFF;
+            }
+        }
+    }
+    /// Obstacle being drawn in edit mode, shown highlighted; `None` to hide.
+    pub fn set_preview(&mut self, shape: Option<Shape>) {
+        self.preview = shape;
+    }
+    /// Fill `shape` with `color`; zero alpha keeps it out of dilation.
+    fn fill_shape(&self, frame: &mut [u8], shape: &Shape, color: [u8; 4]) {
+        if let Shape::Segment { start, end } = *shape {
+            self.draw_line(frame, start, end, color);
+            return;
+        }
+        let (min, max) = shape.bounds();
+        let x0 = min.x.max(0.0) as usize;
+        let y0 = min.y.max(0.0) as usize;
+        let x1 = (max.x.max(0.0) as usize).min(self.width.saturating_sub(1));
+        let y1 = (max.y.max(0.0) as usize).min(self.height.saturating_sub(1));
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if shape.contains(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+                    let idx = (y * self.width + x) * 4;
+                    frame[idx..idx + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+    fn draw_line(&self, frame: &mut [u8], from: Vec2, to: Vec2, color: [u8; 4]) {
+        let steps = (to - from).abs().max_element().ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let p = from.lerp(to, step as f32 / steps as f32);
+            let (x, y) = (p.x as usize, p.y as usize);
+            if p.x >= 0.0 && p.y >= 0.0 && x < self.width && y < self.height {
+                let idx = (y * self.width + x) * 4;
+                frame[idx..idx + 4].copy_from_slice(&color);
+            }
+        }
+    }
+    fn fill_walls(&self, frame: &mut [u8], walls: &SdfGrid, color: [u8; 4]) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if walls.is_solid(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+                    let idx = (y * self.width + x) * 4;
+                    frame[idx..idx + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+    /// Draw into the renderer's own frame, for integrations without a window such as
+    /// video encoders, and borrow the result.
+    pub fn render(&mut self, particles: &ParticleSystem) -> FrameView<'_> {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.resize(self.width * self.height * 4, 0);
+        self.draw(&mut frame, particles);
+        self.frame = frame;
+        self.frame_view()
+    }
+    /// The frame last drawn by `render`, without copying; empty before the first one.
+    pub fn frame_view(&self) -> FrameView<'_> {
+        let height = if self.frame.is_empty() { 0 } else { self.height };
+        FrameView { pixels: &self.frame, width: self.width, height, stride: self.width * 4 }
+    }
+    /// Draw into a column of a wider frame starting at `x_offset`, for split views.
+    pub fn draw_into(&mut self, frame: &mut [u8], frame_width: usize, x_offset: usize, particles: &ParticleSystem) {
+        let mut view = std::mem::take(&mut self.view_buffer);
+        view.resize(self.width * self.height * 4, 0);
+        self.draw(&mut view, particles);
 
-        // Apply post-processing
-        match self.post_process {
-            Some(PostProcess::BoxBlur) => self.fast_blur_alpha_only(frame),
-            Some(PostProcess::Dilate) => self.dilation(frame),
-            _ => {},
+        let row = self.width * 4;
+        for y in 0..self.height {
+            let dst = (y * frame_width + x_offset) * 4;
+            frame[dst..dst + row].copy_from_slice(&view[y * row..(y + 1) * row]);
         }
+        self.view_buffer = view;
     }
-    fn draw_circle(&self, frame: &mut [u8], center_x: i16, center_y: i16, radius: i16, lifetime: f32) {
-        let radius_squared = radius * radius;
-        let min_x = (center_x - radius).max(0);
-        let max_x = (center_x + radius).min(self.width as i16 - 1);
-        let min_y = (center_y - radius).max(0);
-        let max_y = (center_y + radius).min(self.height as i16 - 1);
+    fn draw_heatmap(&mut self, frame: &mut [u8], particles: &ParticleSystem, cell: usize, scaling: DensityScaling) {
+        let cell = cell.max(1);
+        let cols = self.width.div_ceil(cell);
+        let rows = self.height.div_ceil(cell);
+        self.density.clear();
+        self.density.resize(cols * rows, 0);
+        if self.gpu_heatmap {
+            self.heat_values.clear();
+            self.heat_values.resize(cols * rows, 0.0);
+        }
+
+        for i in 0..particles.count {
+            let p = particles.render_position(i);
+            if p.x < 0.0 || p.y < 0.0 {
+                continue;
+            }
+            let (cx, cy) = (p.x as usize / cell, p.y as usize / cell);
+            if cx < cols && cy < rows {
+                self.density[cy * cols + cx] += 1;
+            }
+        }
+        let max = self.density.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return;
+        }
+
+        if scaling == DensityScaling::Equalized {
+            // fraction of occupied cells with density <= d
+            self.density_cdf.clear();
+            self.density_cdf.resize(max as usize + 1, 0.0);
+            for &d in self.density.iter().filter(|&&d| d > 0) {
+                self.density_cdf[d as usize] += 1.0;
+            }
+            let occupied: f32 = self.density_cdf.iter().sum();
+            let mut running = 0.0;
+            for bin in &mut self.density_cdf {
+                running += *bin;
+                *bin = running / occupied;
+            }
+        }
 
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                let dx = x - center_x;
-                let dy = y - center_y;
-                if dx * dx + dy * dy <= radius_squared {
-                    let index = (y as usize * self.width + x as usize) * 4;
-                    let alpha = (lifetime * 255.0) as u8;
-                    frame[index] = 0xFF;     // R
-                    frame[index + 1] = 0xFF; // G
-                    frame[index + 2] = 0xFF; // B
-                    frame[index + 3] = alpha; // A
+        let log_max = (1.0 + max as f32).ln();
+        for cy in 0..rows {
+            for cx in 0..cols {
+                let d = self.density[cy * cols + cx];
+                if d == 0 {
+                    continue;
                 }
+                let t = match scaling {
+                    DensityScaling::Linear => d as f32 / max as f32,
+                    DensityScaling::Log => (1.0 + d as f32).ln() / log_max,
+                    DensityScaling::Equalized => self.density_cdf[d as usize],
+                };
+                if self.gpu_heatmap {
+                    self.heat_values[cy * cols + cx] = t;
+                    continue;
+                }
+                let color = self.theme.palette.sample(t);
+                for y in cy * cell..((cy + 1) * cell).min(self.height) {
+                    for x in cx * cell..((cx + 1) * cell).min(self.width) {
+                        let idx = (y * self.width + x) * 4;
+                        frame[idx..idx + 4].copy_from_slice(&color);
+                    }
+                }
+            }
+        }
+    }
+    /// Additively accumulate a Gaussian splat centered on (`center_x`, `center_y`).
+    fn draw_splat(&mut self, frame: &mut [u8], center_x: usize, center_y: usize, radius: usize, color: [u8; 4]) {
+        let (width, height) = (self.width, self.height);
+        let footprint = self.footprints.splat(radius);
+        for_each_pixel(footprint, center_x as i32, center_y as i32, width, height, |idx, coverage| {
+            for (channel, &c) in frame[idx..idx + 4].iter_mut().zip(&color) {
+                let weight = (coverage as f32 * c as f32 / 255.0) as u8;
+                *channel = channel.saturating_add(weight);
+            }
+        });
+    }
+    /// Fill the disc one scanline span at a time.
+    fn draw_circle(&mut self, frame: &mut [u8], center_x: i16, center_y: i16, radius: i16, pixel: [u8; 4]) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let (cx, cy) = (center_x as i32, center_y as i32);
+        let spans = self.footprints.circle_spans(radius.max(0) as usize);
+        let r = spans.len() as i32 / 2;
+
+        for (dy, &half_width) in (-r..=r).zip(spans) {
+            let y = cy + dy;
+            let x0 = (cx - half_width).max(0);
+            let x1 = (cx + half_width).min(width - 1);
+            if y < 0 || y >= height || x0 > x1 {
+                continue;
+            }
+            let start = ((y * width + x0) * 4) as usize;
+            let end = ((y * width + x1 + 1) * 4) as usize;
+            for px in frame[start..end].chunks_exact_mut(4) {
+                px.copy_from_slice(&pixel);
             }
         }
-    } 
-    fn draw_point_fast(&self, frame: &mut [u8], x: usize, y: usize) {
+    }
+    fn draw_point_fast(&self, frame: &mut [u8], x: usize, y: usize, pixel: [u8; 4]) {
         if x < self.width && y < self.height {
             let idx = (y * self.width + x) * 4;
-            frame[idx..idx + 4].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+            frame[idx..idx + 4].copy_from_slice(&pixel);
         }
     }
     pub fn dilation(&mut self, frame: &mut [u8]) {
+        self.dilate(frame, None);
+    }
+    /// Dilate, tinting the dilated pixels by recent impact energy when `energy` is given.
+    fn dilate(&mut self, frame: &mut [u8], energy: Option<&ImpactMap>) {
         let Some((min_x, min_y, max_x, max_y)) = self.dirty_rect else {
             return;
         };
-        let width = self.width as usize;
-        let height = self.height as usize;
+        let width = self.width;
+        let height = self.height;
         // Copy only dirty region to temp buffer
         self.temp_buffer.copy_from_slice(frame);
         let src = &self.temp_buffer;
@@ -284,14 +2924,61 @@ impl Renderer {
                 if has_neighbor {
                     frame[idx..idx + 3].copy_from_slice(&[0xCC, 0xCC, 0xCC]);
                     frame[idx + 3] = 0xCC; // Slightly transparent dilated pixels
+                    // violent collisions flare from gray to hot colors
+                    let heat = energy.map_or(0.0, |map| (map.energy_at(x, y) / GLOW_ENERGY).min(1.0));
+                    if heat > 0.0 {
+                        let hot = self.theme.palette.sample(0.6 + 0.4 * heat);
+                        for c in 0..3 {
+                            frame[idx + c] = (0xCC as f32 + (hot[c] as f32 - 0xCC as f32) * heat) as u8;
+                        }
+                        frame[idx + 3] = (0xCC as f32 + 0x33 as f32 * heat) as u8;
+                    }
                 }
             }
         }
     }
+    /// Add a blurred copy of the bright pixels. The frame holds sRGB-encoded values, so
+    /// thresholding and blurring happen on decoded linear values and the sum is re-encoded.
+    fn bloom(&mut self, frame: &mut [u8], threshold: f32, radius: usize) {
+        let (w, h) = (self.width, self.height);
+        let mut bright = std::mem::take(&mut self.bloom_buffer);
+        let mut temp = std::mem::take(&mut self.bloom_temp);
+        bright.clear();
+        bright.extend(frame.chunks_exact(4).map(|px| {
+            let linear = Vec3::new(srgb_to_linear(px[0]), srgb_to_linear(px[1]), srgb_to_linear(px[2]));
+            let luminance = linear.dot(Vec3::new(0.2126, 0.7152, 0.0722));
+            if luminance > threshold {
+                linear * ((luminance - threshold) / luminance)
+            } else {
+                Vec3::ZERO
+            }
+        }));
+        temp.resize(bright.len(), Vec3::ZERO);
+
+        // separable box blur: rows into temp, then columns back into bright
+        for y in 0..h {
+            box_blur_line(&bright, &mut temp, y * w, 1, w, radius);
+        }
+        for x in 0..w {
+            box_blur_line(&temp, &mut bright, x, w, h, radius);
+        }
+
+        for (px, glow) in frame.chunks_exact_mut(4).zip(&bright) {
+            if *glow == Vec3::ZERO {
+                continue;
+            }
+            for c in 0..3 {
+                px[c] = linear_to_srgb(srgb_to_linear(px[c]) + glow[c]);
+            }
+            px[3] = px[3].max(px[0]).max(px[1]).max(px[2]);
+        }
+        self.bloom_buffer = bright;
+        self.bloom_temp = temp;
+    }
     // Single-pass accumulation blur (much faster)
     fn fast_blur_alpha_only(&mut self, frame: &mut [u8]) {
-        let w = self.width as usize;
-        let h = self.height as usize;
+        let w = self.width;
+        let h = self.height;
         
         self.blur_buffer.copy_from_slice(frame);
         
@@ -316,4 +3003,51 @@ impl Renderer {
         }
     }
 
-}
\ No newline at end of file
+}
+
+/// Call `f(frame_index, coverage)` for every pixel of `footprint` centered at (`cx`, `cy`)
+/// that falls inside a `width` x `height` frame.
+fn for_each_pixel(footprint: &Footprint, cx: i32, cy: i32, width: usize, height: usize, mut f: impl FnMut(usize, u8)) {
+    for (&(dx, dy), &coverage) in footprint.offsets.iter().zip(&footprint.coverage) {
+        let (x, y) = (cx + dx, cy + dy);
+        if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+            f((y as usize * width + x as usize) * 4, coverage);
+        }
+    }
+}
+
+/// Composite `layer` over `frame`, keeping the brighter value per channel where the layer is drawn.
+fn composite_max(frame: &mut [u8], layer: &[u8]) {
+    for (dst, src) in frame.chunks_exact_mut(4).zip(layer.chunks_exact(4)) {
+        if src[3] > 0 {
+            for c in 0..4 {
+                dst[c] = dst[c].max(src[c]);
+            }
+        }
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    static TABLE: std::sync::OnceLock<[f32; 256]> = std::sync::OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let v = i as f32 / 255.0;
+            if v <= 0.04045 {
+                v / 12.92
+            } else {
+                ((v + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    });
+    table[value as usize]
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
@@ -0,0 +1,74 @@
+use glam::Vec2;
+use riscwaves::backend::{self, BackendKind};
+use riscwaves::world::{Collisions, SimParams, INFINITE_LIFETIME};
+use riscwaves::Batch;
+
+// without a GPU the GPU backend falls back to the CPU, so these hold either way
+#[test]
+fn backends_agree_on_free_fall() {
+    for kind in [BackendKind::Cpu, BackendKind::Gpu] {
+        let mut particles = backend::create(kind, 2, 200, 200);
+        particles.set_params(SimParams { global_drag: Vec2::ZERO, ..SimParams::default() });
+        particles.spawn([100.0, 50.0], [1.0, 0.0], 1.0, INFINITE_LIFETIME);
+        particles.spawn([50.0, 50.0], [0.0, 0.0], 2.0, INFINITE_LIFETIME);
+        particles.spawn([150.0, 50.0], [0.0, 0.0], 1.0, INFINITE_LIFETIME);
+        assert_eq!(particles.live_count(), 2, "{kind:?} spawned past its capacity");
+        particles.step();
+        particles.step();
+        // semi-implicit Euler: velocity 0.5 then 1.0 down, so 1.5 pixels fallen
+        let positions = particles.read_positions().to_vec();
+        assert!(positions[0].distance(Vec2::new(102.0, 51.5)) < 1e-4, "{kind:?}: {positions:?}");
+        assert!(positions[1].distance(Vec2::new(50.0, 51.5)) < 1e-4, "{kind:?}: {positions:?}");
+        assert_eq!(particles.read_masses(), [1.0, 2.0]);
+        assert_eq!(particles.time(), 2.0);
+    }
+}
+
+#[test]
+fn backends_separate_colliding_particles() {
+    let still = SimParams {
+        gravity: Vec2::ZERO,
+        global_drag: Vec2::ZERO,
+        collisions: Some(Collisions { restitution: 0.0 }),
+        ..SimParams::default()
+    };
+    for kind in [BackendKind::Cpu, BackendKind::Gpu] {
+        let mut particles = backend::create(kind, 3, 100, 100);
+        particles.set_params(still.clone());
+        // unit masses have radius 4, so these overlap by 3 pixels
+        particles.spawn([50.0, 50.0], [0.0, 0.0], 1.0, INFINITE_LIFETIME);
+        particles.spawn([55.0, 50.0], [0.0, 0.0], 1.0, INFINITE_LIFETIME);
+        particles.spawn([20.0, 80.0], [0.0, 0.0], 1.0, INFINITE_LIFETIME);
+        particles.step();
+        let positions = particles.read_positions().to_vec();
+        assert!(positions[0].distance(positions[1]) >= 8.0 - 1e-4, "{kind:?}: {positions:?}");
+        assert_eq!(positions[2], Vec2::new(20.0, 80.0), "{kind:?}");
+
+        let mut found = particles.read_neighbors(Vec2::new(52.5, 50.0), 5.0);
+        found.sort_unstable();
+        assert_eq!(found, [0, 1], "{kind:?}");
+        assert!(particles.read_neighbors(Vec2::new(52.5, 50.0), -5.0).is_empty(), "{kind:?}");
+    }
+}
+
+#[test]
+fn batch_stats_run_on_the_selected_backend() {
+    let batch = Batch { steps: 20, particles: 50, seed: Some(3), ..Batch::default() };
+    let cpu = batch.run_stats();
+    assert_eq!(cpu.live, batch.run().1.live);
+    let gpu = Batch { backend: BackendKind::Gpu, ..batch }.run_stats();
+    assert_eq!((gpu.live, gpu.steps, gpu.seed, gpu.time), (cpu.live, cpu.steps, cpu.seed, cpu.time));
+}
+
+#[test]
+fn backends_render_post_processed_frames() {
+    for kind in [BackendKind::Cpu, BackendKind::Gpu] {
+        let mut particles = backend::create(kind, 1, 64, 32);
+        particles.spawn([20.0, 10.0], [0.0, 0.0], 1.0, INFINITE_LIFETIME);
+        let frame = particles.render_frame();
+        assert_eq!(frame.len(), 64 * 32 * 4, "{kind:?}");
+        let lit = |x: usize, y: usize| frame[(y * 64 + x) * 4 + 3] > 0;
+        assert!(lit(20, 10), "{kind:?}");
+        assert!(!lit(50, 25), "{kind:?}");
+    }
+}
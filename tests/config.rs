@@ -0,0 +1,59 @@
+use glam::Vec2;
+use riscwaves::{Action, ActionEvent, Binding, Config, GamepadButton};
+
+#[test]
+fn rejects_non_positive_life_period() {
+    assert!(Config::from_json(r#"{ "life": { "period": 0 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "life": { "period": -1 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "life": { "period": 5 } }"#).is_ok());
+}
+
+#[test]
+fn rejects_non_positive_density() {
+    assert!(Config::from_json(r#"{ "compare": { "density": 0 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "compare": { "density": -0.5 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "compare": { "density": 0.02 } }"#).is_ok());
+}
+
+#[test]
+fn clamps_substeps() {
+    let config = Config::from_json(r#"{ "compare": { "substeps": 4000000000 } }"#).unwrap();
+    assert_eq!(config.compare.unwrap().substeps, riscwaves::world::MAX_SUBSTEPS);
+    let config = Config::from_json(r#"{ "compare": { "substeps": 0 } }"#).unwrap();
+    assert_eq!(config.compare.unwrap().substeps, 1);
+}
+
+#[test]
+fn rejects_bloom_threshold_outside_unit_interval() {
+    let route = |threshold: &str| format!(r#"{{ "post_routes": {{ "1": [{{ "Bloom": {{ "threshold": {threshold}, "radius": 2 }} }}] }} }}"#);
+    assert!(Config::from_json(&route("-0.1")).is_err());
+    assert!(Config::from_json(&route("1.5")).is_err());
+    assert!(Config::from_json(&route("0.6")).is_ok());
+}
+
+#[test]
+fn rejects_non_positive_decal_speed() {
+    assert!(Config::from_json(r#"{ "decals": { "min_speed": 0 } }"#).is_err());
+    assert!(Config::from_json(r#"{ "decals": { "min_speed": 2 } }"#).is_ok());
+}
+
+#[test]
+fn gamepad_bindings_raise_the_same_actions() {
+    let config = Config::from_json(r#"{ "input": { "Pause": [{ "Gamepad": "South" }], "Wind": ["GamepadStick"] } }"#).unwrap();
+    let mut events = Vec::new();
+    config.input.gamepad_events(GamepadButton::South, true, &mut events);
+    config.input.gamepad_events(GamepadButton::South, false, &mut events);
+    // unlisted actions keep their default gamepad bindings
+    config.input.gamepad_events(GamepadButton::North, true, &mut events);
+    config.input.pointer_events(Binding::GamepadStick, Vec2::new(1.0, 2.0), &mut events);
+    assert_eq!(
+        events,
+        [
+            ActionEvent::Pressed(Action::Pause),
+            ActionEvent::Released(Action::Pause),
+            ActionEvent::Pressed(Action::CycleDrawMode),
+            ActionEvent::Pointer(Action::Attract, Vec2::new(1.0, 2.0)),
+            ActionEvent::Pointer(Action::Wind, Vec2::new(1.0, 2.0)),
+        ]
+    );
+}
@@ -0,0 +1,16 @@
+use riscwaves::digest::TrajectoryDigest;
+use riscwaves::Batch;
+
+/// Trajectory of the `digest` example's default run, recorded with
+/// `cargo run --example digest -- --baseline tests/digest_baseline.json --write`.
+/// Re-record it only for intended physics changes.
+const BASELINE: &str = include_str!("digest_baseline.json");
+
+#[test]
+fn matches_baseline() {
+    let baseline: TrajectoryDigest = serde_json::from_str(BASELINE).expect("valid baseline");
+    let batch = Batch { steps: 2000, particles: 2000, seed: Some(1), ..Batch::default() };
+    let mut digest = TrajectoryDigest::new(baseline.every);
+    batch.run_with(|particles| digest.record(particles));
+    assert_eq!(digest.first_divergence(&baseline), None, "trajectory diverges from tests/digest_baseline.json");
+}
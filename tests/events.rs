@@ -0,0 +1,26 @@
+use riscwaves::rules::ParticleEvent;
+use riscwaves::ParticleSystem;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn listeners_get_events_in_recording_order() {
+    let mut particles = ParticleSystem::new(4, 100, 100);
+    particles.record_lifecycle(true);
+    particles.simulation.fade_duration = 0.5;
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    particles.add_event_listener(move |event| {
+        let name = match event {
+            ParticleEvent::Spawned { .. } => "spawned",
+            ParticleEvent::Expired { .. } => "expired",
+            ParticleEvent::Died { .. } => "died",
+            _ => "other",
+        };
+        log.lock().unwrap().push(name);
+    });
+    // spawned between updates, expires and shrinks out during the next one
+    particles.spawn_owned(1, [50.0, 50.0], [0.0, 0.0], 1.0, 0.5);
+    particles.pause_emitters(true);
+    particles.update();
+    assert_eq!(*seen.lock().unwrap(), ["spawned", "expired", "died"]);
+}
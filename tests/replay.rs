@@ -0,0 +1,24 @@
+use riscwaves::Replay;
+
+#[test]
+fn rejects_frames_longer_than_the_data() {
+    // one frame claiming u32::MAX positions, followed by a single one
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(&1.0f32.to_le_bytes());
+    bytes.extend_from_slice(&2.0f32.to_le_bytes());
+    assert!(Replay::from_bytes(&bytes, 10).is_none());
+}
+
+#[test]
+fn reads_what_it_writes() {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1.0f32.to_le_bytes());
+    bytes.extend_from_slice(&2.0f32.to_le_bytes());
+    let replay = Replay::from_bytes(&bytes, 10).expect("valid replay");
+    assert_eq!(replay.len(), 1);
+    assert_eq!(replay.to_bytes(), bytes);
+}
@@ -0,0 +1,37 @@
+use glam::Vec2;
+use riscwaves::distribution::Distribution;
+use riscwaves::rules::SpawnRule;
+use riscwaves::world::INFINITE_LIFETIME;
+use riscwaves::Scene;
+
+#[test]
+fn loads_the_single_attractor_of_old_scenes() {
+    let old = r#"{ "simulation": {}, "attractor": { "position": [10.0, 20.0], "strength": 6.0, "radius": 50 } }"#;
+    let scene = Scene::from_json(old).expect("old scene");
+    assert_eq!(scene.attractors.len(), 1);
+    assert_eq!(scene.attractors[0].position, Vec2::new(10.0, 20.0));
+
+    let without = Scene::from_json(r#"{ "simulation": {}, "attractor": null }"#).expect("old scene");
+    assert!(without.attractors.is_empty());
+}
+
+#[test]
+fn round_trips_attractors() {
+    let json = r#"{ "simulation": {}, "attractors": [
+        { "position": [1.0, 2.0], "strength": 1.0, "radius": 10 },
+        { "position": [3.0, 4.0], "strength": 2.0, "radius": 20 }
+    ] }"#;
+    let scene = Scene::from_json(json).expect("scene");
+    let again = Scene::from_json(&scene.to_json()).expect("round trip");
+    assert_eq!(again.attractors.len(), 2);
+    assert_eq!(again.attractors[1].position, Vec2::new(3.0, 4.0));
+}
+
+#[test]
+fn round_trips_spawn_rules_that_never_expire() {
+    let mut scene = Scene::from_json(r#"{ "simulation": {} }"#).expect("scene");
+    scene.spawn_rules.push(SpawnRule { lifetime: Distribution::Constant(INFINITE_LIFETIME), ..SpawnRule::default() });
+    scene.spawn_rules.push(SpawnRule::default());
+    let again = Scene::from_json(&scene.to_json()).expect("round trip");
+    assert_eq!(again.spawn_rules, scene.spawn_rules);
+}
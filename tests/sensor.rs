@@ -0,0 +1,92 @@
+use proptest::prelude::*;
+use riscwaves::sensor::{encode, Packet, PacketDecoder, MAX_FRAME, SYNC};
+
+fn packet() -> impl Strategy<Value = Packet> {
+    prop_oneof![
+        // finite axes, NaN payloads are not equal to themselves
+        prop::array::uniform3(-1e6f32..1e6).prop_map(Packet::Accel),
+        any::<u64>().prop_map(Packet::Time),
+    ]
+}
+
+/// Any byte but the first of `SYNC`, so it cannot start a frame.
+fn not_sync() -> impl Strategy<Value = u8> {
+    any::<u8>().prop_filter("starts a frame", |&b| b != SYNC[0])
+}
+
+fn encode_all(packets: &[Packet]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    packets.iter().for_each(|packet| encode(packet, &mut bytes));
+    bytes
+}
+
+fn decode_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> (Vec<Packet>, PacketDecoder) {
+    let mut decoder = PacketDecoder::new();
+    let mut packets = Vec::new();
+    for chunk in chunks {
+        decoder.push(chunk, &mut packets);
+        assert!(decoder.buffered() <= MAX_FRAME);
+    }
+    (packets, decoder)
+}
+
+proptest! {
+    #[test]
+    fn round_trip(packets in prop::collection::vec(packet(), 0..32)) {
+        let (decoded, decoder) = decode_chunks([encode_all(&packets).as_slice()]);
+        prop_assert_eq!(decoded, packets);
+        prop_assert_eq!(decoder.dropped, 0);
+        prop_assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn split_stream(packets in prop::collection::vec(packet(), 0..32), sizes in prop::collection::vec(1usize..16, 1..64)) {
+        let bytes = encode_all(&packets);
+        let mut chunks = Vec::new();
+        let mut rest = bytes.as_slice();
+        for size in sizes.iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            chunks.push(chunk);
+            rest = tail;
+        }
+        let (decoded, _) = decode_chunks(chunks);
+        prop_assert_eq!(decoded, packets);
+    }
+
+    #[test]
+    fn garbage_between_frames(packets in prop::collection::vec((packet(), prop::collection::vec(not_sync(), 0..24)), 0..16)) {
+        // each frame after some garbage without frame starts still decodes
+        let mut bytes = Vec::new();
+        for (packet, garbage) in &packets {
+            bytes.extend_from_slice(garbage);
+            encode(packet, &mut bytes);
+        }
+        let (decoded, _) = decode_chunks([bytes.as_slice()]);
+        let expected: Vec<Packet> = packets.iter().map(|&(packet, _)| packet).collect();
+        prop_assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn arbitrary_input(bytes in prop::collection::vec(any::<u8>(), 0..512), split in any::<prop::sample::Index>()) {
+        // never panics, and chunking does not change the result
+        let (whole, _) = decode_chunks([bytes.as_slice()]);
+        let (head, tail) = bytes.split_at(split.index(bytes.len() + 1));
+        let (chunked, _) = decode_chunks([head, tail]);
+        prop_assert_eq!(encode_all(&whole), encode_all(&chunked));
+    }
+
+    #[test]
+    fn recovers_after_garbage(garbage in prop::collection::vec(any::<u8>(), 0..256), packets in prop::collection::vec(packet(), 1..8)) {
+        // a garbage prefix can swallow at most the frames overlapping an unfinished frame
+        // header in it, later frames come through
+        let mut bytes = garbage;
+        let filler = vec![0u8; MAX_FRAME];
+        bytes.extend_from_slice(&filler);
+        bytes.extend_from_slice(&encode_all(&packets));
+        let (decoded, _) = decode_chunks([bytes.as_slice()]);
+        prop_assert!(decoded.ends_with(&packets));
+    }
+}
@@ -0,0 +1,33 @@
+use glam::Vec2;
+use riscwaves::share;
+use riscwaves::world::{GustShape, Wind};
+use riscwaves::{Config, Scene};
+
+#[test]
+fn round_trips_gusting_wind() {
+    let mut scene = Scene::from_json(r#"{ "simulation": {} }"#).expect("scene");
+    scene.simulation.wind = Wind { base: Vec2::new(0.05, 0.0), gust: 0.1, frequency: 0.005, shape: GustShape::Noise };
+    let mut shared = Scene::from_json(r#"{ "simulation": {} }"#).expect("scene");
+    share::apply_query(&mut shared, &share::to_query(&scene));
+    assert_eq!(shared.simulation.wind, scene.simulation.wind);
+}
+
+#[test]
+fn payloads_round_trip_compressed() {
+    let json = r#"{ "simulation": {}, "obstacles": [] }"#.repeat(20);
+    let encoded = share::encode_payload(&json);
+    assert!(encoded.len() < json.len() / 2);
+    assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+    assert_eq!(share::decode_payload(&encoded).as_deref(), Some(json.as_str()));
+    assert_eq!(share::decode_payload("not*base64"), None);
+}
+
+#[test]
+fn round_trips_presets() {
+    assert_eq!(share::preset_query(&Config::default()), None);
+    let config = Config::from_json(r#"{ "lifetime": 300, "emitters": [{ "rate": 2.0, "id": 1 }] }"#).expect("config");
+    let query = share::preset_query(&config).expect("non-default preset");
+    let shared = share::preset_from_query(&format!("?seed=4&{query}")).expect("shared preset");
+    assert_eq!(shared.lifetime, Some(300.0));
+    assert_eq!(shared.emitters.map(|emitters| emitters[0].id), Some(1));
+}
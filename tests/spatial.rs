@@ -0,0 +1,33 @@
+use glam::Vec2;
+use riscwaves::arena::FrameArena;
+use riscwaves::spatial::SpatialGrid;
+use riscwaves::ParticleSystem;
+
+#[test]
+fn negative_radius_finds_nothing_around() {
+    let positions = [Vec2::new(10.0, 10.0), Vec2::new(50.0, 50.0)];
+    let mut grid = SpatialGrid::new();
+    grid.build(&positions, 8.0, (100, 100), [false; 2], &mut FrameArena::new());
+    assert_eq!(grid.near(Vec2::new(50.0, 50.0), -20.0).collect::<Vec<_>>(), vec![1]);
+    grid.build(&positions, 8.0, (100, 100), [true; 2], &mut FrameArena::new());
+    assert_eq!(grid.near(Vec2::new(50.0, 50.0), -20.0).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(grid.near(Vec2::new(50.0, 50.0), f32::NAN).collect::<Vec<_>>(), vec![1]);
+}
+
+#[test]
+fn neighbors_follow_removals() {
+    let mut particles = ParticleSystem::new(3, 100, 100);
+    particles.spawn_owned(1, [10.0, 10.0], [0.0, 0.0], 1.0, f32::INFINITY);
+    particles.spawn_owned(2, [80.0, 80.0], [0.0, 0.0], 1.0, f32::INFINITY);
+    particles.spawn_owned(2, [81.0, 80.0], [0.0, 0.0], 1.0, f32::INFINITY);
+    particles.simulation.gravity = Vec2::ZERO;
+    particles.update_with_dt(0.0);
+    // the particle at 81, 80 moves into the first slot
+    particles.kill_emitter(1);
+    let mut found: Vec<usize> = particles.neighbors_within(Vec2::new(80.0, 80.0), 3.0).collect();
+    found.sort_unstable();
+    assert_eq!(found, vec![0, 1]);
+    // only a particle right at the point is within a radius of 0
+    assert_eq!(particles.neighbors_within(Vec2::new(80.5, 80.0), -3.0).count(), 0);
+    assert_eq!(particles.neighbors_within(Vec2::new(80.0, 80.0), f32::NAN).count(), 1);
+}
@@ -0,0 +1,22 @@
+use riscwaves::integrator::IntegratorKind;
+use riscwaves::validate::{self, Scenario};
+
+#[test]
+fn skips_invalid_time_steps() {
+    assert!(validate::run(Scenario::Projectile, IntegratorKind::default(), 0.0).is_err());
+    assert!(validate::run(Scenario::Orbit, IntegratorKind::default(), f32::NAN).is_err());
+    let results = validate::run_all(&[0.0, -1.0, f32::INFINITY, 1.0]);
+    assert!(results.iter().all(|result| result.dt == 1.0));
+    assert_eq!(results.len(), Scenario::ALL.len() * IntegratorKind::ALL.len());
+}
+
+#[test]
+fn orbit_tells_the_integrators_apart() {
+    let max_error = |integrator, dt| validate::run(Scenario::Orbit, integrator, dt).unwrap().max_error;
+    let euler = max_error(IntegratorKind::SemiImplicitEuler, 0.5);
+    let verlet = max_error(IntegratorKind::Verlet, 0.5);
+    let rk4 = max_error(IntegratorKind::Rk4, 0.5);
+    assert!(euler > verlet && verlet > rk4, "euler {euler}, verlet {verlet}, rk4 {rk4}");
+    // Verlet is second order: halving the step cuts the error about fourfold
+    assert!(max_error(IntegratorKind::Verlet, 1.0) > 3.0 * verlet);
+}
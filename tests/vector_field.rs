@@ -0,0 +1,26 @@
+use riscwaves::vector_field::VectorField;
+
+#[test]
+fn reads_an_evenly_spaced_grid() {
+    let field = VectorField::from_csv("x,y,u,v\n0,0,1,0\n10,0,1,0\n0,10,0,1\n10,10,0,1\n").unwrap();
+    assert_eq!(field.size(), (2, 2));
+}
+
+#[test]
+fn rejects_uneven_spacing() {
+    let csv = "0,0,1,0\n1,0,1,0\n5,0,1,0\n0,1,1,0\n1,1,1,0\n5,1,1,0\n";
+    assert!(VectorField::from_csv(csv).is_err());
+}
+
+#[test]
+fn rejects_non_finite_numbers() {
+    assert!(VectorField::from_csv("0,0,NaN,0\n1,0,1,0\n0,1,1,0\n1,1,1,0\n").is_err());
+    assert!(VectorField::from_csv("0,0,1,0\n1,0,1,0\n0,1,1,0\n1,1,inf,0\n").is_err());
+}
+
+#[test]
+fn rejects_grids_without_a_cell() {
+    assert!(VectorField::from_csv("").is_err());
+    assert!(VectorField::from_csv("x,y,u,v\n").is_err());
+    assert!(VectorField::from_csv("0,0,1,0\n1,0,1,0\n").is_err());
+}